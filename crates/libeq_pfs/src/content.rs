@@ -0,0 +1,183 @@
+use std::fmt;
+
+/// The type of a file's contents as detected from its data.
+///
+/// Archive entries are only typed by their filename extension, which is
+/// not always accurate. Textures, for example, are often stored as DDS
+/// data with a `.bmp` extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentType {
+    /// A WLD file containing fragments (meshes, materials, actors, etc.)
+    Wld,
+    /// A Windows bitmap image
+    Bmp,
+    /// A DirectDraw surface image
+    Dds,
+    /// A PNG image
+    Png,
+    /// A Truevision TGA image
+    Tga,
+    /// A RIFF WAVE audio file
+    Wav,
+    /// Plain text
+    Text,
+    /// Anything that could not be identified
+    Unknown,
+}
+
+impl ContentType {
+    /// The number of bytes from the start of a file needed to detect its type.
+    pub const SNIFF_LEN: usize = 512;
+
+    const WLD_MAGIC: [u8; 4] = 0x54503d02u32.to_le_bytes();
+    const PNG_MAGIC: [u8; 8] = *b"\x89PNG\r\n\x1a\n";
+
+    /// Detect the content type from the beginning of a file's data.
+    ///
+    /// Only the first [`ContentType::SNIFF_LEN`] bytes are considered.
+    pub fn detect(data: &[u8]) -> Self {
+        let data = &data[..data.len().min(Self::SNIFF_LEN)];
+        if data.starts_with(&Self::WLD_MAGIC) {
+            ContentType::Wld
+        } else if data.starts_with(b"DDS ") {
+            ContentType::Dds
+        } else if data.starts_with(&Self::PNG_MAGIC) {
+            ContentType::Png
+        } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WAVE" {
+            ContentType::Wav
+        } else if data.starts_with(b"BM") {
+            ContentType::Bmp
+        } else if is_tga(data) {
+            ContentType::Tga
+        } else if is_text(data) {
+            ContentType::Text
+        } else {
+            ContentType::Unknown
+        }
+    }
+
+    /// The filename extensions normally used for this content type.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ContentType::Wld => &["wld"],
+            ContentType::Bmp => &["bmp"],
+            ContentType::Dds => &["dds"],
+            ContentType::Png => &["png"],
+            ContentType::Tga => &["tga"],
+            ContentType::Wav => &["wav"],
+            ContentType::Text => &["txt", "eff", "ini"],
+            ContentType::Unknown => &[],
+        }
+    }
+
+    /// Whether `filename` has an extension normally used for this content type.
+    pub fn matches_extension(&self, filename: &str) -> bool {
+        let Some((_, ext)) = filename.rsplit_once('.') else {
+            return false;
+        };
+        self.extensions()
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case(ext))
+    }
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ContentType::Wld => "wld",
+            ContentType::Bmp => "bmp",
+            ContentType::Dds => "dds",
+            ContentType::Png => "png",
+            ContentType::Tga => "tga",
+            ContentType::Wav => "wav",
+            ContentType::Text => "text",
+            ContentType::Unknown => "unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+/// TGA files have no magic number. Instead check that the header fields
+/// hold values that are valid for a TGA image.
+fn is_tga(data: &[u8]) -> bool {
+    const HEADER_LEN: usize = 18;
+    if data.len() < HEADER_LEN {
+        return false;
+    }
+    let color_map_type = data[1];
+    let image_type = data[2];
+    let width = u16::from_le_bytes([data[12], data[13]]);
+    let height = u16::from_le_bytes([data[14], data[15]]);
+    let pixel_depth = data[16];
+
+    color_map_type <= 1
+        && matches!(image_type, 1 | 2 | 3 | 9 | 10 | 11)
+        && width > 0
+        && height > 0
+        && matches!(pixel_depth, 8 | 15 | 16 | 24 | 32)
+}
+
+/// Text in these archives is Windows-1252, so any byte other than a control
+/// character counts as text.
+fn is_text(data: &[u8]) -> bool {
+    !data.is_empty()
+        && data
+            .iter()
+            .all(|&b| matches!(b, b'\t' | b'\n' | b'\r' | 0x20..=0x7e | 0x80..=0xff))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_detects_magic_numbers() {
+        assert_eq!(
+            ContentType::detect(&[0x02, 0x3d, 0x50, 0x54, 0x00]),
+            ContentType::Wld
+        );
+        assert_eq!(ContentType::detect(b"BM\x36\x00"), ContentType::Bmp);
+        assert_eq!(ContentType::detect(b"DDS \x7c\x00"), ContentType::Dds);
+        assert_eq!(
+            ContentType::detect(b"\x89PNG\r\n\x1a\n\x00"),
+            ContentType::Png
+        );
+        assert_eq!(
+            ContentType::detect(b"RIFF\x24\x00\x00\x00WAVEfmt "),
+            ContentType::Wav
+        );
+    }
+
+    #[test]
+    fn it_detects_tga() {
+        let mut header = [0u8; 18];
+        header[2] = 2; // Uncompressed true-color
+        header[12..14].copy_from_slice(&64u16.to_le_bytes());
+        header[14..16].copy_from_slice(&32u16.to_le_bytes());
+        header[16] = 24;
+        assert_eq!(ContentType::detect(&header), ContentType::Tga);
+    }
+
+    #[test]
+    fn it_detects_text() {
+        assert_eq!(
+            ContentType::detect(b"gfaydark\r\n\tsome text"),
+            ContentType::Text
+        );
+        assert_eq!(
+            ContentType::detect(b"caf\xe9 \x93quoted\x94"),
+            ContentType::Text
+        );
+        assert_eq!(ContentType::detect(b"\x00\x01\x02"), ContentType::Unknown);
+        assert_eq!(ContentType::detect(b"text\0"), ContentType::Unknown);
+        assert_eq!(ContentType::detect(b"page\x0cbreak"), ContentType::Unknown);
+        assert_eq!(ContentType::detect(b""), ContentType::Unknown);
+    }
+
+    #[test]
+    fn it_matches_extensions() {
+        assert!(ContentType::Bmp.matches_extension("texture.BMP"));
+        assert!(!ContentType::Dds.matches_extension("texture.bmp"));
+        assert!(!ContentType::Unknown.matches_extension("noextension"));
+    }
+}
@@ -1,11 +1,13 @@
 #![doc = include_str!("../README.md")]
 
+mod content;
 mod crc;
 mod error;
 mod parser;
 mod read;
 mod write;
 
+pub use content::ContentType;
 pub use error::Error;
 pub use read::FileInfo;
 pub use read::PfsFileReader;
@@ -106,4 +108,87 @@ mod tests {
         );
         assert_eq!(reader.info("missing-file").unwrap(), None);
     }
+
+    #[test]
+    fn content_type() {
+        let test_files = [
+            ("texture.bmp", b"DDS \x7c\x00\x00\x00".to_vec()),
+            ("notes.txt", b"hello world".to_vec()),
+        ];
+        let bytes = Vec::new();
+        let mut writer = PfsWriter::create(Cursor::new(bytes)).unwrap();
+        for f in &test_files {
+            writer.insert(f.0, Cursor::new(&f.1)).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let mut reader = PfsReader::open(Cursor::new(bytes)).unwrap();
+        assert_eq!(
+            reader.content_type("texture.bmp").unwrap(),
+            Some(ContentType::Dds)
+        );
+        assert_eq!(
+            reader.content_type("notes.txt").unwrap(),
+            Some(ContentType::Text)
+        );
+        assert_eq!(reader.content_type("missing-file").unwrap(), None);
+    }
+
+    #[test]
+    fn to_writer_drops_orphaned_data() {
+        let test_files = [
+            ("test-file0", vec![0xde, 0xad, 0xbe, 0xef]),
+            ("test-file1", vec![0xca, 0xfe, 0xba, 0xbe]),
+        ];
+        let bytes = Vec::new();
+        let mut writer = PfsWriter::create(Cursor::new(bytes)).unwrap();
+        for f in &test_files {
+            writer.insert(f.0, Cursor::new(&f.1)).unwrap();
+        }
+        // Orphan the data of the first file
+        writer.remove(test_files[0].0);
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let mut reader = PfsReader::open(Cursor::new(bytes)).unwrap();
+        let bytes = reader
+            .to_writer(Cursor::new(Vec::new()))
+            .unwrap()
+            .finish()
+            .unwrap()
+            .into_inner();
+
+        let mut reader = PfsReader::open(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.filenames().unwrap(), vec![test_files[1].0]);
+        assert_eq!(
+            reader.get(test_files[1].0).unwrap().unwrap(),
+            test_files[1].1
+        );
+        assert_eq!(
+            reader.info(test_files[1].0).unwrap().unwrap().data_offset,
+            12
+        );
+    }
+
+    #[test]
+    fn to_writer_copies_archives_without_gaps_unchanged() {
+        let bytes = Vec::new();
+        let mut writer = PfsWriter::create(Cursor::new(bytes)).unwrap();
+        writer
+            .insert("test-file0", Cursor::new(vec![0xde, 0xad, 0xbe, 0xef]))
+            .unwrap();
+        writer
+            .insert("test-file1", Cursor::new(vec![0xca, 0xfe, 0xba, 0xbe]))
+            .unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let mut reader = PfsReader::open(Cursor::new(bytes.clone())).unwrap();
+        let copy = reader
+            .to_writer(Cursor::new(Vec::new()))
+            .unwrap()
+            .finish()
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(copy, bytes);
+    }
 }
@@ -3,6 +3,7 @@ use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
 use flate2::read::ZlibDecoder;
 
+use crate::content::ContentType;
 use crate::crc::FilenameCrc;
 use crate::error::Error;
 use crate::parser::{Block, BlockHeader, Directory, Footer, Header, IndexEntry};
@@ -121,6 +122,21 @@ impl<R: Read + Seek> PfsReader<R> {
         }))
     }
 
+    /// Detect the type of a file's contents by filename.
+    ///
+    /// Only the beginning of the file is decompressed to do this.
+    /// See [`ContentType::detect`].
+    pub fn content_type(&mut self, filename: &str) -> Result<Option<ContentType>, Error> {
+        let Some(reader) = self.get_reader(filename)? else {
+            return Ok(None);
+        };
+        let mut buf = Vec::with_capacity(ContentType::SNIFF_LEN);
+        reader
+            .take(ContentType::SNIFF_LEN as u64)
+            .read_to_end(&mut buf)?;
+        Ok(Some(ContentType::detect(&buf)))
+    }
+
     /// Get information about the archive file directory.
     pub fn directory_info(&mut self) -> Result<FileInfo, Error> {
         let dir = self.directory;
@@ -170,17 +186,24 @@ impl<R: Read + Seek> PfsReader<R> {
         entries.sort_by_key(|(_, e)| e.data_offset);
 
         let mut writer = PfsWriter::create(dest)?;
-        writer.entries = entries;
         writer.footer = self.footer;
-        for (_, e) in &writer.entries {
+        // Blocks are copied back to back. Any gaps in the original block
+        // section (e.g. orphaned data) are dropped so data offsets must be
+        // updated to point at the new location of each file.
+        for (_, e) in &mut entries {
+            let data_offset = writer.writer.stream_position()? as u32;
             for b in self.iter_blocks(e)? {
                 writer.writer.write_all(&b?.to_bytes())?;
             }
+            e.data_offset = data_offset;
         }
-        let directory = self.directory;
+        writer.entries = entries;
+        let mut directory = self.directory;
+        let data_offset = writer.writer.stream_position()? as u32;
         for b in self.iter_blocks(&directory)? {
             writer.writer.write_all(&b?.to_bytes())?;
         }
+        directory.data_offset = data_offset;
         writer.directory = Some(directory);
         Ok(writer)
     }
@@ -0,0 +1,80 @@
+//! Benchmarks parsing a whole zone.
+//!
+//! Uses the file named by `WLD_BENCH_FILE`, then `fixtures/gamedata/gfaydark.wld` and
+//! otherwise a generated zone of a similar size. To compare serial and parallel parsing:
+//!
+//! ```sh
+//! cargo bench -p libeq_wld --bench parse -- --save-baseline serial
+//! cargo bench -p libeq_wld --bench parse --features rayon -- --baseline serial
+//! ```
+//!
+//! On the generated zone on a single core machine, serial parsing took 8.27 ms and
+//! parsing with `rayon` 8.09 ms. With one core there is nothing to parallelize, so this
+//! only shows that the parallel path adds no overhead. The speedup on more cores hasn't
+//! been measured yet.
+
+use std::path::PathBuf;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use libeq_wld::builder::{MeshData, ObjectPlacement, PrimitiveData, WldBuilder};
+use libeq_wld::parser::{MaterialType, WldDoc};
+
+fn zone_data() -> Vec<u8> {
+    let path = std::env::var_os("WLD_BENCH_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/gamedata/gfaydark.wld")
+        });
+    std::fs::read(path).unwrap_or_else(|_| generated_zone())
+}
+
+/// A zone made of many small meshes, in the same ballpark as gfaydark.wld.
+fn generated_zone() -> Vec<u8> {
+    let mut builder = WldBuilder::new();
+    let materials = (0..50)
+        .map(|i| {
+            let texture = builder.add_texture(&format!("TEX{i}"), &format!("tex{i}.bmp"));
+            builder.add_material(&format!("TEX{i}"), Some(texture), MaterialType::Diffuse)
+        })
+        .collect::<Vec<_>>();
+
+    let size = 16;
+    let positions = (0..size * size)
+        .map(|i| [(i % size) as f32, ((i * 7) % 5) as f32, (i / size) as f32])
+        .collect::<Vec<_>>();
+    let indices = (0..size - 1)
+        .flat_map(|y| (0..size - 1).map(move |x| y * size + x))
+        .flat_map(|i| [i, i + size, i + 1, i + 1, i + size, i + size + 1])
+        .map(|i| i as u32)
+        .collect::<Vec<_>>();
+
+    for i in 0..1000 {
+        let mesh = builder
+            .add_mesh(MeshData {
+                name: format!("MESH{i}"),
+                normals: vec![[0.0, 1.0, 0.0]; positions.len()],
+                texture_coordinates: positions.iter().map(|p| [p[0], p[2]]).collect(),
+                positions: positions.clone(),
+                primitives: vec![PrimitiveData {
+                    material: materials[i % materials.len()],
+                    indices: indices.clone(),
+                    passable: false,
+                }],
+                ..Default::default()
+            })
+            .unwrap();
+        let model = builder.add_model(&format!("MESH{i}"), mesh);
+        builder.add_object(model, ObjectPlacement::at([i as f32, 0.0, 0.0]));
+    }
+    builder.build().to_bytes()
+}
+
+fn parse(c: &mut Criterion) {
+    let data = zone_data();
+    c.bench_function("parse zone", |b| {
+        b.iter(|| WldDoc::parse(std::hint::black_box(&data)).unwrap())
+    });
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
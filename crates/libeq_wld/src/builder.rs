@@ -0,0 +1,745 @@
+//! Authoring new wld files.
+//!
+//! [WldBuilder] takes textures, materials, meshes, models and object placements and
+//! writes out the chain of fragments the client expects for each of them:
+//!
+//! * textures: [BmInfo] → [SimpleSpriteDef] → [SimpleSprite]
+//! * materials: [MaterialDef]
+//! * meshes: [MaterialPalette] → [DmSpriteDef2] → [DmSprite]
+//! * models: [ActorDef]
+//! * object placements: [Actor]
+//!
+//! Meshes and placements use the same conventions as the loading api ([Mesh](crate::Mesh),
+//! [ObjectLocation](crate::ObjectLocation)) so a file built from them loads back the same
+//! values, up to the precision the file format stores them with.
+//!
+//! # Examples
+//! ```
+//! use libeq_wld::builder::{MeshData, ObjectPlacement, PrimitiveData, WldBuilder};
+//! use libeq_wld::parser::MaterialType;
+//!
+//! let mut builder = WldBuilder::new();
+//! let texture = builder.add_texture("CRATE", "crate.bmp");
+//! let material = builder.add_material("CRATE", Some(texture), MaterialType::Diffuse);
+//! let mesh = builder
+//!     .add_mesh(MeshData {
+//!         name: "CRATE".to_string(),
+//!         positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+//!         texture_coordinates: vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+//!         primitives: vec![PrimitiveData {
+//!             material,
+//!             indices: vec![0, 1, 2],
+//!             passable: false,
+//!         }],
+//!         ..Default::default()
+//!     })
+//!     .unwrap();
+//! let model = builder.add_model("CRATE", mesh);
+//! builder.add_object(model, ObjectPlacement::at([10.0, 0.0, 5.0]));
+//!
+//! let wld_doc = builder.build();
+//! assert!(wld_doc.validate().is_valid());
+//! let bytes = wld_doc.to_bytes();
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::parser::{
+    Action, Actor, ActorDef, ActorDefFlags, ActorInstFlags, BmInfo, DmSprite, DmSpriteDef2,
+    DmSpriteDef2FaceEntry, EncodedFilename, EncodedString, FragmentRef, FragmentType, Location,
+    MaterialDef, MaterialFlags, MaterialPalette, MaterialType, RenderMethod, SimpleSprite,
+    SimpleSpriteDef, SimpleSpriteDefFlags, WldDoc,
+};
+
+/// A texture added with [WldBuilder::add_texture]. Refers to its [SimpleSprite].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureId(usize);
+
+/// A material added with [WldBuilder::add_material]. Refers to its [MaterialDef].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MaterialId(usize);
+
+/// A mesh added with [WldBuilder::add_mesh]. Refers to its [DmSprite].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshId(usize);
+
+/// A model added with [WldBuilder::add_model]. Refers to its [ActorDef].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelId(usize);
+
+/// A mesh to be added with [WldBuilder::add_mesh].
+///
+/// Coordinates are y-up, like those returned by [Mesh](crate::Mesh). `positions` are
+/// relative to `center`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeshData {
+    /// Used as the base of the fragment names, e.g. `NAME_DMSPRITEDEF`.
+    pub name: String,
+    pub center: [f32; 3],
+    pub positions: Vec<[f32; 3]>,
+    /// Either empty or one per position.
+    pub normals: Vec<[f32; 3]>,
+    /// Either empty or one per position.
+    pub texture_coordinates: Vec<[f32; 2]>,
+    pub primitives: Vec<PrimitiveData>,
+}
+
+/// Faces of a [MeshData] that share a material.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrimitiveData {
+    pub material: MaterialId,
+    /// Indices into the positions of the mesh, three per triangle.
+    pub indices: Vec<u32>,
+    /// Whether players can pass through these faces (e.g. water and tree leaves).
+    pub passable: bool,
+}
+
+/// Where to place an object, see [WldBuilder::add_object].
+///
+/// These are the same values returned by [ObjectLocation](crate::ObjectLocation).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObjectPlacement {
+    pub position: [f32; 3],
+    /// Euler rotation in degrees, -360 to 0.
+    pub rotation: [f32; 3],
+    pub scale: f32,
+}
+
+impl ObjectPlacement {
+    /// An unrotated, unscaled object at `position`.
+    pub fn at(position: [f32; 3]) -> Self {
+        Self {
+            position,
+            rotation: [0.0; 3],
+            scale: 1.0,
+        }
+    }
+}
+
+/// A mesh that can't be stored in a [DmSpriteDef2].
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    /// There are more vertices or faces than fit in the 16 bit counts of the fragment.
+    TooLarge {
+        mesh: String,
+        field: &'static str,
+        len: usize,
+    },
+    /// There are normals or texture coordinates but not one per position.
+    AttributeCount {
+        mesh: String,
+        field: &'static str,
+        len: usize,
+        position_count: usize,
+    },
+    /// The indices of a primitive don't make up whole triangles.
+    PartialFace {
+        mesh: String,
+        primitive: usize,
+        index_count: usize,
+    },
+    /// A primitive refers to a position the mesh doesn't have.
+    VertexOutOfRange {
+        mesh: String,
+        primitive: usize,
+        vertex: u32,
+        vertex_count: usize,
+    },
+    /// A position is too far from the center of the mesh to be stored.
+    PositionOutOfRange { mesh: String, position: [f32; 3] },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLarge { mesh, field, len } => write!(
+                f,
+                "mesh {}: {} has {} entries but at most {} can be stored",
+                mesh,
+                field,
+                len,
+                u16::MAX
+            ),
+            Self::AttributeCount {
+                mesh,
+                field,
+                len,
+                position_count,
+            } => write!(
+                f,
+                "mesh {}: {} has {} entries but there are {} positions",
+                mesh, field, len, position_count
+            ),
+            Self::PartialFace {
+                mesh,
+                primitive,
+                index_count,
+            } => write!(
+                f,
+                "mesh {}: primitive {} has {} indices which is not a multiple of 3",
+                mesh, primitive, index_count
+            ),
+            Self::VertexOutOfRange {
+                mesh,
+                primitive,
+                vertex,
+                vertex_count,
+            } => write!(
+                f,
+                "mesh {}: primitive {} contains vertex {} but there are {} vertices",
+                mesh, primitive, vertex, vertex_count
+            ),
+            Self::PositionOutOfRange { mesh, position } => write!(
+                f,
+                "mesh {}: position {:?} is too far from the center of the mesh",
+                mesh, position
+            ),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+/// Builds a new [WldDoc] one texture, material, mesh, model and object at a time.
+///
+/// Fragments are added to the document in the order they are built, so everything
+/// a fragment refers to comes before it. See the [module docs](self) for an example.
+#[derive(Debug, Default)]
+pub struct WldBuilder {
+    doc: WldDoc,
+}
+
+impl WldBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a texture using a single image file.
+    pub fn add_texture(&mut self, name: &str, file_name: &str) -> TextureId {
+        self.add_texture_frames(name, &[file_name], None)
+    }
+
+    /// Add a texture that cycles through `file_names`, showing each for `sleep`
+    /// milliseconds.
+    pub fn add_animated_texture(
+        &mut self,
+        name: &str,
+        file_names: &[&str],
+        sleep: u32,
+    ) -> TextureId {
+        self.add_texture_frames(name, file_names, Some(sleep))
+    }
+
+    fn add_texture_frames(
+        &mut self,
+        name: &str,
+        file_names: &[&str],
+        sleep: Option<u32>,
+    ) -> TextureId {
+        let unnamed = self.doc.intern_string("");
+        let frame_references = file_names
+            .iter()
+            .map(|file_name| {
+                // Filenames are stored in upper case in the game files.
+                let idx = self.add(FragmentType::BmInfo(BmInfo {
+                    name_reference: unnamed,
+                    entry_count: 0,
+                    entries: vec![EncodedFilename::new(&file_name.to_uppercase())],
+                }));
+                FragmentRef::new(index_ref(idx))
+            })
+            .collect();
+
+        let name_reference = self.doc.intern_string(&format!("{}_SPRITE", name));
+        let sprite_def = self.add(FragmentType::SimpleSpriteDef(SimpleSpriteDef {
+            name_reference,
+            // IS_ANIMATED | HAS_SLEEP
+            flags: SimpleSpriteDefFlags::new(if sleep.is_some() { 0x18 } else { 0 }),
+            frame_count: 0,
+            current_frame: None,
+            sleep,
+            frame_references,
+        }));
+
+        TextureId(self.add(FragmentType::SimpleSprite(SimpleSprite {
+            name_reference: unnamed,
+            reference: FragmentRef::new(index_ref(sprite_def)),
+            flags: 0x50,
+        })))
+    }
+
+    /// Add a material, optionally textured.
+    pub fn add_material(
+        &mut self,
+        name: &str,
+        texture: Option<TextureId>,
+        material_type: MaterialType,
+    ) -> MaterialId {
+        let name_reference = self.doc.intern_string(&format!("{}_MDF", name));
+        MaterialId(self.add(FragmentType::MaterialDef(MaterialDef {
+            name_reference,
+            flags: MaterialFlags::new(0),
+            render_method: RenderMethod::UserDefined { material_type },
+            rgb_pen: 0x00b2b2b2,
+            brightness: 0.0,
+            scaled_ambient: 0.75,
+            reference: FragmentRef::new(texture.map_or(0, |t| index_ref(t.0))),
+            pair: None,
+        })))
+    }
+
+    /// Add a mesh along with a [MaterialPalette] of the materials its primitives use.
+    ///
+    /// Positions are stored as 16 bit fixed point numbers, using as many fractional
+    /// bits as the largest coordinate allows. Normals are stored with 8 bits and
+    /// texture coordinates with 8 fractional bits.
+    pub fn add_mesh(&mut self, mesh: MeshData) -> Result<MeshId, BuildError> {
+        let position_count = mesh.positions.len();
+        check_len(&mesh.name, "positions", position_count)?;
+        for (field, len) in [
+            ("normals", mesh.normals.len()),
+            ("texture_coordinates", mesh.texture_coordinates.len()),
+        ] {
+            if len != 0 && len != position_count {
+                return Err(BuildError::AttributeCount {
+                    mesh: mesh.name.clone(),
+                    field,
+                    len,
+                    position_count,
+                });
+            }
+        }
+
+        let mut materials = Vec::new();
+        let mut palette_indices = HashMap::new();
+        let mut faces = Vec::new();
+        let mut face_material_groups = Vec::new();
+        for (primitive, data) in mesh.primitives.iter().enumerate() {
+            if data.indices.len() % 3 != 0 {
+                return Err(BuildError::PartialFace {
+                    mesh: mesh.name.clone(),
+                    primitive,
+                    index_count: data.indices.len(),
+                });
+            }
+            if let Some(&vertex) = data.indices.iter().find(|&&v| v as usize >= position_count) {
+                return Err(BuildError::VertexOutOfRange {
+                    mesh: mesh.name.clone(),
+                    primitive,
+                    vertex,
+                    vertex_count: position_count,
+                });
+            }
+            check_len(&mesh.name, "faces", faces.len() + data.indices.len() / 3)?;
+
+            let palette_index = *palette_indices.entry(data.material).or_insert_with(|| {
+                materials.push(FragmentRef::new(index_ref(data.material.0)));
+                materials.len() - 1
+            });
+            face_material_groups.push(((data.indices.len() / 3) as u16, palette_index as u16));
+            faces.extend(data.indices.chunks(3).map(|face| DmSpriteDef2FaceEntry {
+                flags: if data.passable { 0x0010 } else { 0 },
+                vertex_indexes: (face[0] as u16, face[1] as u16, face[2] as u16),
+            }));
+        }
+
+        // Mesh coordinates are y-up but the file stores them z-up.
+        let largest = mesh
+            .positions
+            .iter()
+            .flatten()
+            .fold(0.0f32, |largest, v| largest.max(v.abs()));
+        let scale = (0..=15u16)
+            .rev()
+            .find(|scale| (largest * (1 << scale) as f32).round() <= i16::MAX as f32)
+            .ok_or_else(|| BuildError::PositionOutOfRange {
+                mesh: mesh.name.clone(),
+                position: *mesh
+                    .positions
+                    .iter()
+                    .find(|p| p.iter().any(|v| v.abs() == largest))
+                    .expect("largest coordinate comes from a position"),
+            })?;
+        let factor = (1 << scale) as f32;
+        let positions = mesh
+            .positions
+            .iter()
+            .map(|p| {
+                (
+                    (p[0] * factor).round() as i16,
+                    (p[2] * factor).round() as i16,
+                    (p[1] * factor).round() as i16,
+                )
+            })
+            .collect();
+        let vertex_normals = mesh
+            .normals
+            .iter()
+            .map(|n| {
+                (
+                    (n[0] * 127.0).round() as i8,
+                    (n[2] * 127.0).round() as i8,
+                    (n[1] * 127.0).round() as i8,
+                )
+            })
+            .collect();
+        let texture_coordinates = mesh
+            .texture_coordinates
+            .iter()
+            .map(|uv| {
+                // Saturate to what the old format can store.
+                (
+                    ((uv[0] * 256.0).round() as i16).into(),
+                    ((uv[1] * 256.0).round() as i16).into(),
+                )
+            })
+            .collect();
+
+        let center = (mesh.center[0], mesh.center[2], mesh.center[1]);
+        let (mut min, mut max) = ([f32::MAX; 3], [f32::MIN; 3]);
+        for p in &mesh.positions {
+            for (axis, v) in [p[0] + center.0, p[2] + center.1, p[1] + center.2]
+                .into_iter()
+                .enumerate()
+            {
+                min[axis] = min[axis].min(v);
+                max[axis] = max[axis].max(v);
+            }
+        }
+        if mesh.positions.is_empty() {
+            (min, max) = (
+                [center.0, center.1, center.2],
+                [center.0, center.1, center.2],
+            );
+        }
+        let max_distance = mesh
+            .positions
+            .iter()
+            .map(|p| (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt())
+            .fold(0.0, f32::max);
+
+        let palette_name = self.doc.intern_string(&format!("{}_MP", mesh.name));
+        let palette = self.add(FragmentType::MaterialPalette(MaterialPalette {
+            name_reference: palette_name,
+            flags: 0,
+            size1: 0,
+            fragments: materials,
+        }));
+
+        let name_reference = self
+            .doc
+            .intern_string(&format!("{}_DMSPRITEDEF", mesh.name));
+        let mesh_def = self.add(FragmentType::DmSpriteDef2(DmSpriteDef2 {
+            name_reference,
+            // The value observed for placeable objects.
+            flags: 0x00014003,
+            material_list_ref: FragmentRef::new(index_ref(palette)),
+            animation_ref: FragmentRef::new(0),
+            fragment3: FragmentRef::new(0),
+            fragment4: FragmentRef::new(0),
+            center,
+            params2: (0, 0, 0),
+            max_distance,
+            min: (min[0], min[1], min[2]),
+            max: (max[0], max[1], max[2]),
+            position_count: 0,
+            texture_coordinate_count: 0,
+            normal_count: 0,
+            color_count: 0,
+            face_count: 0,
+            skin_assignment_groups_count: 0,
+            face_material_groups_count: 0,
+            vertex_material_groups_count: 0,
+            meshop_count: 0,
+            scale,
+            positions,
+            texture_coordinates,
+            vertex_normals,
+            vertex_colors: Vec::new(),
+            faces,
+            skin_assignment_groups: Vec::new(),
+            face_material_groups,
+            vertex_material_groups: Vec::new(),
+            meshops: Vec::new(),
+        }));
+
+        let unnamed = self.doc.intern_string("");
+        Ok(MeshId(self.add(FragmentType::DmSprite(DmSprite {
+            name_reference: unnamed,
+            reference: FragmentRef::new(index_ref(mesh_def)),
+            params: 0,
+        }))))
+    }
+
+    /// Add a model made of a single static mesh. Its name will be `NAME_ACTORDEF`.
+    pub fn add_model(&mut self, name: &str, mesh: MeshId) -> ModelId {
+        let name_reference = self.doc.intern_string(&format!("{}_ACTORDEF", name));
+        let callback_name_reference = self.doc.intern_string("SPRITECALLBACK");
+        ModelId(self.add(FragmentType::ActorDef(ActorDef {
+            name_reference,
+            flags: ActorDefFlags::new(0),
+            callback_name_reference,
+            action_count: 0,
+            fragment_reference_count: 0,
+            bounds_reference: 0,
+            current_action: None,
+            location: None,
+            actions: vec![Action {
+                levels_of_detail_count: 1,
+                unknown: 0,
+                levels_of_detail_distances: vec![1e30],
+            }],
+            fragment_references: vec![FragmentRef::new(index_ref(mesh.0))],
+            unknown: 0,
+        })))
+    }
+
+    /// Place an instance of a model from this document.
+    pub fn add_object(&mut self, model: ModelId, placement: ObjectPlacement) {
+        self.add_actor(FragmentRef::new(index_ref(model.0)), placement);
+    }
+
+    /// Place an instance of a model by its name, e.g. `CRATE_ACTORDEF`. Zones keep their
+    /// models and placements in separate files, which requires referring to models by name.
+    pub fn add_object_by_name(&mut self, model_name: &str, placement: ObjectPlacement) {
+        let name_reference = self.doc.intern_string(model_name);
+        self.add_actor(FragmentRef::new(name_reference.0), placement);
+    }
+
+    fn add_actor(
+        &mut self,
+        actor_def_reference: FragmentRef<ActorDef>,
+        placement: ObjectPlacement,
+    ) {
+        let unnamed = self.doc.intern_string("");
+        // Rotations are stored as fractions of 512 and coordinates are z-up.
+        let rotation = placement.rotation.map(|degrees| degrees / -360.0 * 512.0);
+        self.add(FragmentType::Actor(Actor {
+            name_reference: unnamed,
+            actor_def_reference,
+            // ACTIVE
+            flags: ActorInstFlags::new(0x20),
+            sphere_reference: FragmentRef::new(0),
+            current_action: None,
+            location: Some(Location {
+                x: placement.position[0],
+                y: placement.position[2],
+                z: placement.position[1],
+                rotate_z: rotation[1],
+                rotate_y: rotation[0],
+                rotate_x: rotation[2],
+                unknown: 0,
+            }),
+            bounding_radius: Some(placement.scale),
+            scale_factor: Some(placement.scale),
+            sound_name_reference: None,
+            vertex_color_reference: None,
+            user_data_size: 0,
+            user_data: EncodedString::default(),
+        }));
+    }
+
+    /// Finish the document, filling in fragment counts, flags and the header.
+    pub fn build(mut self) -> WldDoc {
+        self.doc.normalize();
+        self.doc
+    }
+
+    fn add(&mut self, fragment: FragmentType) -> usize {
+        self.doc.add_fragment(fragment)
+    }
+}
+
+/// The reference to the fragment at position `idx`.
+fn index_ref(idx: usize) -> i32 {
+    idx as i32 + 1
+}
+
+fn check_len(mesh: &str, field: &'static str, len: usize) -> Result<(), BuildError> {
+    if len > u16::MAX as usize {
+        return Err(BuildError::TooLarge {
+            mesh: mesh.to_string(),
+            field,
+            len,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ValidationIssue;
+
+    fn triangle(material: MaterialId) -> MeshData {
+        MeshData {
+            name: "TRIANGLE".to_string(),
+            center: [100.0, 20.0, -50.0],
+            positions: vec![[0.0, 0.0, 0.0], [1.5, 0.0, 0.0], [0.0, -2.25, 0.5]],
+            normals: vec![[0.0, 1.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, -1.0]],
+            texture_coordinates: vec![[0.0, 0.0], [1.0, 0.0], [0.5, 1.0]],
+            primitives: vec![PrimitiveData {
+                material,
+                indices: vec![0, 1, 2],
+                passable: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn it_builds_loadable_documents() {
+        let mut builder = WldBuilder::new();
+        let texture = builder.add_texture("BRICK", "brick.bmp");
+        let water = builder.add_animated_texture("WATER", &["water1.bmp", "water2.bmp"], 100);
+        let brick = builder.add_material("BRICK", Some(texture), MaterialType::Diffuse);
+        let water = builder.add_material("WATER", Some(water), MaterialType::Transparent50);
+        let mut mesh = triangle(brick);
+        mesh.positions.push([0.0, 0.0, 1.0]);
+        mesh.normals.push([0.0, 1.0, 0.0]);
+        mesh.texture_coordinates.push([0.0, 1.0]);
+        mesh.primitives.push(PrimitiveData {
+            material: water,
+            indices: vec![0, 2, 3],
+            passable: true,
+        });
+        let mesh = builder.add_mesh(mesh).unwrap();
+        let model = builder.add_model("TRIANGLE", mesh);
+        let placement = ObjectPlacement {
+            position: [1.0, 2.0, 3.0],
+            rotation: [-90.0, -180.0, 0.0],
+            scale: 0.5,
+        };
+        builder.add_object(model, placement);
+        builder.add_object_by_name("TRIANGLE_ACTORDEF", ObjectPlacement::at([0.0; 3]));
+
+        let wld_doc = builder.build();
+        let report = wld_doc.validate();
+        assert!(report.is_valid(), "{}", report);
+        assert_eq!(wld_doc.fragment_count(), 15);
+
+        let wld = crate::load(&wld_doc.to_bytes()).unwrap();
+
+        let meshes = wld.meshes().collect::<Vec<_>>();
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].name(), Some("TRIANGLE_DMSPRITEDEF"));
+        assert_eq!(meshes[0].center(), (100.0, 20.0, -50.0));
+        assert_eq!(
+            meshes[0].positions(),
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.5, 0.0, 0.0],
+                [0.0, -2.25, 0.5],
+                [0.0, 0.0, 1.0]
+            ]
+        );
+        assert_eq!(meshes[0].normals()[2], [0.0, 0.0, -1.0]);
+        assert_eq!(meshes[0].texture_coordinates()[2], [0.5, 1.0]);
+        assert_eq!(meshes[0].indices(), vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(meshes[0].collision_indices(), vec![0, 1, 2]);
+
+        let primitives = meshes[0].primitives();
+        assert_eq!(primitives.len(), 2);
+        assert_eq!(primitives[1].indices(), vec![0, 2, 3]);
+        let material = primitives[1].material().unwrap();
+        assert_eq!(material.name(), Some("WATER_MDF"));
+        let texture = material.base_color_texture().unwrap();
+        assert_eq!(texture.name(), Some("WATER_SPRITE"));
+        assert!(texture.flags().is_animated());
+        assert_eq!(
+            texture.iter_sources().collect::<Vec<_>>(),
+            vec!["water1.bmp", "water2.bmp"]
+        );
+
+        let models = wld.models().collect::<Vec<_>>();
+        assert_eq!(models[0].name(), Some("TRIANGLE_ACTORDEF"));
+        assert_eq!(models[0].type_name(), Some("SPRITECALLBACK"));
+        assert_eq!(
+            models[0].mesh().unwrap().name(),
+            Some("TRIANGLE_DMSPRITEDEF")
+        );
+
+        let objects = wld.objects().collect::<Vec<_>>();
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0].model_name(), Some("TRIANGLE_ACTORDEF"));
+        assert_eq!(objects[0].center(), (1.0, 2.0, 3.0));
+        assert_eq!(objects[0].rotation(), (-90.0, -180.0, 0.0));
+        assert_eq!(objects[0].scale(), (0.5, 0.5));
+        assert_eq!(objects[1].model_name(), Some("TRIANGLE_ACTORDEF"));
+    }
+
+    #[test]
+    fn it_places_objects_from_other_files() {
+        let mut builder = WldBuilder::new();
+        builder.add_object_by_name("CRATE_ACTORDEF", ObjectPlacement::at([0.0; 3]));
+        let report = builder.build().validate();
+
+        assert!(report.is_valid(), "{}", report);
+        assert_eq!(
+            report.issues,
+            [ValidationIssue::UnresolvedName {
+                index: 0,
+                field: "actor_def_reference",
+                name: "CRATE_ACTORDEF".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn it_shares_palette_entries_between_primitives() {
+        let mut builder = WldBuilder::new();
+        let material = builder.add_material("PLAIN", None, MaterialType::Diffuse);
+        let mut mesh = triangle(material);
+        mesh.primitives.push(mesh.primitives[0].clone());
+        builder.add_mesh(mesh).unwrap();
+        let wld_doc = builder.build();
+
+        let palette = wld_doc.fragment_iter::<MaterialPalette>().next().unwrap();
+        assert_eq!(palette.size1, 1);
+        let mesh = wld_doc.fragment_iter::<DmSpriteDef2>().next().unwrap();
+        assert_eq!(mesh.face_material_groups, vec![(1, 0), (1, 0)]);
+    }
+
+    #[test]
+    fn it_rejects_invalid_meshes() {
+        let mut builder = WldBuilder::new();
+        let material = builder.add_material("PLAIN", None, MaterialType::Diffuse);
+
+        let mut mesh = triangle(material);
+        mesh.normals.pop();
+        assert_eq!(
+            builder.add_mesh(mesh),
+            Err(BuildError::AttributeCount {
+                mesh: "TRIANGLE".to_string(),
+                field: "normals",
+                len: 2,
+                position_count: 3,
+            })
+        );
+
+        let mut mesh = triangle(material);
+        mesh.primitives[0].indices.push(0);
+        assert!(matches!(
+            builder.add_mesh(mesh),
+            Err(BuildError::PartialFace { index_count: 4, .. })
+        ));
+
+        let mut mesh = triangle(material);
+        mesh.primitives[0].indices[2] = 3;
+        assert!(matches!(
+            builder.add_mesh(mesh),
+            Err(BuildError::VertexOutOfRange { vertex: 3, .. })
+        ));
+
+        let mut mesh = triangle(material);
+        mesh.positions[1] = [40000.0, 0.0, 0.0];
+        assert!(matches!(
+            builder.add_mesh(mesh),
+            Err(BuildError::PositionOutOfRange { .. })
+        ));
+
+        // Nothing is added for rejected meshes.
+        assert_eq!(builder.build().fragment_count(), 1);
+    }
+}
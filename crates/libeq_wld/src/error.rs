@@ -0,0 +1,44 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::parser::WldDocError;
+
+/// An error encountered while loading a wld file.
+///
+/// This holds every error found while parsing the file, not just the first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WldError {
+    errors: Vec<WldDocError>,
+}
+
+impl WldError {
+    pub(crate) fn new(errors: Vec<WldDocError>) -> Self {
+        Self { errors }
+    }
+
+    /// All of the errors that were found.
+    pub fn errors(&self) -> &[WldDocError] {
+        &self.errors
+    }
+}
+
+impl fmt::Display for WldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.errors.as_slice() {
+            [] => write!(f, "failed to parse wld"),
+            [error] => write!(f, "failed to parse wld: {}", error),
+            [first, rest @ ..] => write!(
+                f,
+                "failed to parse wld: {} (and {} more error(s))",
+                first,
+                rest.len()
+            ),
+        }
+    }
+}
+
+impl Error for WldError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.errors.first().map(|e| e as &(dyn Error + 'static))
+    }
+}
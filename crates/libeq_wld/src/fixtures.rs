@@ -0,0 +1,1389 @@
+//! Small synthetic wld files for tests that can't depend on game data.
+//!
+//! The files are written with the crate's own serializers. [wld_doc] holds at least one
+//! fragment of every [FragmentType] the given game's files can hold and each fragment
+//! with optional fields is added twice: once with every field its flags can enable and
+//! once with none of them. The fragments refer to each other the way they do in game
+//! files, so the document also loads with the high level api ([Wld](crate::Wld)).
+//!
+//! [FragmentType::Raw] is the exception as it only comes from fragments that fail to
+//! parse. Add a [RawFragment](crate::parser::RawFragment) and read the file with
+//! [WldDoc::parse_lenient] to cover it.
+//!
+//! Enabled for the crate's own tests and by the `fixtures` feature.
+//!
+//! # Examples
+//! ```
+//! use libeq_wld::fixtures;
+//! use libeq_wld::parser::{FragmentGame, WldDoc};
+//!
+//! let data = fixtures::wld(FragmentGame::EverQuest);
+//! let wld_doc = WldDoc::parse(&data).unwrap();
+//! assert!(wld_doc.validate().is_valid());
+//! ```
+
+use crate::parser::{
+    Action, Actor, ActorDef, ActorDefFlags, ActorInstFlags, AmbientLight, BlitSprite,
+    BlitSpriteDef, BlitSpriteDefFlags, BmInfo, BmInfoRtk, BspNodeEntry, Dag, DefaultPaletteFile,
+    DirectionalLight, DirectionalLightFlags, DmRGBTrack, DmRGBTrackDef, DmSprite, DmSpriteDef,
+    DmSpriteDef2, DmSpriteDef2FaceEntry, DmSpriteDef2MeshOpEntry, DmSpriteDefFaceEntry,
+    DmSpriteDefMeshopEntry, DmTrack, DmTrackDef, DmTrackDef2, DrawStyle, EncodedFilename,
+    FragmentGame, FragmentRef, FragmentType, FrameTransform, GlobalAmbientLightDef,
+    HierarchicalSprite, HierarchicalSpriteDef, HierarchicalSpriteDefFlags, LegacyFrameTransform,
+    Light, LightDef, LightDefFlags, Lighting, Location, MaterialDef, MaterialFlags,
+    MaterialPalette, MaterialType, Obstacle, ObstacleFlags, ObstacleType, ParticleCloudDef,
+    ParticleMovement, ParticleSprite, ParticleSpriteDef, ParticleSpriteDefFlags, PointLight,
+    PointLightFlags, Polyhedron, PolyhedronDef, PolyhedronFlags, RangeEntry, Region, RegionFlags,
+    RenderInfo, RenderInfoFlags, RenderMethod, Shading, SimpleSprite, SimpleSpriteDef,
+    SimpleSpriteDefFlags, Sphere, SphereList, SphereListDef, SphereListDefFlags, Sprite2D,
+    Sprite2DDef, Sprite3D, Sprite3DDef, Sprite4D, Sprite4DDef, Sprite4DDefFlags, SpriteFlags,
+    SpriteHeading, SpritePitch, StringReference, TextureStyle, ThreeDSpriteFlags, Track, TrackDef,
+    TrackInstanceFlags, UvInfo, UvMap, VisNode, VisibleList, Wall, WallFlags, WldDoc, WorldNode,
+    WorldTree, WorldVertices, Zone,
+};
+
+/// A document with fragments of every type that files from `game` can hold, see the
+/// [module documentation](self).
+///
+/// The 0x2c fragments are [DmSpriteDef]s, [WorldVertices] or [BmInfoRtk]s depending on
+/// `game`, and are laid out so that [WldDoc::parse] detects the game from them.
+pub fn wld_doc(game: FragmentGame) -> WldDoc {
+    let mut fixture = Fixture::default();
+
+    let materials = fixture.add_materials();
+    let meshes = fixture.add_meshes(materials);
+    let skeleton = fixture.add_skeleton(meshes);
+    fixture.add_models(meshes, skeleton);
+    let light = fixture.add_lights();
+    fixture.add_regions(materials, meshes, light);
+    fixture.add_sprites();
+    fixture.add_particles(materials);
+    match game {
+        FragmentGame::EverQuest => fixture.add_legacy_meshes(materials),
+        FragmentGame::Tanarus => fixture.add_world_vertices(),
+        FragmentGame::ReturnToKrondor => fixture.add_rtk_bitmaps(),
+    }
+    fixture.add_zones();
+
+    fixture.doc.normalize();
+    fixture.doc
+}
+
+/// [wld_doc] as it is written to a file.
+pub fn wld(game: FragmentGame) -> Vec<u8> {
+    wld_doc(game).to_bytes()
+}
+
+/// References to the [MaterialDef]s and their [MaterialPalette].
+#[derive(Clone, Copy)]
+struct Materials {
+    palette: i32,
+    sprite: i32,
+}
+
+/// References to the [DmSpriteDef2]s and the [DmSprite]s that place them.
+#[derive(Clone, Copy)]
+struct Meshes {
+    mesh: i32,
+    sprite: i32,
+    animated_sprite: i32,
+}
+
+#[derive(Default)]
+struct Fixture {
+    doc: WldDoc,
+}
+
+impl Fixture {
+    /// Add `fragment`, returning a reference to it.
+    fn add(&mut self, fragment: FragmentType) -> i32 {
+        self.doc.add_fragment(fragment) as i32 + 1
+    }
+
+    fn name(&mut self, name: &str) -> StringReference {
+        self.doc.intern_string(name)
+    }
+
+    /// Two textures, a still one and an animated one, and a material for each.
+    fn add_materials(&mut self) -> Materials {
+        let name_reference = self.name("DEFAULT.PAL");
+        self.add(FragmentType::DefaultPaletteFile(DefaultPaletteFile {
+            name_reference,
+            entry: EncodedFilename::new("default.pal"),
+        }));
+
+        let mut bitmaps = Vec::new();
+        for (name, file_name) in [
+            ("CRATE.BMP", "crate.bmp"),
+            ("WATER1.BMP", "water1.bmp"),
+            ("WATER2.BMP", "water2.bmp"),
+        ] {
+            let name_reference = self.name(name);
+            bitmaps.push(self.add(FragmentType::BmInfo(BmInfo {
+                name_reference,
+                entry_count: 0,
+                entries: vec![EncodedFilename::new(file_name)],
+            })));
+        }
+
+        let name_reference = self.name("CRATE_SPRITE");
+        let crate_sprite_def = self.add(FragmentType::SimpleSpriteDef(SimpleSpriteDef {
+            name_reference,
+            flags: SimpleSpriteDefFlags::new(0),
+            frame_count: 0,
+            current_frame: None,
+            sleep: None,
+            frame_references: vec![FragmentRef::new(bitmaps[0])],
+        }));
+        let name_reference = self.name("WATER_SPRITE");
+        // IS_ANIMATED | HAS_SLEEP | HAS_CURRENT_FRAME
+        let water_sprite_def = self.add(FragmentType::SimpleSpriteDef(SimpleSpriteDef {
+            name_reference,
+            flags: SimpleSpriteDefFlags::new(0x38),
+            frame_count: 0,
+            current_frame: Some(1),
+            sleep: Some(100),
+            frame_references: vec![FragmentRef::new(bitmaps[1]), FragmentRef::new(bitmaps[2])],
+        }));
+
+        let unnamed = self.name("");
+        let crate_sprite = self.add(FragmentType::SimpleSprite(SimpleSprite {
+            name_reference: unnamed,
+            reference: FragmentRef::new(crate_sprite_def),
+            flags: 0x50,
+        }));
+        let water_sprite = self.add(FragmentType::SimpleSprite(SimpleSprite {
+            name_reference: unnamed,
+            reference: FragmentRef::new(water_sprite_def),
+            flags: 0x50,
+        }));
+
+        let name_reference = self.name("CRATE_MDF");
+        let crate_material = self.add(FragmentType::MaterialDef(MaterialDef {
+            name_reference,
+            flags: MaterialFlags::new(0x01),
+            render_method: RenderMethod::UserDefined {
+                material_type: MaterialType::Diffuse,
+            },
+            rgb_pen: 0x00b2b2b2,
+            brightness: 0.0,
+            scaled_ambient: 0.75,
+            reference: FragmentRef::new(crate_sprite),
+            pair: None,
+        }));
+        let name_reference = self.name("WATER_MDF");
+        // HAS_PAIR
+        let water_material = self.add(FragmentType::MaterialDef(MaterialDef {
+            name_reference,
+            flags: MaterialFlags::new(0x02),
+            render_method: RenderMethod::Standard {
+                draw_style: DrawStyle::Solid,
+                lighting: Lighting::Ambient,
+                shading: Shading::Gouraud1,
+                texture_style: TextureStyle::TransTexture1,
+                unknown_bits: 0,
+            },
+            rgb_pen: 0xff000000,
+            brightness: 0.5,
+            scaled_ambient: 1.0,
+            reference: FragmentRef::new(water_sprite),
+            pair: Some((0, 1.0)),
+        }));
+
+        let name_reference = self.name("CRATE_MP");
+        let palette = self.add(FragmentType::MaterialPalette(MaterialPalette {
+            name_reference,
+            flags: 0,
+            size1: 0,
+            fragments: vec![
+                FragmentRef::new(crate_material),
+                FragmentRef::new(water_material),
+            ],
+        }));
+
+        Materials {
+            palette,
+            sprite: crate_sprite,
+        }
+    }
+
+    /// A static mesh with a primitive per material and an animated one.
+    fn add_meshes(&mut self, materials: Materials) -> Meshes {
+        let name_reference = self.name("CRATE_DMTRACKDEF");
+        let track_def = self.add(FragmentType::DmTrackDef2(DmTrackDef2 {
+            name_reference,
+            flags: 0,
+            vertex_count: 3,
+            frame_count: 0,
+            param1: 100,
+            param2: 0,
+            scale: 8,
+            frames: vec![
+                vec![(0, 0, 0), (256, 0, 0), (0, 0, 256)],
+                vec![(0, 0, 0), (512, 0, 0), (0, 0, 512)],
+            ],
+            size6: 0,
+        }));
+        let unnamed = self.name("");
+        let track = self.add(FragmentType::DmTrack(DmTrack {
+            name_reference: unnamed,
+            reference: FragmentRef::new(track_def),
+            flags: 0,
+        }));
+
+        let name_reference = self.name("CRATE_DMSPRITEDEF");
+        let mesh = self.add(FragmentType::DmSpriteDef2(DmSpriteDef2 {
+            name_reference,
+            flags: 0x00014003,
+            material_list_ref: FragmentRef::new(materials.palette),
+            animation_ref: FragmentRef::new(0),
+            fragment3: FragmentRef::new(0),
+            fragment4: FragmentRef::new(0),
+            center: (10.0, 5.0, 0.0),
+            params2: (0, 0, 0),
+            max_distance: 1.5,
+            min: (10.0, 5.0, 0.0),
+            max: (11.0, 6.0, 1.0),
+            position_count: 0,
+            texture_coordinate_count: 0,
+            normal_count: 0,
+            color_count: 0,
+            face_count: 0,
+            skin_assignment_groups_count: 0,
+            face_material_groups_count: 0,
+            vertex_material_groups_count: 0,
+            meshop_count: 0,
+            scale: 8,
+            positions: vec![(0, 0, 0), (256, 0, 0), (0, 256, 0), (0, 0, 256)],
+            texture_coordinates: vec![(0, 0), (256, 0), (0, 256), (256, 256)],
+            vertex_normals: vec![(0, 0, 127), (0, 0, 127), (0, 0, 127), (127, 0, 0)],
+            vertex_colors: vec![0xffffffff, 0xff808080, 0xff000000, 0xff0000ff],
+            faces: vec![
+                DmSpriteDef2FaceEntry {
+                    flags: 0,
+                    vertex_indexes: (0, 1, 2),
+                },
+                // Passable
+                DmSpriteDef2FaceEntry {
+                    flags: 0x10,
+                    vertex_indexes: (0, 2, 3),
+                },
+            ],
+            skin_assignment_groups: vec![(4, 0)],
+            face_material_groups: vec![(1, 0), (1, 1)],
+            vertex_material_groups: vec![(3, 0), (1, 1)],
+            meshops: vec![
+                DmSpriteDef2MeshOpEntry {
+                    index1: Some(0),
+                    index2: Some(1),
+                    offset: None,
+                    param1: 0,
+                    type_field: 1,
+                },
+                DmSpriteDef2MeshOpEntry {
+                    index1: None,
+                    index2: None,
+                    offset: Some(0.5),
+                    param1: 0,
+                    type_field: 4,
+                },
+            ],
+        }));
+        let name_reference = self.name("WAVE_DMSPRITEDEF");
+        let animated_mesh = self.add(FragmentType::DmSpriteDef2(DmSpriteDef2 {
+            name_reference,
+            flags: 0x00014003,
+            material_list_ref: FragmentRef::new(materials.palette),
+            animation_ref: FragmentRef::new(track),
+            fragment3: FragmentRef::new(0),
+            fragment4: FragmentRef::new(0),
+            center: (0.0, 0.0, 0.0),
+            params2: (0, 0, 0),
+            max_distance: 1.0,
+            min: (0.0, 0.0, 0.0),
+            max: (1.0, 0.0, 1.0),
+            position_count: 0,
+            texture_coordinate_count: 0,
+            normal_count: 0,
+            color_count: 0,
+            face_count: 0,
+            skin_assignment_groups_count: 0,
+            face_material_groups_count: 0,
+            vertex_material_groups_count: 0,
+            meshop_count: 0,
+            scale: 8,
+            positions: vec![(0, 0, 0), (256, 0, 0), (0, 0, 256)],
+            texture_coordinates: vec![],
+            vertex_normals: vec![],
+            vertex_colors: vec![],
+            faces: vec![DmSpriteDef2FaceEntry {
+                flags: 0,
+                vertex_indexes: (0, 1, 2),
+            }],
+            skin_assignment_groups: vec![],
+            face_material_groups: vec![(1, 1)],
+            vertex_material_groups: vec![],
+            meshops: vec![],
+        }));
+
+        let sprite = self.add(FragmentType::DmSprite(DmSprite {
+            name_reference: unnamed,
+            reference: FragmentRef::new(mesh),
+            params: 0,
+        }));
+        let animated_sprite = self.add(FragmentType::DmSprite(DmSprite {
+            name_reference: unnamed,
+            reference: FragmentRef::new(animated_mesh),
+            params: 0,
+        }));
+
+        Meshes {
+            mesh,
+            sprite,
+            animated_sprite,
+        }
+    }
+
+    /// A skeleton with a root and a child bone that each have a track, returning the
+    /// reference to its [HierarchicalSprite].
+    fn add_skeleton(&mut self, meshes: Meshes) -> i32 {
+        let name_reference = self.name("ELF_TRACKDEF");
+        // HAS_FRAME_TRANSFORMS
+        let track_def = self.add(FragmentType::TrackDef(TrackDef {
+            name_reference,
+            flags: 0x08,
+            frame_count: 2,
+            frame_transforms: Some(vec![
+                FrameTransform {
+                    rotate_denominator: 16384,
+                    rotate_x_numerator: 0,
+                    rotate_y_numerator: 0,
+                    rotate_z_numerator: 0,
+                    shift_x_numerator: 0,
+                    shift_y_numerator: 0,
+                    shift_z_numerator: 256,
+                    shift_denominator: 256,
+                },
+                FrameTransform {
+                    rotate_denominator: 11585,
+                    rotate_x_numerator: 0,
+                    rotate_y_numerator: 0,
+                    rotate_z_numerator: 11585,
+                    shift_x_numerator: 128,
+                    shift_y_numerator: 0,
+                    shift_z_numerator: 256,
+                    shift_denominator: 256,
+                },
+            ]),
+            legacy_frame_transforms: None,
+        }));
+        let name_reference = self.name("ELF_ARM_TRACKDEF");
+        let legacy_track_def = self.add(FragmentType::TrackDef(TrackDef {
+            name_reference,
+            flags: 0,
+            frame_count: 1,
+            frame_transforms: None,
+            legacy_frame_transforms: Some(vec![LegacyFrameTransform {
+                rotate_x: 0.0,
+                rotate_y: 0.0,
+                rotate_z: 0.0,
+                rotate_w: 1.0,
+                shift_x_numerator: 1.0,
+                shift_y_numerator: 0.0,
+                shift_z_numerator: 0.0,
+                shift_denominator: 1.0,
+            }]),
+        }));
+
+        let name_reference = self.name("ELF_TRACK");
+        // HAS_SLEEP | INTERPOLATE
+        let track = self.add(FragmentType::Track(Track {
+            name_reference,
+            reference: FragmentRef::new(track_def),
+            flags: TrackInstanceFlags::new(0x05),
+            sleep: Some(100),
+        }));
+        let name_reference = self.name("ELF_ARM_TRACK");
+        let legacy_track = self.add(FragmentType::Track(Track {
+            name_reference,
+            reference: FragmentRef::new(legacy_track_def),
+            flags: TrackInstanceFlags::new(0),
+            sleep: None,
+        }));
+
+        let root_name = self.name("ELF_DAG");
+        let arm_name = self.name("ELF_ARM_DAG");
+        let dags = vec![
+            Dag {
+                name_reference: root_name.0,
+                flags: 0,
+                track_reference: FragmentRef::new(track),
+                mesh_or_sprite_reference: FragmentRef::new(meshes.sprite),
+                num_sub_dags: 0,
+                sub_dags: vec![1],
+            },
+            Dag {
+                name_reference: arm_name.0,
+                flags: 0,
+                track_reference: FragmentRef::new(legacy_track),
+                mesh_or_sprite_reference: FragmentRef::new(0),
+                num_sub_dags: 0,
+                sub_dags: vec![],
+            },
+        ];
+
+        let name_reference = self.name("ELF_HS_DEF");
+        let skeleton = self.add(FragmentType::HierarchicalSpriteDef(HierarchicalSpriteDef {
+            name_reference,
+            flags: HierarchicalSpriteDefFlags::new(0),
+            num_dags: 0,
+            collision_volume_reference: 0,
+            center_offset: Some((0, 0, 0)),
+            bounding_radius: Some(2.5),
+            dags,
+            num_attached_skins: None,
+            dm_sprites: Some(vec![FragmentRef::new(meshes.animated_sprite)]),
+            link_skin_updates_to_dag_index: Some(vec![0]),
+        }));
+        let name_reference = self.name("ELF_BARE_HS_DEF");
+        self.add(FragmentType::HierarchicalSpriteDef(HierarchicalSpriteDef {
+            name_reference,
+            flags: HierarchicalSpriteDefFlags::new(0),
+            num_dags: 0,
+            collision_volume_reference: 0,
+            center_offset: None,
+            bounding_radius: None,
+            dags: vec![Dag {
+                name_reference: root_name.0,
+                flags: 0,
+                track_reference: FragmentRef::new(track),
+                mesh_or_sprite_reference: FragmentRef::new(0),
+                num_sub_dags: 0,
+                sub_dags: vec![],
+            }],
+            num_attached_skins: None,
+            dm_sprites: None,
+            link_skin_updates_to_dag_index: None,
+        }));
+
+        let unnamed = self.name("");
+        self.add(FragmentType::HierarchicalSprite(HierarchicalSprite {
+            name_reference: unnamed,
+            reference: FragmentRef::new(skeleton),
+            params1: 0,
+        }))
+    }
+
+    /// A model for the static mesh and one for the skeleton, with an object placed for each.
+    fn add_models(&mut self, meshes: Meshes, skeleton: i32) {
+        let name_reference = self.name("CRATE_ACTORDEF");
+        let callback_name_reference = self.name("SPRITECALLBACK");
+        let crate_model = self.add(FragmentType::ActorDef(ActorDef {
+            name_reference,
+            flags: ActorDefFlags::new(0),
+            callback_name_reference,
+            action_count: 0,
+            fragment_reference_count: 0,
+            bounds_reference: 0,
+            current_action: None,
+            location: None,
+            actions: vec![Action {
+                levels_of_detail_count: 1,
+                unknown: 0,
+                levels_of_detail_distances: vec![1e30],
+            }],
+            fragment_references: vec![FragmentRef::new(meshes.sprite)],
+            unknown: 0,
+        }));
+        let name_reference = self.name("ELF_ACTORDEF");
+        // ACTIVE_GEOMETRY
+        self.add(FragmentType::ActorDef(ActorDef {
+            name_reference,
+            flags: ActorDefFlags::new(0x40),
+            callback_name_reference,
+            action_count: 0,
+            fragment_reference_count: 0,
+            bounds_reference: 0,
+            current_action: Some(0),
+            location: Some(Location {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                rotate_z: 0.0,
+                rotate_y: 0.0,
+                rotate_x: 0.0,
+                unknown: 0,
+            }),
+            actions: vec![
+                Action {
+                    levels_of_detail_count: 2,
+                    unknown: 0,
+                    levels_of_detail_distances: vec![100.0, 1e30],
+                },
+                Action {
+                    levels_of_detail_count: 0,
+                    unknown: 0,
+                    levels_of_detail_distances: vec![],
+                },
+            ],
+            fragment_references: vec![FragmentRef::new(skeleton), FragmentRef::new(skeleton)],
+            unknown: 0,
+        }));
+
+        let name_reference = self.name("CRATE_RGBTRACKDEF");
+        let colors_def = self.add(FragmentType::DmRGBTrackDef(DmRGBTrackDef {
+            name_reference,
+            data1: 1,
+            vertex_color_count: 0,
+            data2: 1,
+            data3: 200,
+            data4: 0,
+            vertex_colors: vec![0xff204060; 4],
+        }));
+        let name_reference = self.name("CRATE_RGBTRACK");
+        let colors = self.add(FragmentType::DmRGBTrack(DmRGBTrack {
+            name_reference,
+            reference: FragmentRef::new(colors_def),
+            flags: 0,
+        }));
+
+        let unnamed = self.name("");
+        let sound_name_reference = self.name("CREAK");
+        let elf_model_name = self.name("ELF_ACTORDEF");
+        let actor_name = self.name("CRATE_ACTORINST");
+        self.add(FragmentType::Actor(Actor {
+            name_reference: actor_name,
+            actor_def_reference: FragmentRef::new(crate_model),
+            // ACTIVE
+            flags: ActorInstFlags::new(0x20),
+            sphere_reference: FragmentRef::new(0),
+            current_action: Some(0),
+            location: Some(Location {
+                x: 10.0,
+                y: 5.0,
+                z: 0.0,
+                rotate_z: 0.0,
+                rotate_y: -128.0,
+                rotate_x: 0.0,
+                unknown: 0,
+            }),
+            bounding_radius: Some(0.5),
+            scale_factor: Some(2.0),
+            sound_name_reference: Some(sound_name_reference),
+            vertex_color_reference: Some(FragmentRef::new(colors)),
+            user_data_size: 8,
+            user_data: "CRATE01".into(),
+        }));
+        self.add(FragmentType::Actor(Actor {
+            name_reference: unnamed,
+            actor_def_reference: FragmentRef::new(elf_model_name.0),
+            flags: ActorInstFlags::new(0),
+            sphere_reference: FragmentRef::new(0),
+            current_action: None,
+            location: None,
+            bounding_radius: None,
+            scale_factor: None,
+            sound_name_reference: None,
+            vertex_color_reference: None,
+            user_data_size: 0,
+            user_data: Default::default(),
+        }));
+    }
+
+    /// Lights of every kind, returning the reference to the [Light] they share.
+    fn add_lights(&mut self) -> i32 {
+        let name_reference = self.name("TORCH_LDEF");
+        let light_def = self.add(FragmentType::LightDef(LightDef {
+            name_reference,
+            flags: LightDefFlags::new(0),
+            frame_count: 2,
+            current_frame: Some(0),
+            sleep: Some(200),
+            light_levels: Some(vec![1.0, 0.75]),
+            colors: Some(vec![(1.0, 0.75, 0.5), (1.0, 0.5, 0.25)]),
+        }));
+        let name_reference = self.name("DIM_LDEF");
+        self.add(FragmentType::LightDef(LightDef {
+            name_reference,
+            flags: LightDefFlags::new(0),
+            frame_count: 1,
+            current_frame: None,
+            sleep: None,
+            light_levels: None,
+            colors: None,
+        }));
+
+        let name_reference = self.name("TORCH_L");
+        let light = self.add(FragmentType::Light(Light {
+            name_reference,
+            reference: FragmentRef::new(light_def),
+            flags: 0,
+        }));
+        let unnamed = self.name("");
+        // IS_STATIC | STATIC_INFLUENCE
+        self.add(FragmentType::PointLight(PointLight {
+            name_reference: unnamed,
+            reference: FragmentRef::new(light),
+            flags: PointLightFlags::new(0x60),
+            x: 10.0,
+            y: 5.0,
+            z: 2.0,
+            radius: 50.0,
+        }));
+        let name_reference = self.name("SUN_DIRLIGHT");
+        // IS_STATIC
+        self.add(FragmentType::DirectionalLight(DirectionalLight {
+            name_reference,
+            light_reference: FragmentRef::new(light),
+            flags: DirectionalLightFlags::new(0x20),
+            normal: (0.0, 0.0, -1.0),
+            num_regions: 0,
+            regions: vec![0],
+        }));
+        let name_reference = self.name("DEFAULT_AMBIENTLIGHT");
+        self.add(FragmentType::GlobalAmbientLightDef(GlobalAmbientLightDef {
+            name_reference,
+        }));
+
+        light
+    }
+
+    /// Two regions, one with every optional field and one without, and a world tree
+    /// splitting them.
+    fn add_regions(&mut self, materials: Materials, meshes: Meshes, light: i32) {
+        let name_reference = self.name("DEFAULT_AMBIENTLIGHT_L");
+        let ambient_light = self.add(FragmentType::AmbientLight(AmbientLight {
+            name_reference,
+            reference: FragmentRef::new(light),
+            flags: 0,
+            region_count: 0,
+            regions: vec![0, 1],
+        }));
+
+        let render_info = RenderInfo {
+            flags: RenderInfoFlags::new(0x7f),
+            pen: Some(201),
+            brightness: Some(1.0),
+            scaled_ambient: Some(1.0),
+            simple_sprite_reference: Some(materials.sprite as u32),
+            uv_info: Some(UvInfo {
+                uv_origin: (0.0, 0.0, 0.0),
+                u_axis: (16.0, 0.0, 0.0),
+                v_axis: (0.0, 16.0, 0.0),
+            }),
+            uv_map: Some(UvMap {
+                entry_count: 4,
+                entries: vec![(0.0, 1.0), (1.0, 1.0), (1.0, 0.0), (0.0, 0.0)],
+            }),
+        };
+
+        let name_reference = self.name("R1");
+        // HAS_BYTE_ENTRIES
+        let first = self.add(FragmentType::Region(Region {
+            name_reference,
+            flags: RegionFlags::new(0x80),
+            ambient_light: FragmentRef::new(ambient_light),
+            num_region_vertex: 0,
+            num_proximal_regions: 0,
+            num_render_vertices: 0,
+            num_walls: 0,
+            num_obstacles: 0,
+            num_cutting_obstacles: 0,
+            num_vis_node: 0,
+            num_vis_list: 0,
+            region_vertices: vec![
+                (0.0, 0.0, 0.0),
+                (100.0, 0.0, 0.0),
+                (100.0, 100.0, 0.0),
+                (0.0, 100.0, 0.0),
+            ],
+            proximal_regions: vec![(1, 50.0)],
+            render_vertices: vec![],
+            walls: vec![
+                // HAS_FLOOR | HAS_METHOD_AND_NORMAL
+                Wall {
+                    flags: WallFlags::new(0x03),
+                    num_vertices: 4,
+                    render_method: Some(RenderMethod::UserDefined {
+                        material_type: MaterialType::Diffuse,
+                    }),
+                    render_info: Some(render_info),
+                    normal_abcd: Some((0.0, 0.0, 1.0, 0.0)),
+                    vertex_list: vec![0, 1, 2, 3],
+                },
+                Wall {
+                    flags: WallFlags::new(0),
+                    num_vertices: 2,
+                    render_method: None,
+                    render_info: None,
+                    normal_abcd: None,
+                    vertex_list: vec![0, 1],
+                },
+            ],
+            obstacles: vec![
+                Obstacle {
+                    flags: ObstacleFlags::new(0),
+                    next_region: 2,
+                    obstacle_type: ObstacleType::EdgePolygonNormalAbcd,
+                    num_vertices: Some(4),
+                    vertex_list: Some(vec![0, 1, 2, 3]),
+                    normal_abcd: Some((1.0, 0.0, 0.0, -100.0)),
+                    edge_wall: None,
+                    user_data_size: None,
+                    user_data: None,
+                },
+                // IS_FLOOR | HAS_USER_DATA
+                Obstacle {
+                    flags: ObstacleFlags::new(0x05),
+                    next_region: 0,
+                    obstacle_type: ObstacleType::EdgeWall,
+                    num_vertices: None,
+                    vertex_list: None,
+                    normal_abcd: None,
+                    edge_wall: Some(0),
+                    user_data_size: Some(4),
+                    user_data: Some(b"DATA".to_vec()),
+                },
+                Obstacle {
+                    flags: ObstacleFlags::new(0),
+                    next_region: 2,
+                    obstacle_type: ObstacleType::EdgePolygon,
+                    num_vertices: Some(3),
+                    vertex_list: Some(vec![0, 1, 2]),
+                    normal_abcd: None,
+                    edge_wall: None,
+                    user_data_size: None,
+                    user_data: None,
+                },
+                Obstacle {
+                    flags: ObstacleFlags::new(0),
+                    next_region: 2,
+                    obstacle_type: ObstacleType::XyVertex,
+                    num_vertices: None,
+                    vertex_list: None,
+                    normal_abcd: None,
+                    edge_wall: None,
+                    user_data_size: None,
+                    user_data: None,
+                },
+            ],
+            vis_nodes: vec![VisNode {
+                normal_abcd: (0.0, 0.0, 0.0, 0.0),
+                vis_list_index: 1,
+                front_tree: 0,
+                back_tree: 0,
+            }],
+            visible_lists: vec![VisibleList {
+                range_count: 2,
+                ranges: vec![RangeEntry::Byte(0x00), RangeEntry::Byte(0xc2)],
+            }],
+            sphere: Some((50.0, 50.0, 0.0, 71.0)),
+            reverb_volume: Some(0.5),
+            reverb_offset: Some(10),
+            user_data_size: 5,
+            user_data: b"WATER".to_vec(),
+            mesh_reference: Some(FragmentRef::new(meshes.mesh)),
+        }));
+        let name_reference = self.name("R2");
+        let second = self.add(FragmentType::Region(Region {
+            name_reference,
+            flags: RegionFlags::new(0),
+            ambient_light: FragmentRef::new(ambient_light),
+            num_region_vertex: 0,
+            num_proximal_regions: 0,
+            num_render_vertices: 0,
+            num_walls: 0,
+            num_obstacles: 0,
+            num_cutting_obstacles: 0,
+            num_vis_node: 0,
+            num_vis_list: 0,
+            region_vertices: vec![],
+            proximal_regions: vec![],
+            render_vertices: vec![],
+            walls: vec![],
+            obstacles: vec![],
+            vis_nodes: vec![],
+            visible_lists: vec![VisibleList {
+                range_count: 1,
+                ranges: vec![RangeEntry::Word(0x00ff)],
+            }],
+            sphere: None,
+            reverb_volume: None,
+            reverb_offset: None,
+            user_data_size: 0,
+            user_data: vec![],
+            mesh_reference: None,
+        }));
+
+        let name_reference = self.name("WORLD_TREE");
+        self.add(FragmentType::WorldTree(WorldTree {
+            name_reference,
+            world_node_count: 0,
+            world_nodes: vec![
+                WorldNode {
+                    normal: (1.0, 0.0, 0.0),
+                    split_distance: -100.0,
+                    region: FragmentRef::new(0),
+                    front_tree: FragmentRef::new(2),
+                    back_tree: FragmentRef::new(3),
+                },
+                WorldNode {
+                    normal: (0.0, 0.0, 0.0),
+                    split_distance: 0.0,
+                    region: FragmentRef::new(first),
+                    front_tree: FragmentRef::new(0),
+                    back_tree: FragmentRef::new(0),
+                },
+                WorldNode {
+                    normal: (0.0, 0.0, 0.0),
+                    split_distance: 0.0,
+                    region: FragmentRef::new(second),
+                    front_tree: FragmentRef::new(0),
+                    back_tree: FragmentRef::new(0),
+                },
+            ],
+        }));
+    }
+
+    /// The 2d, 3d and 4d sprites and the spheres and polyhedra they are bounded by.
+    fn add_sprites(&mut self) {
+        let name_reference = self.name("TREE_SPHERE");
+        let sphere = self.add(FragmentType::Sphere(Sphere {
+            name_reference,
+            radius: 4.0,
+        }));
+        let name_reference = self.name("TREE_SPHERELISTDEF");
+        let sphere_list_def = self.add(FragmentType::SphereListDef(SphereListDef {
+            name_reference,
+            flags: SphereListDefFlags::new(0),
+            num_spheres: 0,
+            bounding_radius: 5.0,
+            scale_factor: Some(1.0),
+            spheres: vec![(0.0, 0.0, 1.0, 1.0), (0.0, 0.0, 3.0, 2.0)],
+        }));
+        let name_reference = self.name("ROCK_SPHERELISTDEF");
+        self.add(FragmentType::SphereListDef(SphereListDef {
+            name_reference,
+            flags: SphereListDefFlags::new(0),
+            num_spheres: 0,
+            bounding_radius: 1.0,
+            scale_factor: None,
+            spheres: vec![],
+        }));
+        let unnamed = self.name("");
+        let sphere_list = self.add(FragmentType::SphereList(SphereList {
+            name_reference: unnamed,
+            reference: FragmentRef::new(sphere_list_def),
+            params1: 0,
+        }));
+
+        let render_info = |flags, pen| RenderInfo {
+            flags: RenderInfoFlags::new(flags),
+            pen,
+            brightness: None,
+            scaled_ambient: None,
+            simple_sprite_reference: None,
+            uv_info: None,
+            uv_map: None,
+        };
+        let render_method = RenderMethod::UserDefined {
+            material_type: MaterialType::TransparentMasked,
+        };
+
+        let name_reference = self.name("TREE_2DSPRITEDEF");
+        let sprite_2d_def = self.add(FragmentType::Sprite2DDef(Sprite2DDef {
+            name_reference,
+            flags: SpriteFlags::new(0),
+            num_frames: 2,
+            num_pitches: 0,
+            sprite_size: (8.0, 16.0),
+            sphere_fragment: FragmentRef::new(sphere),
+            depth_scale: Some(1.0),
+            center_offset: Some((0.0, 0.0, 8.0)),
+            bounding_radius: Some(9.0),
+            current_frame: Some(0),
+            sleep: Some(100),
+            pitches: vec![SpritePitch {
+                pitch_cap: 512,
+                num_headings: 0,
+                headings: vec![
+                    SpriteHeading {
+                        heading_cap: 64,
+                        frames: vec![1, 2],
+                    },
+                    SpriteHeading {
+                        heading_cap: 128,
+                        frames: vec![3, 4],
+                    },
+                ],
+            }],
+            render_method,
+            render_info: render_info(0x01, Some(0)),
+        }));
+        let name_reference = self.name("BUSH_2DSPRITEDEF");
+        self.add(FragmentType::Sprite2DDef(Sprite2DDef {
+            name_reference,
+            flags: SpriteFlags::new(0),
+            num_frames: 0,
+            num_pitches: 0,
+            sprite_size: (1.0, 1.0),
+            sphere_fragment: FragmentRef::new(0),
+            depth_scale: None,
+            center_offset: None,
+            bounding_radius: None,
+            current_frame: None,
+            sleep: None,
+            pitches: vec![],
+            render_method,
+            render_info: render_info(0, None),
+        }));
+        self.add(FragmentType::Sprite2D(Sprite2D {
+            name_reference: unnamed,
+            reference: FragmentRef::new(sprite_2d_def),
+            flags: 0,
+        }));
+
+        let name_reference = self.name("TREE_3DSPRITEDEF");
+        let sprite_3d_def = self.add(FragmentType::Sprite3DDef(Sprite3DDef {
+            name_reference,
+            flags: ThreeDSpriteFlags::new(0),
+            vertex_count: 0,
+            bsp_node_count: 0,
+            sphere_list_reference: FragmentRef::new(sphere_list),
+            center_offset: Some((0.0, 0.0, 1.0)),
+            bounding_radius: Some(2.0),
+            vertices: vec![(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)],
+            bsp_nodes: vec![BspNodeEntry {
+                vertex_count: 0,
+                front_tree: 0,
+                back_tree: 0,
+                vertex_indices: vec![0, 1, 2],
+                render_method,
+                render_info: render_info(0x01, Some(1)),
+            }],
+        }));
+        let name_reference = self.name("ROCK_3DSPRITEDEF");
+        self.add(FragmentType::Sprite3DDef(Sprite3DDef {
+            name_reference,
+            flags: ThreeDSpriteFlags::new(0),
+            vertex_count: 0,
+            bsp_node_count: 0,
+            sphere_list_reference: FragmentRef::new(0),
+            center_offset: None,
+            bounding_radius: None,
+            vertices: vec![],
+            bsp_nodes: vec![],
+        }));
+        self.add(FragmentType::Sprite3D(Sprite3D {
+            name_reference: unnamed,
+            reference: FragmentRef::new(sprite_3d_def),
+            flags: 0,
+        }));
+
+        let name_reference = self.name("TREE_POLYHDEF");
+        let polyhedron_def = self.add(FragmentType::PolyhedronDef(PolyhedronDef {
+            name_reference,
+            flags: 0,
+            size1: 3,
+            size2: 1,
+            params1: 1.0,
+            params2: 1.0,
+            entries1: vec![(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)],
+            entries2: vec![(3, vec![0, 1, 2])],
+        }));
+        // HAS_SCALE_FACTOR
+        self.add(FragmentType::Polyhedron(Polyhedron {
+            name_reference: unnamed,
+            reference: FragmentRef::new(polyhedron_def),
+            flags: PolyhedronFlags::new(0x01),
+            scale_factor: Some(2.0),
+        }));
+        self.add(FragmentType::Polyhedron(Polyhedron {
+            name_reference: unnamed,
+            reference: FragmentRef::new(polyhedron_def),
+            flags: PolyhedronFlags::new(0),
+            scale_factor: None,
+        }));
+
+        let name_reference = self.name("TREE_4DSPRITEDEF");
+        let sprite_4d_def = self.add(FragmentType::Sprite4DDef(Sprite4DDef {
+            name_reference,
+            flags: Sprite4DDefFlags::new(0),
+            num_frames: 0,
+            polygon_fragment: polyhedron_def as u32,
+            center_offset: Some((0.0, 0.0, 1.0)),
+            bounding_radius: Some(2.0),
+            current_frame: Some(0),
+            sleep: Some(100),
+            sprite_fragments: Some(vec![sprite_3d_def as u32, sprite_3d_def as u32]),
+        }));
+        let name_reference = self.name("ROCK_4DSPRITEDEF");
+        self.add(FragmentType::Sprite4DDef(Sprite4DDef {
+            name_reference,
+            flags: Sprite4DDefFlags::new(0),
+            num_frames: 1,
+            polygon_fragment: polyhedron_def as u32,
+            center_offset: None,
+            bounding_radius: None,
+            current_frame: None,
+            sleep: None,
+            sprite_fragments: None,
+        }));
+        self.add(FragmentType::Sprite4D(Sprite4D {
+            name_reference: unnamed,
+            reference: FragmentRef::new(sprite_4d_def),
+            params1: 0,
+        }));
+    }
+
+    fn add_particles(&mut self, materials: Materials) {
+        let name_reference = self.name("SPARK_SPB");
+        // TRANSPARENT
+        let blit_sprite_def = self.add(FragmentType::BlitSpriteDef(BlitSpriteDef {
+            name_reference,
+            flags: BlitSpriteDefFlags::new(0x100),
+            blit_sprite_reference: materials.sprite as u32,
+            unknown: 0,
+        }));
+        let unnamed = self.name("");
+        self.add(FragmentType::BlitSprite(BlitSprite {
+            name_reference: unnamed,
+            blit_sprite_reference: blit_sprite_def as u32,
+            unknown: 0,
+        }));
+
+        let render_info = RenderInfo {
+            flags: RenderInfoFlags::new(0x03),
+            pen: Some(0),
+            brightness: Some(1.0),
+            scaled_ambient: None,
+            simple_sprite_reference: None,
+            uv_info: None,
+            uv_map: None,
+        };
+        let name_reference = self.name("SPARK_PSD");
+        let particle_sprite_def = self.add(FragmentType::ParticleSpriteDef(ParticleSpriteDef {
+            name_reference,
+            flags: ParticleSpriteDefFlags::new(0),
+            num_vertices: 0,
+            unknown: 0,
+            center_offset: Some((0.0, 0.0, 0.5)),
+            bounding_radius: Some(1.0),
+            vertices: vec![(0.0, 0.0, 0.0), (0.0, 0.0, 1.0)],
+            render_method: RenderMethod::UserDefined {
+                material_type: MaterialType::TransparentAdditive,
+            },
+            render_info,
+            pen: vec![0xffffffff, 0xff00ffff],
+        }));
+        let name_reference = self.name("SMOKE_PSD");
+        self.add(FragmentType::ParticleSpriteDef(ParticleSpriteDef {
+            name_reference,
+            flags: ParticleSpriteDefFlags::new(0),
+            num_vertices: 0,
+            unknown: 0,
+            center_offset: None,
+            bounding_radius: None,
+            vertices: vec![],
+            render_method: RenderMethod::UserDefined {
+                material_type: MaterialType::Transparent50,
+            },
+            render_info: RenderInfo {
+                flags: RenderInfoFlags::new(0),
+                pen: None,
+                brightness: None,
+                scaled_ambient: None,
+                simple_sprite_reference: None,
+                uv_info: None,
+                uv_map: None,
+            },
+            pen: vec![],
+        }));
+        self.add(FragmentType::ParticleSprite(ParticleSprite {
+            name_reference: unnamed,
+            reference: FragmentRef::new(particle_sprite_def),
+            params1: 0,
+        }));
+
+        let name_reference = self.name("SPARK_PCD");
+        self.add(FragmentType::ParticleCloudDef(ParticleCloudDef {
+            name_reference,
+            unknown_1: 4,
+            unknown_2: 3,
+            particle_movement: ParticleMovement::Sphere,
+            flags: 0x2,
+            simultaneous_particles: 20,
+            unknown_6: 0,
+            unknown_7: 0,
+            unknown_8: 0,
+            unknown_9: 0,
+            unknown_10: 0,
+            spawn_radius: 1.0,
+            spawn_angle: 30.0,
+            spawn_lifespan: 1000,
+            spawn_velocity: 2.0,
+            spawn_normal_z: 1.0,
+            spawn_normal_x: 0.0,
+            spawn_normal_y: 0.0,
+            spawn_rate: 10,
+            spawn_scale: 0.5,
+            color: (255, 128, 0, 255),
+            blitsprite: FragmentRef::new(blit_sprite_def),
+        }));
+    }
+
+    /// Two [DmSpriteDef]s, the 0x2c layout in EverQuest files.
+    fn add_legacy_meshes(&mut self, materials: Materials) {
+        let name_reference = self.name("OLD_CRATE_DMSPRITEDEF");
+        self.add(FragmentType::DmSpriteDef(DmSpriteDef {
+            name_reference,
+            flags: 0x7a00,
+            vertex_count: 0,
+            texture_coordinate_count: 0,
+            normal_count: 0,
+            color_count: 0,
+            face_count: 0,
+            meshop_count: 0,
+            fragment1: 0,
+            skin_assignment_group_count: 0,
+            material_list_ref: FragmentRef::new(materials.palette),
+            fragment3: 0,
+            center: (0.0, 0.0, 0.0),
+            params1: (0.0, 0.0, 0.0),
+            vertices: vec![(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)],
+            texture_coordinates: vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)],
+            vertex_normals: vec![(0.0, 0.0, 1.0); 3],
+            vertex_colors: vec![0xffffffff; 3],
+            faces: vec![DmSpriteDefFaceEntry {
+                flags: 0,
+                data: (0, 0, 0, 0),
+                vertex_indexes: (0, 1, 2),
+            }],
+            meshops: vec![
+                DmSpriteDefMeshopEntry {
+                    type_field: 1,
+                    vertex_index: Some(0),
+                    offset: None,
+                    param1: 0,
+                    param2: 0,
+                },
+                DmSpriteDefMeshopEntry {
+                    type_field: 4,
+                    vertex_index: None,
+                    offset: Some(0.5),
+                    param1: 0,
+                    param2: 0,
+                },
+            ],
+            skin_assignment_groups: vec![(3, 0)],
+            size8: Some(2),
+            data8: Some(vec![0, 1]),
+            face_material_group_count: Some(1),
+            face_material_groups: Some(vec![(1, 0)]),
+            vertex_material_group_count: Some(1),
+            vertex_material_groups: Some(vec![(3, 0)]),
+            params2: Some((0, 0, 0)),
+            params3: Some((0.0, 0.0, 0.0, 1.0, 1.0, 1.0)),
+        }));
+        let name_reference = self.name("OLD_ROCK_DMSPRITEDEF");
+        self.add(FragmentType::DmSpriteDef(DmSpriteDef {
+            name_reference,
+            flags: 0,
+            vertex_count: 0,
+            texture_coordinate_count: 0,
+            normal_count: 0,
+            color_count: 0,
+            face_count: 0,
+            meshop_count: 0,
+            fragment1: 0,
+            skin_assignment_group_count: 0,
+            material_list_ref: FragmentRef::new(materials.palette),
+            fragment3: 0,
+            center: (0.0, 0.0, 0.0),
+            params1: (0.0, 0.0, 0.0),
+            vertices: vec![(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)],
+            texture_coordinates: vec![],
+            vertex_normals: vec![],
+            vertex_colors: vec![],
+            faces: vec![],
+            meshops: vec![],
+            skin_assignment_groups: vec![],
+            size8: None,
+            data8: None,
+            face_material_group_count: None,
+            face_material_groups: None,
+            vertex_material_group_count: None,
+            vertex_material_groups: None,
+            params2: None,
+            params3: None,
+        }));
+
+        let name_reference = self.name("OLD_WAVE_DMTRACKDEF");
+        self.add(FragmentType::DmTrackDef(DmTrackDef {
+            name_reference,
+            flags: 0,
+            vertex_count: 3,
+            frame_count: 0,
+            sleep: 100,
+            param1: 0,
+            frames: vec![
+                vec![(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)],
+                vec![(0.0, 0.0, 0.5), (1.0, 0.0, 0.5), (0.0, 1.0, 0.5)],
+            ],
+        }));
+    }
+
+    /// Two [WorldVertices], the 0x2c layout in Tanarus files.
+    fn add_world_vertices(&mut self) {
+        for count in [4, 5] {
+            self.add(FragmentType::WorldVertices(WorldVertices {
+                name_reference: StringReference::new(0),
+                num_vertices: 0,
+                vertices: (0..count).map(|v| (v as f32, 0.0, 1.0)).collect(),
+            }));
+        }
+    }
+
+    /// Two [BmInfoRtk]s, the 0x2c layout in Return to Krondor files.
+    fn add_rtk_bitmaps(&mut self) {
+        for (name, file_name) in [("GRASS.BMP", "grass.bmp"), ("DIRT.BMP", "dirt.bmp")] {
+            let name_reference = self.name(name);
+            self.add(FragmentType::BmInfoRtk(BmInfoRtk {
+                name_reference,
+                size1: 0,
+                rtk: 0,
+                entries: vec![EncodedFilename::new(file_name)],
+            }));
+        }
+    }
+
+    fn add_zones(&mut self) {
+        let name_reference = self.name("WT_ZONE");
+        self.add(FragmentType::Zone(Zone {
+            name_reference,
+            flags: 0,
+            region_count: 0,
+            regions: vec![0],
+            user_data_size: 0,
+            user_data: Default::default(),
+        }));
+        let name_reference = self.name("Z0000_ZONE");
+        self.add(FragmentType::Zone(Zone {
+            name_reference,
+            flags: 0,
+            region_count: 0,
+            regions: vec![0, 1],
+            user_data_size: 12,
+            user_data: "WTN__01521".into(),
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::parser::{RawFragment, WldVersion};
+
+    const GAMES: [FragmentGame; 3] = [
+        FragmentGame::EverQuest,
+        FragmentGame::Tanarus,
+        FragmentGame::ReturnToKrondor,
+    ];
+
+    /// The name of the fragment's variant. The match is exhaustive so that new fragment
+    /// types have to be added here, and to the fixtures for the test below to pass.
+    fn variant(fragment: &FragmentType) -> &'static str {
+        match fragment {
+            FragmentType::DmSpriteDef(_) => "DmSpriteDef",
+            FragmentType::AmbientLight(_) => "AmbientLight",
+            FragmentType::BlitSpriteDef(_) => "BlitSpriteDef",
+            FragmentType::BlitSprite(_) => "BlitSprite",
+            FragmentType::Region(_) => "Region",
+            FragmentType::WorldTree(_) => "WorldTree",
+            FragmentType::Sprite3DDef(_) => "Sprite3DDef",
+            FragmentType::Sprite3D(_) => "Sprite3D",
+            FragmentType::GlobalAmbientLightDef(_) => "GlobalAmbientLightDef",
+            FragmentType::Sprite4D(_) => "Sprite4D",
+            FragmentType::Sprite4DDef(_) => "Sprite4DDef",
+            FragmentType::PointLight(_) => "PointLight",
+            FragmentType::LightDef(_) => "LightDef",
+            FragmentType::Light(_) => "Light",
+            FragmentType::MaterialDef(_) => "MaterialDef",
+            FragmentType::MaterialPalette(_) => "MaterialPalette",
+            FragmentType::DmSpriteDef2(_) => "DmSpriteDef2",
+            FragmentType::DmTrackDef2(_) => "DmTrackDef2",
+            FragmentType::DmTrack(_) => "DmTrack",
+            FragmentType::DmSprite(_) => "DmSprite",
+            FragmentType::TrackDef(_) => "TrackDef",
+            FragmentType::Track(_) => "Track",
+            FragmentType::ActorDef(_) => "ActorDef",
+            FragmentType::Actor(_) => "Actor",
+            FragmentType::ParticleSprite(_) => "ParticleSprite",
+            FragmentType::ParticleSpriteDef(_) => "ParticleSpriteDef",
+            FragmentType::ParticleCloudDef(_) => "ParticleCloudDef",
+            FragmentType::DefaultPaletteFile(_) => "DefaultPaletteFile",
+            FragmentType::PolyhedronDef(_) => "PolyhedronDef",
+            FragmentType::Polyhedron(_) => "Polyhedron",
+            FragmentType::Zone(_) => "Zone",
+            FragmentType::HierarchicalSpriteDef(_) => "HierarchicalSpriteDef",
+            FragmentType::HierarchicalSprite(_) => "HierarchicalSprite",
+            FragmentType::SphereList(_) => "SphereList",
+            FragmentType::SphereListDef(_) => "SphereListDef",
+            FragmentType::SimpleSpriteDef(_) => "SimpleSpriteDef",
+            FragmentType::BmInfo(_) => "BmInfo",
+            FragmentType::BmInfoRtk(_) => "BmInfoRtk",
+            FragmentType::SimpleSprite(_) => "SimpleSprite",
+            FragmentType::Sprite2DDef(_) => "Sprite2DDef",
+            FragmentType::Sprite2D(_) => "Sprite2D",
+            FragmentType::DmTrackDef(_) => "DmTrackDef",
+            FragmentType::DmRGBTrackDef(_) => "DmRGBTrackDef",
+            FragmentType::DmRGBTrack(_) => "DmRGBTrack",
+            FragmentType::WorldVertices(_) => "WorldVertices",
+            FragmentType::Sphere(_) => "Sphere",
+            FragmentType::DirectionalLight(_) => "DirectionalLight",
+            FragmentType::Raw(_) => "Raw",
+        }
+    }
+
+    #[test]
+    fn it_covers_every_fragment_type() {
+        let mut variants = BTreeSet::new();
+        for game in GAMES {
+            let mut wld_doc = wld_doc(game);
+            wld_doc.add_fragment(FragmentType::Raw(RawFragment::new(0x99, &[0; 4])));
+            wld_doc.normalize();
+            let (wld_doc, warnings) = WldDoc::parse_lenient(&wld_doc.to_bytes()).unwrap();
+            assert_eq!(warnings.len(), 1);
+            variants.extend(wld_doc.iter().map(variant));
+        }
+        assert_eq!(variants.len(), 48, "{:?}", variants);
+    }
+
+    #[test]
+    fn it_round_trips() {
+        for game in GAMES {
+            for version in [WldVersion::Old, WldVersion::New] {
+                let mut wld_doc = wld_doc(game);
+                wld_doc.set_version(version);
+                assert!(wld_doc.validate().is_valid(), "{}", wld_doc.validate());
+                let data = wld_doc.to_bytes();
+
+                let parsed = WldDoc::parse(&data).unwrap();
+                assert_eq!(parsed.game(), game);
+                assert_eq!(parsed.version(), version);
+                assert_eq!(parsed.fragment_count(), wld_doc.fragment_count());
+                for (parsed, fragment) in parsed.iter().zip(wld_doc.iter()) {
+                    assert_eq!(
+                        parsed.to_bytes_versioned(version),
+                        fragment.to_bytes_versioned(version),
+                        "{:?}",
+                        fragment
+                    );
+                }
+                assert_eq!(parsed.to_bytes(), data);
+            }
+        }
+    }
+}
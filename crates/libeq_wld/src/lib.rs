@@ -39,30 +39,28 @@
 //! seemed like a better fit. The goal is that this will be usable in more modern engines and
 //! hopefully the names used are more familiar in that context.
 //!
+pub mod builder;
+mod error;
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
 pub mod parser;
 
+pub use error::WldError;
 use parser::{
-    Actor, ActorDef, DmSprite, DmSpriteDef2, DmSpriteDef2FaceEntry, DmTrackDef2, FragmentRef,
+    Actor, ActorDef, DmSpriteDef2, DmSpriteDef2FaceEntry, DmTrackDef2, FragmentRef, FragmentType,
     MaterialDef, RenderMethod, SimpleSpriteDef, SimpleSpriteDefFlags, WldDoc,
 };
-use std::error::Error;
-
-pub struct WldError;
 
 pub struct Wld(WldDoc);
 
 /// Load and parse a wld file from a slice.
-pub fn load(data: &[u8]) -> Result<Wld, Box<dyn Error>> {
-    Ok(Wld::load(data))
+pub fn load(data: &[u8]) -> Result<Wld, WldError> {
+    Wld::load(data)
 }
 
 impl Wld {
-    // FIXME: Handle errors, do not panic!
-    fn load(data: &[u8]) -> Wld {
-        match WldDoc::parse(data) {
-            Ok(wld_doc) => Wld(wld_doc),
-            Err(err) => panic!("Failed to parse Wld: {:?}", err),
-        }
+    fn load(data: &[u8]) -> Result<Wld, WldError> {
+        WldDoc::parse(data).map(Wld).map_err(WldError::new)
     }
 
     /// Iterate over all meshes in the wld file.
@@ -120,7 +118,7 @@ impl<'a> MeshAnimatedVertices<'a> {
 
     /// The "frames" of the animated mesh; each being an array of new vertex positions
     pub fn frames(&self) -> Vec<Vec<[f32; 3]>> {
-        let scale = 1.0 / (1 << self.fragment.scale) as f32;
+        let scale = 0.5f32.powi(self.fragment.scale.into());
         self.fragment
             .frames
             .iter()
@@ -153,7 +151,7 @@ impl<'a> Mesh<'a> {
 
     /// The positions of the vertices that make up this mesh.
     pub fn positions(&self) -> Vec<[f32; 3]> {
-        let scale = 1.0 / (1 << self.fragment.scale) as f32;
+        let scale = 0.5f32.powi(self.fragment.scale.into());
         self.fragment
             .positions
             .iter()
@@ -219,20 +217,15 @@ impl<'a> Mesh<'a> {
             .collect()
     }
 
-    /// A list of materials used by this mesh.
+    /// A list of materials used by this mesh. Materials that can't be found are left out.
     pub fn materials(&self) -> Vec<Material<'_>> {
-        let material_list = self
-            .doc
-            .get(&self.fragment.material_list_ref)
-            .expect("Invalid material list reference");
+        let Some(material_list) = self.doc.get(&self.fragment.material_list_ref) else {
+            return vec![];
+        };
         material_list
             .fragments
             .iter()
-            .map(|fragment_ref| {
-                self.doc
-                    .get(fragment_ref)
-                    .expect("Invalid material reference")
-            })
+            .filter_map(|fragment_ref| self.doc.get(fragment_ref))
             .map(|fragment| Material {
                 doc: self.doc,
                 fragment,
@@ -240,26 +233,22 @@ impl<'a> Mesh<'a> {
             .collect()
     }
 
-    /// Primitives belonging to this mesh.
+    /// Primitives belonging to this mesh. Faces of a primitive that run past the end of
+    /// the mesh's faces are left out.
     pub fn primitives(&self) -> Vec<Primitive<'_>> {
+        let faces = &self.fragment.faces;
         let mut pos = 0;
         self.fragment
             .face_material_groups
             .iter()
             .enumerate()
             .map(|(index, (poly_count, material_idx))| {
-                let count = *poly_count as usize;
-                let next_pos = pos + count;
-                let batch = pos..next_pos;
-                pos = next_pos;
+                let start = pos.min(faces.len());
+                pos += *poly_count as usize;
                 Primitive {
                     mesh: self,
                     index,
-                    fragments: self
-                        .fragment
-                        .faces
-                        .get(batch)
-                        .expect("Primitive fragments out of range"),
+                    fragments: &faces[start..pos.min(faces.len())],
                     material_idx: *material_idx as usize,
                 }
             })
@@ -315,9 +304,12 @@ impl<'a> Primitive<'a> {
         self.mesh.texture_coordinates()
     }
 
-    /// The material that this primitive uses.
-    pub fn material(&self) -> Material<'_> {
-        self.mesh.materials().remove(self.material_idx)
+    /// The material that this primitive uses, if it can be found.
+    pub fn material(&self) -> Option<Material<'_>> {
+        let doc = self.mesh.doc;
+        let material_list = doc.get(&self.mesh.fragment.material_list_ref)?;
+        let fragment = doc.get(material_list.fragments.get(self.material_idx)?)?;
+        Some(Material { doc, fragment })
     }
 
     /// The index of this primitive in its parent mesh.
@@ -407,7 +399,13 @@ pub struct ObjectLocation<'a> {
 
 impl<'a> ObjectLocation<'a> {
     pub fn model_name(&self) -> Option<&str> {
-        self.doc.get_string(self.fragment.actor_def_reference)
+        match self.fragment.actor_def_reference {
+            FragmentRef::Name(name_ref, _) => self.doc.get_string(name_ref),
+            ref index => {
+                let actor_def = self.doc.get(index)?;
+                self.doc.get_string(actor_def.name_reference)
+            }
+        }
     }
 
     /// The world position of the object.  This must be combined with the offset of the mesh itself.
@@ -495,26 +493,163 @@ impl<'a> Model<'a> {
         })
     }
 
-    // FIXME: I think casting the fragments to DmSpriteDef2 can be done in some better way, but this works for me at the moment.
     /// Follow the fragment reference to find the DmSpriteDef2
     fn get_mesh_fragment(&self) -> Option<&DmSpriteDef2> {
-        let fragment_ref = *self.fragment.fragment_references.first()?;
-        let fragment_ref: FragmentRef<DmSprite> = FragmentRef::new(fragment_ref as i32);
-        let fragment = self.doc.get(&fragment_ref)?;
+        let fragment_ref = self.fragment.fragment_references.first()?;
+        let FragmentType::DmSprite(fragment) = self.doc.get(fragment_ref)? else {
+            return None;
+        };
         self.doc.get(&fragment.reference)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    #[cfg(feature = "gamedata")]
     use super::*;
+    use parser::FragmentGame;
+    use parser::tests::wld_with_fragments;
+
+    #[test]
+    fn it_fails_to_load_truncated_data() {
+        let data = &wld_with_fragments(&[], &[])[..12];
+        let err = load(data).err().unwrap();
+
+        assert_eq!(err.errors().len(), 1);
+        assert_eq!(err.errors()[0].index(), None);
+        assert_eq!(err.errors()[0].file_offset(), Some(12));
+    }
+
+    #[test]
+    fn it_reports_every_bad_fragment() {
+        let data = wld_with_fragments(&[], &[(0x99, &[0; 4]), (0x30, &[0; 4]), (0x99, &[0; 4])]);
+        let err = load(&data).err().unwrap();
+
+        let errors = err.errors();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].index(), Some(0));
+        assert_eq!(errors[0].fragment_type(), Some(0x99));
+        assert_eq!(errors[0].file_offset(), Some(28 + 4 + 8));
+        assert_eq!(errors[1].index(), Some(1));
+        assert_eq!(errors[1].fragment_type(), Some(0x30));
+        assert_eq!(errors[1].file_offset(), Some(28 + 4 + 12 + 8 + 4));
+        assert_eq!(errors[2].index(), Some(2));
+        assert!(err.to_string().contains("and 2 more error(s)"));
+    }
+
+    fn load_fixture() -> Wld {
+        load(&fixtures::wld(FragmentGame::EverQuest)).unwrap()
+    }
+
+    #[test]
+    fn it_loads_meshes() {
+        let wld = load_fixture();
+        let meshes = wld.meshes().collect::<Vec<_>>();
+
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes[0].name(), Some("CRATE_DMSPRITEDEF"));
+        assert_eq!(meshes[0].center(), (10.0, 0.0, 5.0));
+        assert_eq!(
+            meshes[0].positions(),
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0],
+                [0.0, 1.0, 0.0]
+            ]
+        );
+        assert_eq!(meshes[0].normals()[0], [0.0, 1.0, 0.0]);
+        assert_eq!(meshes[0].texture_coordinates()[3], [1.0, 1.0]);
+        assert_eq!(meshes[0].indices(), vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(meshes[0].collision_indices(), vec![0, 1, 2]);
+        assert!(meshes[0].animated_vertices().is_none());
+
+        let primitives = meshes[0].primitives();
+        assert_eq!(primitives.len(), 2);
+        assert_eq!(primitives[1].index(), 1);
+        assert_eq!(primitives[1].indices(), vec![0, 2, 3]);
+        assert_eq!(primitives[1].material().unwrap().name(), Some("WATER_MDF"));
+
+        let animated_vertices = meshes[1].animated_vertices().unwrap();
+        assert_eq!(animated_vertices.name(), Some("CRATE_DMTRACKDEF"));
+        assert_eq!(animated_vertices.frames().len(), 2);
+        assert_eq!(animated_vertices.frames()[1][1], [2.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn it_skips_mesh_references_that_cannot_be_found() {
+        let mut wld_doc = fixtures::wld_doc(FragmentGame::EverQuest);
+        {
+            let mut meshes = wld_doc.fragment_iter_mut::<DmSpriteDef2>();
+            let mesh = meshes.next().unwrap();
+            mesh.face_material_groups.push((100, 9));
+            let mesh = meshes.next().unwrap();
+            mesh.material_list_ref = FragmentRef::new(1000);
+        }
+        let wld = Wld(wld_doc);
+        let meshes = wld.meshes().collect::<Vec<_>>();
+
+        let primitives = meshes[0].primitives();
+        assert_eq!(primitives.len(), 3);
+        assert_eq!(primitives[2].indices(), Vec::<u32>::new());
+        assert!(primitives[2].material().is_none());
+
+        assert!(meshes[1].materials().is_empty());
+        assert!(meshes[1].primitives()[0].material().is_none());
+    }
+
+    #[test]
+    fn it_loads_materials() {
+        let wld = load_fixture();
+        let materials = wld.materials().collect::<Vec<_>>();
+
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].name(), Some("CRATE_MDF"));
+        let texture = materials[0].base_color_texture().unwrap();
+        assert_eq!(texture.name(), Some("CRATE_SPRITE"));
+        assert_eq!(texture.source(), Some("crate.bmp".to_string()));
+
+        let texture = materials[1].base_color_texture().unwrap();
+        assert!(texture.flags().is_animated());
+        assert_eq!(
+            texture.iter_sources().collect::<Vec<_>>(),
+            vec!["water1.bmp", "water2.bmp"]
+        );
+    }
+
+    #[test]
+    fn it_loads_models() {
+        let wld = load_fixture();
+        let models = wld.models().collect::<Vec<_>>();
+
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].name(), Some("CRATE_ACTORDEF"));
+        assert_eq!(models[0].type_name(), Some("SPRITECALLBACK"));
+        assert_eq!(models[0].mesh().unwrap().name(), Some("CRATE_DMSPRITEDEF"));
+        // Skeletal models refer to a hierarchical sprite rather than a mesh
+        assert_eq!(models[1].name(), Some("ELF_ACTORDEF"));
+        assert!(models[1].mesh().is_none());
+    }
+
+    #[test]
+    fn it_loads_objects() {
+        let wld = load_fixture();
+        let objects = wld.objects().collect::<Vec<_>>();
+
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0].model_name(), Some("CRATE_ACTORDEF"));
+        assert_eq!(objects[0].center(), (10.0, 0.0, 5.0));
+        assert_eq!(objects[0].rotation(), (90.0, 0.0, 0.0));
+        assert_eq!(objects[0].scale(), (0.5, 2.0));
+        // Referring to its model by name
+        assert_eq!(objects[1].model_name(), Some("ELF_ACTORDEF"));
+        assert_eq!(objects[1].center(), (0.0, 0.0, 0.0));
+    }
 
     #[cfg(feature = "gamedata")]
     #[test]
     fn it_builds_meshes() {
         let wld_data = &include_bytes!("../fixtures/gamedata/gfaydark.wld")[..];
-        let wld = Wld::load(wld_data);
+        let wld = Wld::load(wld_data).unwrap();
         let meshes = wld.meshes().collect::<Vec<_>>();
 
         assert_eq!(meshes.len(), 1597);
@@ -534,7 +669,7 @@ mod tests {
     #[test]
     fn it_builds_materials() {
         let wld_data = &include_bytes!("../fixtures/gamedata/gfaydark.wld")[..];
-        let wld = Wld::load(wld_data);
+        let wld = Wld::load(wld_data).unwrap();
         let materials = wld.materials().collect::<Vec<_>>();
 
         assert_eq!(materials.len(), 33);
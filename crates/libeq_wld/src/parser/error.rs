@@ -1,72 +1,266 @@
+use std::error::Error;
+use std::fmt;
+
 use nom::error::{ContextError, ErrorKind, ParseError};
 
-use super::FragmentHeader;
+use super::fragment_type_name;
+
+/// The maximum number of bytes kept from the input as an excerpt of where an error occurred.
+const EXCERPT_LEN: usize = 16;
 
-#[derive(Debug)]
-pub enum WldDocError<'a> {
+#[derive(Debug, Clone, PartialEq)]
+pub enum WldDocError {
+    /// An error from one of the low level parsers, including [super::FragmentParser::parse].
     Parse {
-        input: &'a [u8],
+        /// The number of bytes of input left when the error occurred. Subtracting this
+        /// from the length of the input gives the offset of the error.
+        remaining: usize,
         message: String,
     },
+    /// An error outside of any fragment body, in the file header, string hash
+    /// or table of fragment headers.
+    ParseHeader { offset: usize, message: String },
     ParseFragment {
         index: usize,
+        fragment_type: u32,
+        /// The offset of the fragment body from the start of the file.
+        fragment_offset: usize,
+        /// The offset of the error from the start of the fragment body.
         offset: usize,
-        header: FragmentHeader<'a>,
         message: String,
+        /// A few bytes of the fragment body starting at `offset`.
+        excerpt: Vec<u8>,
     },
     UnknownFragment {
         index: usize,
-        header: FragmentHeader<'a>,
+        fragment_type: u32,
+        /// The offset of the fragment body from the start of the file.
+        fragment_offset: usize,
+        /// The first few bytes of the fragment body.
+        excerpt: Vec<u8>,
     },
 }
 
-impl ContextError<&'_ [u8]> for WldDocError<'_> {
-    fn add_context(_input: &'_ [u8], ctx: &'static str, other: Self) -> Self {
-        match other {
-            Self::Parse { input, message } => Self::Parse {
-                input,
-                message: format!("{}: {}", ctx, message),
+impl WldDocError {
+    /// Take a short excerpt of `data` to include in an error.
+    pub(crate) fn excerpt(data: &[u8]) -> Vec<u8> {
+        data[..data.len().min(EXCERPT_LEN)].to_vec()
+    }
+
+    /// The index of the fragment the error occurred in, if any.
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            Self::ParseFragment { index, .. } | Self::UnknownFragment { index, .. } => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// The type id of the fragment the error occurred in, if any.
+    pub fn fragment_type(&self) -> Option<u32> {
+        match self {
+            Self::ParseFragment { fragment_type, .. }
+            | Self::UnknownFragment { fragment_type, .. } => Some(*fragment_type),
+            _ => None,
+        }
+    }
+
+    /// The offset of the error from the start of the file. This is `None` for
+    /// [WldDocError::Parse] errors, which only know how much input was left.
+    pub fn file_offset(&self) -> Option<usize> {
+        match self {
+            Self::Parse { .. } => None,
+            Self::ParseHeader { offset, .. } => Some(*offset),
+            Self::ParseFragment {
+                fragment_offset,
+                offset,
+                ..
+            } => Some(fragment_offset + offset),
+            Self::UnknownFragment {
+                fragment_offset, ..
+            } => Some(*fragment_offset),
+        }
+    }
+
+    /// Prefix the message of the error with more context.
+    fn with_context(self, context: impl fmt::Display) -> Self {
+        match self {
+            Self::Parse { remaining, message } => Self::Parse {
+                remaining,
+                message: format!("{}: {}", context, message),
+            },
+            Self::ParseHeader { offset, message } => Self::ParseHeader {
+                offset,
+                message: format!("{}: {}", context, message),
             },
-            _ => panic!("Only WldDocError::Parse errors can be given context"),
+            Self::ParseFragment {
+                index,
+                fragment_type,
+                fragment_offset,
+                offset,
+                message,
+                excerpt,
+            } => Self::ParseFragment {
+                index,
+                fragment_type,
+                fragment_offset,
+                offset,
+                message: format!("{}: {}", context, message),
+                excerpt,
+            },
+            e @ Self::UnknownFragment { .. } => e,
+        }
+    }
+}
+
+impl fmt::Display for WldDocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe_fragment =
+            |index: usize, fragment_type: u32| match fragment_type_name(fragment_type) {
+                Some(name) => format!("fragment {} ({:#04x} {})", index, fragment_type, name),
+                None => format!("fragment {} ({:#04x})", index, fragment_type),
+            };
+
+        match self {
+            Self::Parse { remaining, message } => {
+                write!(f, "{} ({} bytes remaining)", message, remaining)
+            }
+            Self::ParseHeader { offset, message } => {
+                write!(f, "at offset {:#x}: {}", offset, message)
+            }
+            Self::ParseFragment {
+                index,
+                fragment_type,
+                fragment_offset,
+                offset,
+                message,
+                excerpt,
+            } => write!(
+                f,
+                "{} at offset {:#x} (body offset {:#x}): {} [{}]",
+                describe_fragment(*index, *fragment_type),
+                fragment_offset + offset,
+                offset,
+                message,
+                format_hex(excerpt)
+            ),
+            Self::UnknownFragment {
+                index,
+                fragment_type,
+                fragment_offset,
+                excerpt,
+            } => write!(
+                f,
+                "{} at offset {:#x}: unknown fragment type [{}]",
+                describe_fragment(*index, *fragment_type),
+                fragment_offset,
+                format_hex(excerpt)
+            ),
         }
     }
 }
 
-impl<'a> From<nom::Err<WldDocError<'a>>> for WldDocError<'a> {
-    fn from(e: nom::Err<WldDocError<'a>>) -> Self {
+impl Error for WldDocError {}
+
+fn format_hex(data: &[u8]) -> String {
+    data.iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl ContextError<&'_ [u8]> for WldDocError {
+    fn add_context(_input: &'_ [u8], ctx: &'static str, other: Self) -> Self {
+        other.with_context(ctx)
+    }
+}
+
+impl From<nom::Err<WldDocError>> for WldDocError {
+    fn from(e: nom::Err<WldDocError>) -> Self {
         match e {
             nom::Err::Error(e) => e,
             nom::Err::Failure(e) => e,
-            // No need for `Incomplete`, we can assume this parser will
-            // always be given the full data.
-            nom::Err::Incomplete(_needed) => panic!("No support for parsing of incomplete data."),
+            // The parsers are always given the full data so this shouldn't
+            // happen, but report it rather than giving up.
+            nom::Err::Incomplete(needed) => Self::Parse {
+                remaining: 0,
+                message: format!("incomplete data: {:?}", needed),
+            },
         }
     }
 }
 
-impl<'a> ParseError<&'a [u8]> for WldDocError<'a> {
-    fn from_error_kind(input: &'a [u8], kind: ErrorKind) -> Self {
+impl ParseError<&[u8]> for WldDocError {
+    fn from_error_kind(input: &[u8], kind: ErrorKind) -> Self {
         Self::Parse {
-            input,
+            remaining: input.len(),
             message: format!("{:?}", kind),
         }
     }
 
-    fn append(input: &'a [u8], kind: ErrorKind, other: Self) -> Self {
+    fn append(_input: &[u8], kind: ErrorKind, other: Self) -> Self {
+        // Keep the position of the innermost error, it is the most precise.
         match other {
-            Self::Parse { message, .. } => Self::Parse {
-                input,
+            Self::Parse { remaining, message } => Self::Parse {
+                remaining,
                 message: format!("{:?} -> {}", kind, message),
             },
-            _ => panic!("Only WldDocError::Parse errors can be appended to"),
+            other => other.with_context(format!("{:?}", kind)),
         }
     }
 
-    fn from_char(_input: &[u8], _: char) -> Self {
-        panic!("This is a binary parser");
+    fn from_char(input: &[u8], c: char) -> Self {
+        Self::Parse {
+            remaining: input.len(),
+            message: format!("expected {:?}", c),
+        }
     }
 
     fn or(self, other: Self) -> Self {
         other
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_displays_fragment_errors() {
+        let error = WldDocError::ParseFragment {
+            index: 3,
+            fragment_type: 0x30,
+            fragment_offset: 0x40,
+            offset: 4,
+            message: "Eof".to_string(),
+            excerpt: vec![0xde, 0xad],
+        };
+
+        assert_eq!(error.file_offset(), Some(0x44));
+        assert_eq!(
+            error.to_string(),
+            "fragment 3 (0x30 MaterialDef) at offset 0x44 (body offset 0x4): Eof [de ad]"
+        );
+    }
+
+    #[test]
+    fn it_does_not_panic_on_context() {
+        let error = WldDocError::UnknownFragment {
+            index: 0,
+            fragment_type: 0x99,
+            fragment_offset: 0,
+            excerpt: vec![],
+        };
+        let error = WldDocError::add_context(&[], "ctx", error);
+        let error = WldDocError::append(&[], ErrorKind::Count, error);
+        assert_eq!(error.index(), Some(0));
+
+        let error: WldDocError = nom::Err::Incomplete(nom::Needed::Unknown).into();
+        assert!(matches!(error, WldDocError::Parse { remaining: 0, .. }));
+    }
+
+    #[test]
+    fn it_is_owned() {
+        fn assert_owned<T: Send + Sync + 'static>() {}
+        assert_owned::<WldDocError>();
+    }
+}
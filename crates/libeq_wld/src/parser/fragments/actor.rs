@@ -1,9 +1,12 @@
 use std::any::Any;
 
-use crate::parser::strings::{decode_string, encode_string};
+use crate::parser::strings::EncodedString;
 
 use super::common::Location;
-use super::{DmRGBTrack, Fragment, FragmentParser, FragmentRef, Sphere, StringReference, WResult};
+use super::{
+    ActorDef, DmRGBTrack, Fragment, FragmentParser, FragmentRef, FragmentReference, Sphere,
+    StringReference, WResult, set_flag,
+};
 
 use nom::Parser;
 use nom::multi::count;
@@ -21,7 +24,7 @@ pub struct Actor {
     /// When used in main zone files, the reference points to a 0x14 Player Info fragment. When used for static (placeable) objects,
     /// the reference is a string reference (not a fragment reference) and points to a “magic” string.
     /// It typically contains the name of the object with “_ACTORDEF” appended to the end.
-    pub actor_def_reference: StringReference, // FIXME: This can be a FragmentRef sometimes, as stated above
+    pub actor_def_reference: FragmentRef<ActorDef>,
 
     /// Typically 0x2E when used in main zone files and 0x32E when
     /// used for placeable objects.
@@ -67,7 +70,7 @@ pub struct Actor {
     pub user_data_size: u32,
 
     /// USERDATA %s
-    pub user_data: String,
+    pub user_data: EncodedString,
 }
 
 impl FragmentParser for Actor {
@@ -78,7 +81,7 @@ impl FragmentParser for Actor {
 
     fn parse(input: &[u8]) -> WResult<'_, Actor> {
         let (i, name_reference) = StringReference::parse(input)?;
-        let (i, actor_def_reference) = StringReference::parse(i)?;
+        let (i, actor_def_reference) = FragmentRef::parse(i)?;
         let (i, flags) = ActorInstFlags::parse(i)?;
         let (i, sphere_reference) = FragmentRef::parse(i)?;
         let (i, current_action) = if flags.has_current_action() {
@@ -128,7 +131,7 @@ impl FragmentParser for Actor {
                 sound_name_reference,
                 vertex_color_reference,
                 user_data_size,
-                user_data: decode_string(&user_data).trim_end_matches("\0").to_string(),
+                user_data: EncodedString::decode(&user_data),
             },
         ))
     }
@@ -138,7 +141,7 @@ impl Fragment for Actor {
     fn to_bytes(&self) -> Vec<u8> {
         let user_data_size = self.user_data_size as usize;
         let padding = (4 - user_data_size % 4) % 4;
-        let mut user_data = encode_string(&format!("{}{}", &self.user_data, "\0"));
+        let mut user_data = self.user_data.encode(user_data_size);
         user_data.resize(user_data_size + padding, 0);
 
         [
@@ -171,13 +174,86 @@ impl Fragment for Actor {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn string_references(&self) -> Vec<StringReference> {
+        std::iter::once(self.name_reference)
+            .chain(self.sound_name_reference)
+            .collect()
+    }
+
+    fn remap_string_references(&mut self, f: &mut dyn FnMut(StringReference) -> StringReference) {
+        self.name_reference = f(self.name_reference);
+        self.sound_name_reference = self.sound_name_reference.map(f);
+    }
+
+    fn normalize(&mut self) {
+        set_flag(
+            &mut self.flags.0,
+            ActorInstFlags::HAS_CURRENT_ACTION,
+            self.current_action.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            ActorInstFlags::HAS_LOCATION,
+            self.location.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            ActorInstFlags::HAS_BOUNDING_RADIUS,
+            self.bounding_radius.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            ActorInstFlags::HAS_SCALE_FACTOR,
+            self.scale_factor.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            ActorInstFlags::HAS_SOUND,
+            self.sound_name_reference.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            ActorInstFlags::HAS_VERTEX_COLOR_REFERENCE,
+            self.vertex_color_reference.is_some(),
+        );
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        [
+            Some(self.actor_def_reference.reference("actor_def_reference")),
+            Some(self.sphere_reference.reference("sphere_reference")),
+            self.vertex_color_reference
+                .as_ref()
+                .map(|r| r.reference("vertex_color_reference")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.actor_def_reference.remap(f);
+        self.sphere_reference.remap(f);
+        if let Some(r) = &mut self.vertex_color_reference {
+            r.remap(f);
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -200,6 +276,10 @@ impl ActorInstFlags {
     const SPRITE_VOLUME_ONLY: u32 = 0x80;
     const HAS_VERTEX_COLOR_REFERENCE: u32 = 0x100;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (remaining, raw_flags) = le_u32(input)?;
         Ok((remaining, Self(raw_flags)))
@@ -251,7 +331,7 @@ mod tests {
         // flags: HAS_LOCATION (0x02) | HAS_BOUNDING_RADIUS (0x04) | HAS_SCALE_FACTOR (0x08) | ACTIVE (0x20) = 0x2e = 46
         Actor {
             name_reference: StringReference::new(0),
-            actor_def_reference: StringReference::new(4640), // FIXME: this is a FragmentRef
+            actor_def_reference: FragmentRef::new(4640),
             flags: ActorInstFlags(46),
             sphere_reference: FragmentRef::new(4641),
             current_action: None,
@@ -269,7 +349,7 @@ mod tests {
             sound_name_reference: None,
             vertex_color_reference: None,
             user_data_size: 0,
-            user_data: String::new(),
+            user_data: EncodedString::default(),
         }
     }
 
@@ -277,7 +357,7 @@ mod tests {
         // flags: HAS_LOCATION (0x02) | HAS_BOUNDING_RADIUS (0x04) | HAS_SCALE_FACTOR (0x08) | ACTIVE (0x20) | HAS_VERTEX_COLOR_REFERENCE (0x100) = 0x32e = 814
         Actor {
             name_reference: StringReference::new(0),
-            actor_def_reference: StringReference::new(-10),
+            actor_def_reference: FragmentRef::new(-10),
             flags: ActorInstFlags(814),
             sphere_reference: FragmentRef::new(0),
             current_action: None,
@@ -295,7 +375,7 @@ mod tests {
             sound_name_reference: None,
             vertex_color_reference: Some(FragmentRef::new(2)),
             user_data_size: 0,
-            user_data: String::new(),
+            user_data: EncodedString::default(),
         }
     }
 
@@ -303,7 +383,7 @@ mod tests {
         // flags: 0 (no optional fields except userdata which is always present)
         Actor {
             name_reference: StringReference::new(0),
-            actor_def_reference: StringReference::new(-1),
+            actor_def_reference: FragmentRef::new(-1),
             flags: ActorInstFlags(0),
             sphere_reference: FragmentRef::new(0),
             current_action: None,
@@ -313,7 +393,7 @@ mod tests {
             sound_name_reference: None,
             vertex_color_reference: None,
             user_data_size: 5, // "data" + null terminator
-            user_data: String::from("data"),
+            user_data: "data".into(),
         }
     }
 
@@ -323,8 +403,7 @@ mod tests {
         let (remaining, frag) = Actor::parse(data).unwrap();
 
         assert_eq!(frag.name_reference, StringReference::new(0));
-        // FIXME: this is a FragmentRef
-        assert_eq!(frag.actor_def_reference, StringReference::new(4640));
+        assert_eq!(frag.actor_def_reference, FragmentRef::new(4640));
         assert_eq!(frag.flags, ActorInstFlags(46));
         assert_eq!(frag.sphere_reference, FragmentRef::new(4641));
         assert_eq!(frag.current_action, None);
@@ -346,7 +425,7 @@ mod tests {
         assert_eq!(frag.vertex_color_reference, None);
         assert_eq!(frag.user_data_size, 0);
         assert_eq!(frag.user_data, String::new());
-        assert_eq!(remaining, vec![]);
+        assert_eq!(remaining.len(), 0);
     }
 
     #[test]
@@ -355,7 +434,7 @@ mod tests {
         let (remaining, frag) = Actor::parse(data).unwrap();
 
         assert_eq!(frag.name_reference, StringReference::new(0));
-        assert_eq!(frag.actor_def_reference, StringReference::new(-10));
+        assert_eq!(frag.actor_def_reference, FragmentRef::new(-10));
         assert_eq!(frag.flags, ActorInstFlags(814));
         assert_eq!(frag.sphere_reference, FragmentRef::new(0));
         assert_eq!(frag.current_action, None);
@@ -377,7 +456,7 @@ mod tests {
         assert_eq!(frag.vertex_color_reference, Some(FragmentRef::new(2)));
         assert_eq!(frag.user_data_size, 0);
         assert_eq!(frag.user_data, String::new());
-        assert_eq!(remaining, vec![]);
+        assert_eq!(remaining.len(), 0);
     }
 
     #[test]
@@ -415,4 +494,19 @@ mod tests {
 
         assert_eq!(parsed.to_bytes(), data);
     }
+
+    #[test]
+    fn it_normalizes_flags() {
+        let mut frag = fixture_zone();
+        frag.location = None;
+        frag.vertex_color_reference = Some(FragmentRef::new(2));
+        frag.normalize();
+
+        assert!(!frag.flags.has_location());
+        assert!(frag.flags.has_vertex_color_reference());
+        let parsed = Actor::parse(&frag.to_bytes()).unwrap().1;
+        assert_eq!(parsed.location, None);
+        assert_eq!(parsed.vertex_color_reference, Some(FragmentRef::new(2)));
+        assert_eq!(parsed.to_bytes(), frag.to_bytes());
+    }
 }
@@ -1,7 +1,10 @@
 use std::any::Any;
 
 use super::common::Location;
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{
+    AnyFragmentRef, Fragment, FragmentCount, FragmentParser, FragmentRef, FragmentReference,
+    StringReference, WResult, remap_raw, set_flag,
+};
 
 use nom::Parser;
 use nom::multi::count;
@@ -51,7 +54,7 @@ pub struct ActorDef {
     /// reference, it points to a 0x11 [HierarchicalSprite] fragment.
     /// This also has been seen to point to a 0x07 [Sprite2D] fragment
     /// (e.g. coins and blood spots).
-    pub fragment_references: Vec<u32>,
+    pub fragment_references: Vec<AnyFragmentRef>,
 
     pub unknown: u32,
 }
@@ -80,7 +83,8 @@ impl FragmentParser for ActorDef {
             (i, None)
         };
         let (i, actions) = count(Action::parse, action_count as usize).parse(i)?;
-        let (i, fragment_references) = count(le_u32, fragment_reference_count as usize).parse(i)?;
+        let (i, fragment_references) =
+            count(FragmentRef::parse, fragment_reference_count as usize).parse(i)?;
         let (i, unknown) = le_u32(i)?;
 
         Ok((
@@ -123,7 +127,7 @@ impl Fragment for ActorDef {
             &self
                 .fragment_references
                 .iter()
-                .flat_map(|f| f.to_le_bytes())
+                .flat_map(|f| f.to_bytes())
                 .collect::<Vec<_>>()[..],
             &self.unknown.to_le_bytes()[..],
         ]
@@ -134,13 +138,80 @@ impl Fragment for ActorDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn string_references(&self) -> Vec<StringReference> {
+        vec![self.name_reference, self.callback_name_reference]
+    }
+
+    fn remap_string_references(&mut self, f: &mut dyn FnMut(StringReference) -> StringReference) {
+        self.name_reference = f(self.name_reference);
+        self.callback_name_reference = f(self.callback_name_reference);
+    }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![
+            FragmentCount::new(
+                "action_count",
+                self.action_count as usize,
+                self.actions.len(),
+            ),
+            FragmentCount::new(
+                "fragment_reference_count",
+                self.fragment_reference_count as usize,
+                self.fragment_references.len(),
+            ),
+        ]
+    }
+
+    fn normalize(&mut self) {
+        self.action_count = self.actions.len() as u32;
+        self.fragment_reference_count = self.fragment_references.len() as u32;
+        set_flag(
+            &mut self.flags.0,
+            ActorDefFlags::HAS_CURRENT_ACTION,
+            self.current_action.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            ActorDefFlags::HAS_LOCATION,
+            self.location.is_some(),
+        );
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        std::iter::once(FragmentReference::new(
+            "bounds_reference",
+            self.bounds_reference as i32,
+        ))
+        .chain(
+            self.fragment_references
+                .iter()
+                .map(|r| FragmentReference::new("fragment_references", r.value())),
+        )
+        .collect()
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        remap_raw(&mut self.bounds_reference, f);
+        for r in &mut self.fragment_references {
+            r.remap(f);
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -159,6 +230,10 @@ impl ActorDefFlags {
     const ACTIVE_GEOMETRY: u32 = 0x40;
     const SPRITE_VOLUME_ONLY: u32 = 0x80;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (remaining, raw_flags) = le_u32(input)?;
         Ok((remaining, Self(raw_flags)))
@@ -247,7 +322,7 @@ mod tests {
                 unknown: 0,
                 levels_of_detail_distances: vec![1e30],
             }],
-            fragment_references: vec![4639],
+            fragment_references: vec![FragmentRef::new(4639)],
             unknown: 0,
         }
     }
@@ -273,7 +348,7 @@ mod tests {
                 levels_of_detail_distances: vec![1e30]
             }]
         );
-        assert_eq!(frag.fragment_references, vec![4639]);
+        assert_eq!(frag.fragment_references, vec![FragmentRef::new(4639)]);
         assert_eq!(frag.unknown, 0);
     }
 
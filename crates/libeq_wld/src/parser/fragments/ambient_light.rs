@@ -1,6 +1,9 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, FragmentRef, Light, StringReference, WResult};
+use super::{
+    Fragment, FragmentCount, FragmentParser, FragmentRef, FragmentReference, Light,
+    StringReference, WResult,
+};
 
 use nom::Parser;
 use nom::multi::count;
@@ -78,13 +81,41 @@ impl Fragment for AmbientLight {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "region_count",
+            self.region_count as usize,
+            self.regions.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.region_count = self.regions.len() as u32;
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        vec![self.reference.reference("reference")]
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.reference.remap(f);
+    }
 }
 
 #[cfg(test)]
@@ -2,7 +2,7 @@ use std::any::Any;
 
 use nom::number::complete::{le_i32, le_u32};
 
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{Fragment, FragmentParser, FragmentReference, StringReference, WResult, remap_raw};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -54,11 +54,30 @@ impl Fragment for BlitSprite {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        vec![FragmentReference::new(
+            "blit_sprite_reference",
+            self.blit_sprite_reference as i32,
+        )]
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        remap_raw(&mut self.blit_sprite_reference, f);
+    }
 }
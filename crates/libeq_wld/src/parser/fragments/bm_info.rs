@@ -1,7 +1,7 @@
 use std::any::Any;
 
 use super::common::EncodedFilename;
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{Fragment, FragmentCount, FragmentParser, StringReference, WResult};
 
 use nom::Parser;
 use nom::multi::count;
@@ -47,7 +47,7 @@ impl FragmentParser for BmInfo {
         let (i, name_reference) = StringReference::parse(input)?;
         let (i, entry_count) = le_u32(i)?;
         let (remaining, entries) =
-            count(EncodedFilename::parse, (entry_count + 1) as usize).parse(i)?;
+            count(EncodedFilename::parse, entry_count as usize + 1).parse(i)?;
         Ok((
             remaining,
             BmInfo {
@@ -83,13 +83,34 @@ impl Fragment for BmInfo {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        // The field holds one less than the number of entries.
+        vec![FragmentCount::new(
+            "entry_count",
+            self.entry_count as usize + 1,
+            self.entries.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.entry_count = self.entries.len().saturating_sub(1) as u32;
+    }
 }
 
 #[cfg(test)]
@@ -103,7 +124,7 @@ mod tests {
             entry_count: 0,
             entries: vec![EncodedFilename {
                 name_length: 11,
-                file_name: "SGRASS.BMP".to_string(),
+                file_name: "SGRASS.BMP".into(),
             }],
         }
     }
@@ -116,11 +137,11 @@ mod tests {
             entries: vec![
                 EncodedFilename {
                     name_length: 12,
-                    file_name: "TEXTURE.BMP".to_string(),
+                    file_name: "TEXTURE.BMP".into(),
                 },
                 EncodedFilename {
                     name_length: 13,
-                    file_name: "TEXTURE2.DDS".to_string(),
+                    file_name: "TEXTURE2.DDS".into(),
                 },
             ],
         }
@@ -162,4 +183,33 @@ mod tests {
 
         assert_eq!(frag.to_bytes(), data);
     }
+
+    #[test]
+    fn it_counts_entries() {
+        let mut frag = fixture_multiple();
+        assert!(frag.counts().iter().all(|c| c.count == c.len));
+
+        frag.entries.pop();
+        assert_eq!(frag.counts()[0].count, 2);
+        assert_eq!(frag.counts()[0].len, 1);
+    }
+
+    #[test]
+    fn it_normalizes() {
+        let mut frag = fixture_multiple();
+        frag.entries.pop();
+        frag.normalize();
+
+        assert_eq!(frag.entry_count, 0);
+        let parsed = BmInfo::parse(&frag.to_bytes()).unwrap().1;
+        assert_eq!(parsed, frag);
+    }
+
+    #[test]
+    fn it_fails_on_entry_count_overflow() {
+        let mut data = fixture().to_bytes();
+        data[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(BmInfo::parse(&data).is_err());
+    }
 }
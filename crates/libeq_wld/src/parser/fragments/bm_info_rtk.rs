@@ -1,7 +1,7 @@
 use std::any::Any;
 
 use super::common::EncodedFilename;
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{Fragment, FragmentCount, FragmentParser, StringReference, WResult};
 
 use nom::Parser;
 use nom::multi::count;
@@ -38,7 +38,7 @@ impl FragmentParser for BmInfoRtk {
         let (i, rtk) = le_u32(i)?;
         let (i, size1) = le_u32(i)?;
         // TODO: This is hardcoded to one entry, is this all we need?
-        let (remaining, entries) = count(EncodedFilename::parse, (size1 + 1) as usize).parse(i)?;
+        let (remaining, entries) = count(EncodedFilename::parse, size1 as usize + 1).parse(i)?;
         Ok((
             remaining,
             BmInfoRtk {
@@ -70,13 +70,34 @@ impl Fragment for BmInfoRtk {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        // The field holds one less than the number of entries.
+        vec![FragmentCount::new(
+            "size1",
+            self.size1 as usize + 1,
+            self.entries.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.size1 = self.entries.len().saturating_sub(1) as u32;
+    }
 }
 
 #[cfg(test)]
@@ -90,7 +111,7 @@ mod tests {
             size1: 0,
             entries: vec![EncodedFilename {
                 name_length: 13, // "TEXTURE1.BMP" + null = 13 bytes
-                file_name: "TEXTURE1.BMP".to_string(),
+                file_name: "TEXTURE1.BMP".into(),
             }],
         }
     }
@@ -117,4 +138,26 @@ mod tests {
 
         assert_eq!(parsed.to_bytes(), data);
     }
+
+    #[test]
+    fn it_normalizes() {
+        let mut frag = fixture();
+        frag.entries.push(EncodedFilename {
+            name_length: 13,
+            file_name: "TEXTURE2.BMP".into(),
+        });
+        frag.normalize();
+
+        assert_eq!(frag.size1, 1);
+        let parsed = BmInfoRtk::parse(&frag.to_bytes()).unwrap().1;
+        assert_eq!(parsed, frag);
+    }
+
+    #[test]
+    fn it_fails_on_entry_count_overflow() {
+        let mut data = fixture().to_bytes();
+        data[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(BmInfoRtk::parse(&data).is_err());
+    }
 }
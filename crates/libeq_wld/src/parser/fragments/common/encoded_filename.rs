@@ -1,5 +1,5 @@
 use super::WResult;
-use crate::parser::strings::{decode_string, encode_string};
+use crate::parser::strings::{EncodedString, encode_string};
 
 use nom::Parser;
 use nom::multi::count;
@@ -24,10 +24,17 @@ pub struct EncodedFilename {
     /// used by a placeable object and not when the textures are in the main zone file.
     /// It is unknown whether the substitution depends on the presence and exact order
     /// of all four textures.
-    pub file_name: String,
+    pub file_name: EncodedString,
 }
 
 impl EncodedFilename {
+    pub fn new(file_name: &str) -> Self {
+        EncodedFilename {
+            name_length: (encode_string(file_name).len() + 1) as u16,
+            file_name: file_name.into(),
+        }
+    }
+
     pub fn parse(input: &[u8]) -> WResult<'_, EncodedFilename> {
         let (i, name_length) = le_u16(input)?;
         let (remaining, file_name) = count(le_u8, name_length as usize).parse(i)?;
@@ -35,7 +42,7 @@ impl EncodedFilename {
             remaining,
             EncodedFilename {
                 name_length,
-                file_name: decode_string(&file_name).trim_end_matches("\0").to_string(),
+                file_name: EncodedString::decode(&file_name),
             },
         ))
     }
@@ -43,7 +50,7 @@ impl EncodedFilename {
     pub fn to_bytes(&self) -> Vec<u8> {
         [
             &self.name_length.to_le_bytes()[..],
-            &encode_string(&format!("{}{}", &self.file_name, "\0"))[..],
+            &self.file_name.encode(self.name_length as usize)[..],
         ]
         .concat()
     }
@@ -1,6 +1,8 @@
 use super::WResult;
 
 use nom::Parser;
+use nom::combinator::map_opt;
+use nom::error::context;
 use nom::multi::count;
 use nom::number::complete::{le_f32, le_u32};
 
@@ -269,9 +271,7 @@ pub enum RenderMethod {
 
 impl RenderMethod {
     pub fn parse(input: &[u8]) -> WResult<'_, Self> {
-        let (remaining, raw_flags) = le_u32(input)?;
-
-        Ok((remaining, Self::from_u32(raw_flags)))
+        context("render_method", map_opt(le_u32, Self::from_u32)).parse(input)
     }
 
     fn as_u32(&self) -> u32 {
@@ -293,19 +293,21 @@ impl RenderMethod {
         }
     }
 
-    pub fn from_u32(raw_flags: u32) -> Self {
+    /// Decode the raw render method flags. Returns `None` for user defined render
+    /// methods with an unknown [MaterialType].
+    pub fn from_u32(raw_flags: u32) -> Option<Self> {
         if raw_flags >> 31 == 1 {
-            Self::UserDefined {
-                material_type: FromPrimitive::from_u32(raw_flags & !0x80000000).unwrap(),
-            }
+            Some(Self::UserDefined {
+                material_type: FromPrimitive::from_u32(raw_flags & !0x80000000)?,
+            })
         } else {
-            Self::Standard {
-                draw_style: FromPrimitive::from_u32(raw_flags & 0b11).unwrap(),
-                lighting: FromPrimitive::from_u32((raw_flags >> 2) & 0b111).unwrap(),
-                shading: FromPrimitive::from_u32((raw_flags >> 5) & 0b11).unwrap(),
-                texture_style: FromPrimitive::from_u32((raw_flags >> 7) & 0b1111).unwrap(),
+            Some(Self::Standard {
+                draw_style: FromPrimitive::from_u32(raw_flags & 0b11)?,
+                lighting: FromPrimitive::from_u32((raw_flags >> 2) & 0b111)?,
+                shading: FromPrimitive::from_u32((raw_flags >> 5) & 0b11)?,
+                texture_style: FromPrimitive::from_u32((raw_flags >> 7) & 0b1111)?,
                 unknown_bits: (raw_flags >> 11) & 0xfffff,
-            }
+            })
         }
     }
 
@@ -49,10 +49,18 @@ impl Fragment for DefaultPaletteFile {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
@@ -67,7 +75,7 @@ mod tests {
             name_reference: StringReference::new(0),
             entry: EncodedFilename {
                 name_length: 12,
-                file_name: String::from("palette.bmp"),
+                file_name: "palette.bmp".into(),
             },
         }
     }
@@ -81,7 +89,7 @@ mod tests {
             frag.entry,
             EncodedFilename {
                 name_length: 12,
-                file_name: String::from("palette.bmp")
+                file_name: "palette.bmp".into()
             }
         );
     }
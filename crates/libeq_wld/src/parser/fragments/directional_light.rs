@@ -1,6 +1,9 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, FragmentRef, Light, StringReference, WResult};
+use super::{
+    Fragment, FragmentCount, FragmentParser, FragmentRef, FragmentReference, Light,
+    StringReference, WResult,
+};
 
 use nom::Parser;
 use nom::multi::count;
@@ -85,13 +88,41 @@ impl Fragment for DirectionalLight {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "num_regions",
+            self.num_regions as usize,
+            self.regions.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.num_regions = self.regions.len() as u32;
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        vec![self.light_reference.reference("light_reference")]
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.light_reference.remap(f);
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -107,6 +138,10 @@ impl From<DirectionalLightFlags> for u32 {
 impl DirectionalLightFlags {
     const IS_STATIC: u32 = 0x20;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (i, raw_flags) = le_u32(input)?;
         Ok((i, Self(raw_flags)))
@@ -1,6 +1,6 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{Fragment, FragmentCount, FragmentParser, StringReference, WResult};
 
 use nom::Parser;
 use nom::multi::count;
@@ -100,13 +100,33 @@ impl Fragment for DmRGBTrackDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "vertex_color_count",
+            self.vertex_color_count as usize,
+            self.vertex_colors.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.vertex_color_count = self.vertex_colors.len() as u32;
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,9 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, FragmentRef, MaterialPalette, StringReference, WResult};
+use super::{
+    Fragment, FragmentCount, FragmentParser, FragmentRef, FragmentReference, MaterialPalette,
+    StringReference, WResult,
+};
 
 use nom::Parser;
 use nom::multi::count;
@@ -439,13 +442,71 @@ impl Fragment for DmSpriteDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![
+            FragmentCount::new(
+                "vertex_count",
+                self.vertex_count as usize,
+                self.vertices.len(),
+            ),
+            FragmentCount::new(
+                "texture_coordinate_count",
+                self.texture_coordinate_count as usize,
+                self.texture_coordinates.len(),
+            ),
+            FragmentCount::new(
+                "normal_count",
+                self.normal_count as usize,
+                self.vertex_normals.len(),
+            ),
+            FragmentCount::new(
+                "color_count",
+                self.color_count as usize,
+                self.vertex_colors.len(),
+            ),
+            FragmentCount::new("face_count", self.face_count as usize, self.faces.len()),
+            FragmentCount::new("meshop_count", self.meshop_count.into(), self.meshops.len()),
+            FragmentCount::new(
+                "skin_assignment_group_count",
+                self.skin_assignment_group_count as usize,
+                self.skin_assignment_groups.len(),
+            ),
+        ]
+    }
+
+    fn normalize(&mut self) {
+        self.vertex_count = self.vertices.len() as u32;
+        self.texture_coordinate_count = self.texture_coordinates.len() as u32;
+        self.normal_count = self.vertex_normals.len() as u32;
+        self.color_count = self.vertex_colors.len() as u32;
+        self.face_count = self.faces.len() as u32;
+        self.meshop_count = self.meshops.len() as u16;
+        self.skin_assignment_group_count = self.skin_assignment_groups.len() as u32;
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        vec![self.material_list_ref.reference("material_list_ref")]
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.material_list_ref.remap(f);
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
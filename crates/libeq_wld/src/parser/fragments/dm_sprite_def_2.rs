@@ -1,12 +1,14 @@
 use std::any::Any;
 
 use super::{
-    DmTrack, Fragment, FragmentParser, FragmentRef, MaterialPalette, StringReference, WResult,
+    DmTrack, Fragment, FragmentCount, FragmentParser, FragmentRef, FragmentReference,
+    MaterialPalette, StringReference, WResult, WldVersion,
 };
 
 use nom::Parser;
+use nom::bytes::complete::take;
 use nom::multi::count;
-use nom::number::complete::{le_f32, le_i8, le_i16, le_u8, le_u16, le_u32};
+use nom::number::complete::{le_f32, le_i8, le_i16, le_i32, le_u8, le_u16, le_u32};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -136,7 +138,9 @@ pub struct DmSpriteDef2 {
     /// Two formats are possible:
     /// * Old - Signed 16-bit texture value in pixels (most textures are 256 pixels in size).
     /// * New - Signed 32-bit value
-    pub texture_coordinates: Vec<(i16, i16)>,
+    ///
+    /// Values outside of the 16-bit range are clamped to it when written in the old format.
+    pub texture_coordinates: Vec<(i32, i32)>,
 
     /// Vertex normals (x, y, z). Each element contains a signed byte representing the
     /// component of the vertex normal, scaled such that –127 represents –1 and
@@ -194,6 +198,10 @@ impl FragmentParser for DmSpriteDef2 {
     const TYPE_NAME: &'static str = "DmSpriteDef2";
 
     fn parse(input: &[u8]) -> WResult<'_, DmSpriteDef2> {
+        Self::parse_versioned(input, WldVersion::Old)
+    }
+
+    fn parse_versioned(input: &[u8], version: WldVersion) -> WResult<'_, DmSpriteDef2> {
         let (
             i,
             (
@@ -244,11 +252,22 @@ impl FragmentParser for DmSpriteDef2 {
         )
             .parse(input)?;
 
+        let (i, positions) = count((le_i16, le_i16, le_i16), position_count as usize).parse(i)?;
+        // The new format stores texture coordinates with 32 bits instead of 16.
+        let (i, texture_coordinates) = match version {
+            WldVersion::Old => count(
+                (le_i16, le_i16).map(|(u, v)| (u.into(), v.into())),
+                texture_coordinate_count as usize,
+            )
+            .parse(i)?,
+            WldVersion::New => {
+                count((le_i32, le_i32), texture_coordinate_count as usize).parse(i)?
+            }
+        };
+
         let (
             remaining,
             (
-                positions,
-                texture_coordinates,
                 vertex_normals,
                 vertex_colors,
                 faces,
@@ -258,8 +277,6 @@ impl FragmentParser for DmSpriteDef2 {
                 meshops,
             ),
         ) = (
-            count((le_i16, le_i16, le_i16), position_count as usize),
-            count((le_i16, le_i16), texture_coordinate_count as usize),
             count((le_i8, le_i8, le_i8), normal_count as usize),
             count(le_u32, color_count as usize),
             count(DmSpriteDef2FaceEntry::parse, face_count as usize),
@@ -312,6 +329,10 @@ impl FragmentParser for DmSpriteDef2 {
 
 impl Fragment for DmSpriteDef2 {
     fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_versioned(WldVersion::Old)
+    }
+
+    fn to_bytes_versioned(&self, version: WldVersion) -> Vec<u8> {
         let meshops = &self
             .meshops
             .iter()
@@ -358,7 +379,14 @@ impl Fragment for DmSpriteDef2 {
             &self
                 .texture_coordinates
                 .iter()
-                .flat_map(|t| [t.0.to_le_bytes(), t.1.to_le_bytes()].concat())
+                .flat_map(|t| match version {
+                    WldVersion::Old => [
+                        saturate_i16(t.0).to_le_bytes(),
+                        saturate_i16(t.1).to_le_bytes(),
+                    ]
+                    .concat(),
+                    WldVersion::New => [t.0.to_le_bytes(), t.1.to_le_bytes()].concat(),
+                })
                 .collect::<Vec<_>>()[..],
             &self
                 .vertex_normals
@@ -400,13 +428,91 @@ impl Fragment for DmSpriteDef2 {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![
+            FragmentCount::new(
+                "position_count",
+                self.position_count.into(),
+                self.positions.len(),
+            ),
+            FragmentCount::new(
+                "texture_coordinate_count",
+                self.texture_coordinate_count.into(),
+                self.texture_coordinates.len(),
+            ),
+            FragmentCount::new(
+                "normal_count",
+                self.normal_count.into(),
+                self.vertex_normals.len(),
+            ),
+            FragmentCount::new(
+                "color_count",
+                self.color_count.into(),
+                self.vertex_colors.len(),
+            ),
+            FragmentCount::new("face_count", self.face_count.into(), self.faces.len()),
+            FragmentCount::new(
+                "skin_assignment_groups_count",
+                self.skin_assignment_groups_count.into(),
+                self.skin_assignment_groups.len(),
+            ),
+            FragmentCount::new(
+                "face_material_groups_count",
+                self.face_material_groups_count.into(),
+                self.face_material_groups.len(),
+            ),
+            FragmentCount::new(
+                "vertex_material_groups_count",
+                self.vertex_material_groups_count.into(),
+                self.vertex_material_groups.len(),
+            ),
+            FragmentCount::new("meshop_count", self.meshop_count.into(), self.meshops.len()),
+        ]
+    }
+
+    fn normalize(&mut self) {
+        self.position_count = self.positions.len() as u16;
+        self.texture_coordinate_count = self.texture_coordinates.len() as u16;
+        self.normal_count = self.vertex_normals.len() as u16;
+        self.color_count = self.vertex_colors.len() as u16;
+        self.face_count = self.faces.len() as u16;
+        self.skin_assignment_groups_count = self.skin_assignment_groups.len() as u16;
+        self.face_material_groups_count = self.face_material_groups.len() as u16;
+        self.vertex_material_groups_count = self.vertex_material_groups.len() as u16;
+        self.meshop_count = self.meshops.len() as u16;
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        vec![
+            self.material_list_ref.reference("material_list_ref"),
+            self.animation_ref.reference("animation_ref"),
+            FragmentReference::new("fragment3", self.fragment3.value()),
+            FragmentReference::new("fragment4", self.fragment4.value()),
+        ]
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.material_list_ref.remap(f);
+        self.animation_ref.remap(f);
+        self.fragment3.remap(f);
+        self.fragment4.remap(f);
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -479,10 +585,7 @@ pub struct DmSpriteDef2MeshOpEntry {
 
 impl DmSpriteDef2MeshOpEntry {
     fn parse(input: &[u8]) -> WResult<'_, DmSpriteDef2MeshOpEntry> {
-        let unknown_data = &input[0..4];
-        let input = &input[4..];
-
-        let (i, (param1, type_field)) = (le_u8, le_u8).parse(input)?;
+        let (i, (unknown_data, param1, type_field)) = (take(4usize), le_u8, le_u8).parse(input)?;
 
         let (unknown_data, offset) = if type_field == 4 {
             le_f32(unknown_data).map(|(i, offset)| (i, Some(offset)))?
@@ -522,6 +625,11 @@ impl DmSpriteDef2MeshOpEntry {
     }
 }
 
+/// Clamp a texture coordinate to the 16-bit range of the old format.
+fn saturate_i16(value: i32) -> i16 {
+    value.clamp(i16::MIN.into(), i16::MAX.into()) as i16
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -709,4 +817,48 @@ mod tests {
 
         assert_eq!(parsed.to_bytes(), data);
     }
+
+    #[test]
+    fn it_serializes_the_new_format() {
+        let mut frag = fixture_basic();
+        frag.texture_coordinates[0] = (40000, -3);
+        let data = frag.to_bytes_versioned(WldVersion::New);
+        assert_eq!(data.len(), fixture_basic().to_bytes().len() + 3 * 4);
+
+        let parsed = DmSpriteDef2::parse_versioned(&data, WldVersion::New)
+            .unwrap()
+            .1;
+        assert_eq!(parsed.texture_coordinates[0], (40000, -3));
+        assert_eq!(parsed.vertex_normals, frag.vertex_normals);
+        assert_eq!(parsed.to_bytes_versioned(WldVersion::New), data);
+    }
+
+    #[test]
+    fn it_converts_the_new_format_to_the_old() {
+        let mut frag = fixture_basic();
+        frag.texture_coordinates[0] = (40000, -40000);
+        frag.texture_coordinates[1] = (65537, -3);
+        let new = DmSpriteDef2::parse_versioned(
+            &frag.to_bytes_versioned(WldVersion::New),
+            WldVersion::New,
+        )
+        .unwrap()
+        .1;
+        let data = new.to_bytes_versioned(WldVersion::Old);
+        assert_eq!(data.len(), fixture_basic().to_bytes().len());
+
+        let old = DmSpriteDef2::parse_versioned(&data, WldVersion::Old)
+            .unwrap()
+            .1;
+        assert_eq!(
+            old.texture_coordinates,
+            vec![(32767, -32768), (32767, -3), (0, 128)]
+        );
+        assert_eq!(old.vertex_normals, frag.vertex_normals);
+    }
+
+    #[test]
+    fn it_fails_on_truncated_meshops() {
+        assert!(DmSpriteDef2MeshOpEntry::parse(&[0x01, 0x02]).is_err());
+    }
 }
@@ -1,5 +1,7 @@
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{Fragment, FragmentCount, FragmentParser, StringReference, WResult};
+use crate::parser::WldDocError;
 use nom::Parser;
+use nom::error::{ErrorKind, ParseError};
 use nom::multi::count;
 use nom::number::complete::{le_f32, le_u32};
 use std::any::Any;
@@ -25,6 +27,11 @@ pub struct DmTrackDef {
     pub frames: Vec<Vec<(f32, f32, f32)>>,
 }
 
+impl DmTrackDef {
+    /// The most frames without vertices that are read.
+    const MAX_EMPTY_FRAMES: u32 = u16::MAX as u32;
+}
+
 impl FragmentParser for DmTrackDef {
     type T = Self;
 
@@ -37,6 +44,14 @@ impl FragmentParser for DmTrackDef {
         let (i, (flags, vertex_count, frame_count, sleep, param1)) =
             (le_u32, le_u32, le_u32, le_u32, le_u32).parse(i)?;
 
+        // Frames without vertices take up no input, so a corrupt frame count would
+        // otherwise allocate without ever running out of input.
+        if vertex_count == 0 && frame_count > Self::MAX_EMPTY_FRAMES {
+            return Err(nom::Err::Error(WldDocError::from_error_kind(
+                i,
+                ErrorKind::Count,
+            )));
+        }
         let (i, frames) = count(
             count((le_f32, le_f32, le_f32), vertex_count as usize),
             frame_count as usize,
@@ -62,7 +77,7 @@ impl Fragment for DmTrackDef {
     fn to_bytes(&self) -> Vec<u8> {
         [
             &self.name_reference.to_bytes()[..],
-            &self.flags.to_be_bytes()[..],
+            &self.flags.to_le_bytes()[..],
             &self.vertex_count.to_le_bytes()[..],
             &self.frame_count.to_le_bytes()[..],
             &self.sleep.to_le_bytes()[..],
@@ -84,11 +99,70 @@ impl Fragment for DmTrackDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "frame_count",
+            self.frame_count as usize,
+            self.frames.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.frame_count = self.frames.len() as u32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_fails_on_frames_without_vertices() {
+        let frag = DmTrackDef {
+            name_reference: StringReference::new(0),
+            flags: 0,
+            vertex_count: 0,
+            frame_count: u32::MAX,
+            sleep: 0,
+            param1: 0,
+            frames: vec![],
+        };
+
+        assert!(DmTrackDef::parse(&frag.to_bytes()).is_err());
+    }
+
+    #[test]
+    fn it_parses_frames_without_vertices() {
+        let frag = DmTrackDef {
+            name_reference: StringReference::new(0),
+            flags: 0x01,
+            vertex_count: 0,
+            frame_count: 2,
+            sleep: 100,
+            param1: 0,
+            frames: vec![vec![], vec![]],
+        };
+        let data = frag.to_bytes();
+
+        let (remaining, parsed) = DmTrackDef::parse(&data).unwrap();
+        assert_eq!(parsed, frag);
+        assert_eq!(remaining.len(), 0);
+        assert_eq!(parsed.to_bytes(), data);
+    }
 }
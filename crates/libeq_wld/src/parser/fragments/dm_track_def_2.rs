@@ -1,6 +1,6 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{Fragment, FragmentCount, FragmentParser, StringReference, WResult};
 
 use nom::Parser;
 use nom::multi::count;
@@ -122,13 +122,33 @@ impl Fragment for DmTrackDef2 {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "frame_count",
+            self.frame_count.into(),
+            self.frames.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.frame_count = self.frames.len() as u16;
+    }
 }
 
 #[cfg(test)]
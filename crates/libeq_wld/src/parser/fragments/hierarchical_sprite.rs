@@ -1,7 +1,8 @@
 use std::any::Any;
 
 use super::{
-    Fragment, FragmentParser, FragmentRef, HierarchicalSpriteDef, StringReference, WResult,
+    Fragment, FragmentParser, FragmentRef, FragmentReference, HierarchicalSpriteDef,
+    StringReference, WResult,
 };
 
 use nom::Parser;
@@ -59,13 +60,29 @@ impl Fragment for HierarchicalSprite {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        vec![self.reference.reference("reference")]
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.reference.remap(f);
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,9 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{
+    AnyFragmentRef, DmSprite, Fragment, FragmentCount, FragmentParser, FragmentRef,
+    FragmentReference, StringReference, Track, WResult, remap_raw, set_flag,
+};
 
 use nom::Parser;
 use nom::multi::count;
@@ -54,7 +57,7 @@ pub struct HierarchicalSpriteDef {
     /// field is set. These entries generally point to 0x2D [DmSprite]s and
     /// outline all of the meshes in the animated model. For example, there might be a mesh
     /// for a model’s body and another one for the head.
-    pub dm_sprites: Option<Vec<u32>>,
+    pub dm_sprites: Option<Vec<FragmentRef<DmSprite>>>,
 
     /// _Unknown_ - There are size2 of these.
     pub link_skin_updates_to_dag_index: Option<Vec<u32>>,
@@ -97,7 +100,7 @@ impl FragmentParser for HierarchicalSpriteDef {
         let (remaining, (dm_sprites, link_skin_updates_to_dag_index)) = if flags.has_unknown_flag()
         {
             let size = num_attached_skins.unwrap_or(0) as usize;
-            (count(le_u32, size), count(le_u32, size))
+            (count(FragmentRef::parse, size), count(le_u32, size))
                 .parse(i)
                 .map(|(i, (f3, d3))| (i, (Some(f3), Some(d3))))?
         } else {
@@ -149,10 +152,10 @@ pub struct Dag {
     /// generally immediately follow the 0x10 Skeleton Track Set fragment (with the
     /// 0x11 Skeleton Track Set Reference immediately following those). I don’t know
     /// if this is a necessary arrangement.
-    pub track_reference: u32,
+    pub track_reference: FragmentRef<Track>,
 
     /// Sometimes refers to a 0x2D Mesh Reference fragment.
-    pub mesh_or_sprite_reference: u32,
+    pub mesh_or_sprite_reference: AnyFragmentRef,
 
     /// The number of data entries
     pub num_sub_dags: u32,
@@ -183,8 +186,8 @@ impl Dag {
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (i, name_reference) = le_i32(input)?;
         let (i, flags) = le_u32(i)?;
-        let (i, track_reference) = le_u32(i)?;
-        let (i, mesh_or_sprite_reference) = le_u32(i)?;
+        let (i, track_reference) = FragmentRef::parse(i)?;
+        let (i, mesh_or_sprite_reference) = FragmentRef::parse(i)?;
         let (i, num_sub_dags) = le_u32(i)?;
         let (remaining, sub_dags) = count(le_u32, num_sub_dags as usize).parse(i)?;
 
@@ -205,8 +208,8 @@ impl Dag {
         [
             &self.name_reference.to_le_bytes()[..],
             &self.flags.to_le_bytes()[..],
-            &self.track_reference.to_le_bytes()[..],
-            &self.mesh_or_sprite_reference.to_le_bytes()[..],
+            &self.track_reference.to_bytes()[..],
+            &self.mesh_or_sprite_reference.to_bytes()[..],
             &self.num_sub_dags.to_le_bytes()[..],
             &self
                 .sub_dags
@@ -242,7 +245,7 @@ impl Fragment for HierarchicalSpriteDef {
             &self
                 .dm_sprites
                 .as_ref()
-                .map_or(vec![], |f| f.iter().flat_map(|x| x.to_le_bytes()).collect())[..],
+                .map_or(vec![], |f| f.iter().flat_map(|x| x.to_bytes()).collect())[..],
             &self
                 .link_skin_updates_to_dag_index
                 .as_ref()
@@ -255,13 +258,86 @@ impl Fragment for HierarchicalSpriteDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "num_dags",
+            self.num_dags as usize,
+            self.dags.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.num_dags = self.dags.len() as u32;
+        for dag in &mut self.dags {
+            dag.num_sub_dags = dag.sub_dags.len() as u32;
+        }
+        self.num_attached_skins = self.dm_sprites.as_ref().map(|d| d.len() as u32);
+        set_flag(
+            &mut self.flags.0,
+            HierarchicalSpriteDefFlags::HAS_CENTER_OFFSET,
+            self.center_offset.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            HierarchicalSpriteDefFlags::HAS_BOUNDING_RADIUS,
+            self.bounding_radius.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            HierarchicalSpriteDefFlags::UNKNOWN_FLAG,
+            self.dm_sprites.is_some(),
+        );
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        std::iter::once(FragmentReference::new(
+            "collision_volume_reference",
+            self.collision_volume_reference as i32,
+        ))
+        .chain(self.dags.iter().flat_map(|dag| {
+            [
+                dag.track_reference.reference("dags.track_reference"),
+                FragmentReference::new(
+                    "dags.mesh_or_sprite_reference",
+                    dag.mesh_or_sprite_reference.value(),
+                ),
+            ]
+        }))
+        .chain(
+            self.dm_sprites
+                .iter()
+                .flatten()
+                .map(|r| r.reference("dm_sprites")),
+        )
+        .collect()
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        remap_raw(&mut self.collision_volume_reference, f);
+        for dag in &mut self.dags {
+            dag.track_reference.remap(f);
+            dag.mesh_or_sprite_reference.remap(f);
+        }
+        for r in self.dm_sprites.iter_mut().flatten() {
+            r.remap(f);
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -279,6 +355,10 @@ impl HierarchicalSpriteDefFlags {
     const HAS_BOUNDING_RADIUS: u32 = 0x02;
     const UNKNOWN_FLAG: u32 = 0x200;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (remaining, raw_flags) = le_u32(input)?;
         Ok((remaining, Self(raw_flags)))
@@ -318,30 +398,30 @@ mod tests {
                 Dag {
                     name_reference: -168,
                     flags: 0,
-                    track_reference: 8,
-                    mesh_or_sprite_reference: 0,
+                    track_reference: FragmentRef::new(8),
+                    mesh_or_sprite_reference: FragmentRef::new(0),
                     num_sub_dags: 1,
                     sub_dags: vec![1],
                 },
                 Dag {
                     name_reference: -175,
                     flags: 0,
-                    track_reference: 10,
-                    mesh_or_sprite_reference: 0,
+                    track_reference: FragmentRef::new(10),
+                    mesh_or_sprite_reference: FragmentRef::new(0),
                     num_sub_dags: 1,
                     sub_dags: vec![2],
                 },
                 Dag {
                     name_reference: -182,
                     flags: 0,
-                    track_reference: 12,
-                    mesh_or_sprite_reference: 0,
+                    track_reference: FragmentRef::new(12),
+                    mesh_or_sprite_reference: FragmentRef::new(0),
                     num_sub_dags: 0,
                     sub_dags: vec![],
                 },
             ],
             num_attached_skins: Some(1),
-            dm_sprites: Some(vec![13]),
+            dm_sprites: Some(vec![FragmentRef::new(13)]),
             link_skin_updates_to_dag_index: Some(vec![2]),
         }
     }
@@ -359,13 +439,13 @@ mod tests {
         assert_eq!(frag.dags.len(), 3);
         assert_eq!(frag.dags[0].name_reference, -168);
         assert_eq!(frag.dags[0].flags, 0);
-        assert_eq!(frag.dags[0].track_reference, 8);
-        assert_eq!(frag.dags[0].mesh_or_sprite_reference, 0);
+        assert_eq!(frag.dags[0].track_reference, FragmentRef::new(8));
+        assert_eq!(frag.dags[0].mesh_or_sprite_reference, FragmentRef::new(0));
         assert_eq!(frag.dags[0].num_sub_dags, 1);
         assert_eq!(frag.dags[0].sub_dags.len(), 1);
         assert_eq!(frag.dags[0].sub_dags[0], 1);
         assert_eq!(frag.num_attached_skins, Some(1));
-        assert_eq!(frag.dm_sprites.clone().unwrap(), vec![13]);
+        assert_eq!(frag.dm_sprites.clone().unwrap(), vec![FragmentRef::new(13)]);
         assert_eq!(
             frag.link_skin_updates_to_dag_index.clone().unwrap(),
             vec![2]
@@ -380,4 +460,23 @@ mod tests {
 
         assert_eq!(parsed.to_bytes(), data);
     }
+
+    #[test]
+    fn it_lists_references() {
+        let references: Vec<_> = fixture()
+            .references()
+            .into_iter()
+            .filter(|r| r.value != 0)
+            .collect();
+
+        assert_eq!(
+            references,
+            vec![
+                FragmentRef::<Track>::new(8).reference("dags.track_reference"),
+                FragmentRef::<Track>::new(10).reference("dags.track_reference"),
+                FragmentRef::<Track>::new(12).reference("dags.track_reference"),
+                FragmentRef::<DmSprite>::new(13).reference("dm_sprites"),
+            ]
+        );
+    }
 }
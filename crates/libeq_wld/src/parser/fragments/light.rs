@@ -1,6 +1,8 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, FragmentRef, LightDef, StringReference, WResult};
+use super::{
+    Fragment, FragmentParser, FragmentRef, FragmentReference, LightDef, StringReference, WResult,
+};
 
 use nom::Parser;
 use nom::number::complete::le_u32;
@@ -57,13 +59,29 @@ impl Fragment for Light {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        vec![self.reference.reference("reference")]
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.reference.remap(f);
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,6 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{Fragment, FragmentParser, StringReference, WResult, set_flag};
 
 use nom::Parser;
 use nom::multi::count;
@@ -137,13 +137,44 @@ impl Fragment for LightDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn normalize(&mut self) {
+        set_flag(
+            &mut self.flags.0,
+            LightDefFlags::HAS_CURRENT_FRAME,
+            self.current_frame.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            LightDefFlags::HAS_SLEEP,
+            self.sleep.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            LightDefFlags::HAS_LIGHT_LEVELS,
+            self.light_levels.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            LightDefFlags::HAS_COLOR,
+            self.colors.is_some(),
+        );
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -157,6 +188,10 @@ impl LightDefFlags {
     const SKIP_FRAMES: u32 = 0x08;
     const HAS_COLOR: u32 = 0x10;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (remaining, raw_flags) = le_u32(input)?;
         Ok((remaining, Self(raw_flags)))
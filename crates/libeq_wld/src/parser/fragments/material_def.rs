@@ -1,7 +1,10 @@
 use std::any::Any;
 
 use super::common::RenderMethod;
-use super::{Fragment, FragmentParser, FragmentRef, SimpleSprite, StringReference, WResult};
+use super::{
+    Fragment, FragmentParser, FragmentRef, FragmentReference, SimpleSprite, StringReference,
+    WResult, set_flag,
+};
 
 use nom::Parser;
 use nom::number::complete::{le_f32, le_u32};
@@ -107,13 +110,37 @@ impl Fragment for MaterialDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        vec![self.reference.reference("reference")]
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.reference.remap(f);
+    }
+
+    fn normalize(&mut self) {
+        set_flag(
+            &mut self.flags.0,
+            MaterialFlags::HAS_PAIR,
+            self.pair.is_some(),
+        );
+    }
 }
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -123,6 +150,10 @@ impl MaterialFlags {
     const IS_TWO_SIDED: u32 = 0x01;
     const HAS_PAIR: u32 = 0x02;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (i, raw_flags) = le_u32(input)?;
         Ok((i, Self(raw_flags)))
@@ -155,7 +186,7 @@ mod tests {
         MaterialDef {
             name_reference: StringReference::new(-22),
             flags: MaterialFlags(0x02),
-            render_method: RenderMethod::from_u32(0x80000001),
+            render_method: RenderMethod::from_u32(0x80000001).unwrap(),
             rgb_pen: 0x4e4e4e,
             brightness: 0.0,
             scaled_ambient: 0.75,
@@ -168,7 +199,7 @@ mod tests {
         MaterialDef {
             name_reference: StringReference::new(-200),
             flags: MaterialFlags(0x03),
-            render_method: RenderMethod::from_u32(0x80000013),
+            render_method: RenderMethod::from_u32(0x80000013).unwrap(),
             rgb_pen: 0xb2b2b2,
             brightness: 0.0,
             scaled_ambient: 0.75,
@@ -220,4 +251,24 @@ mod tests {
 
         assert_eq!(frag.to_bytes(), data);
     }
+
+    #[test]
+    fn it_normalizes_flags() {
+        let mut frag = fixture_two_sided();
+        frag.pair = None;
+        frag.normalize();
+
+        assert!(!frag.flags.has_pair());
+        assert!(frag.flags.is_two_sided());
+        let parsed = MaterialDef::parse(&frag.to_bytes()).unwrap().1;
+        assert_eq!(parsed, frag);
+    }
+
+    #[test]
+    fn it_fails_on_unknown_material_type() {
+        let mut data = fixture().to_bytes();
+        data[8..12].copy_from_slice(&0x80000004u32.to_le_bytes());
+
+        assert!(MaterialDef::parse(&data).is_err());
+    }
 }
@@ -1,6 +1,9 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, FragmentRef, MaterialDef, StringReference, WResult};
+use super::{
+    Fragment, FragmentCount, FragmentParser, FragmentRef, FragmentReference, MaterialDef,
+    StringReference, WResult,
+};
 
 use nom::Parser;
 use nom::multi::count;
@@ -67,13 +70,46 @@ impl Fragment for MaterialPalette {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "size1",
+            self.size1 as usize,
+            self.fragments.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.size1 = self.fragments.len() as u32;
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        self.fragments
+            .iter()
+            .map(|r| r.reference("fragments"))
+            .collect()
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        for r in &mut self.fragments {
+            r.remap(f);
+        }
+    }
 }
 
 #[cfg(test)]
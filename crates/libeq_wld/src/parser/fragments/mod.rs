@@ -29,6 +29,7 @@ mod particle_sprite_def;
 mod point_light;
 mod polyhedron;
 mod polyhedron_def;
+mod raw;
 mod region;
 mod simple_sprite;
 mod simple_sprite_def;
@@ -49,7 +50,7 @@ mod zone;
 
 use std::any::Any;
 use std::marker::PhantomData;
-use std::ops::Deref;
+use std::ops::{Deref, DerefMut};
 
 use nom::number::complete::le_i32;
 
@@ -89,6 +90,7 @@ pub use particle_sprite_def::*;
 pub use point_light::*;
 pub use polyhedron::*;
 pub use polyhedron_def::*;
+pub use raw::*;
 pub use region::*;
 pub use simple_sprite::*;
 pub use simple_sprite_def::*;
@@ -108,12 +110,36 @@ pub use world_vertices::*;
 pub use zone::*;
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug)]
 pub enum FragmentRef<T> {
     Name(StringReference, PhantomData<T>),
     Index(u32, PhantomData<T>),
 }
 
+/// A reference to a fragment that may be one of several types. Resolving it with
+/// [crate::parser::WldDoc::get] gives a [FragmentType] to match on.
+pub type AnyFragmentRef = FragmentRef<FragmentType>;
+
+// These are implemented by hand so that they don't require `T` to implement them,
+// `T` is only a marker.
+impl<T> Clone for FragmentRef<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for FragmentRef<T> {}
+
+impl<T> PartialEq for FragmentRef<T> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Name(a, _), Self::Name(b, _)) => a == b,
+            (Self::Index(a, _), Self::Index(b, _)) => a == b,
+            _ => false,
+        }
+    }
+}
+
 impl<T> FragmentRef<T> {
     pub fn new(idx: i32) -> FragmentRef<T> {
         if idx > 0 {
@@ -135,13 +161,183 @@ impl<T> FragmentRef<T> {
             Self::Index(idx, _) => idx.to_le_bytes().to_vec(),
         }
     }
+
+    /// The reference as it is stored in the file.
+    pub fn value(&self) -> i32 {
+        match self {
+            Self::Name(string_ref, _) => string_ref.0,
+            Self::Index(idx, _) => *idx as i32,
+        }
+    }
+
+    /// Replace the reference with the result of `f` applied to its value.
+    pub fn remap(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        *self = FragmentRef::new(f(self.value()));
+    }
+}
+
+/// Replace a reference stored as a plain integer with the result of `f` applied to it.
+fn remap_raw(value: &mut u32, f: &mut dyn FnMut(i32) -> i32) {
+    *value = f(*value as i32) as u32;
+}
+
+/// A reference held by a fragment to another fragment, as it is stored in the file.
+///
+/// Positive values are 1-based fragment indices, negative values are references to
+/// the name of a fragment and 0 is no reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentReference {
+    /// The field the reference was found in. Fields nested in lists or structures are
+    /// separated by dots, e.g. `dags.track_reference`.
+    pub field: &'static str,
+    pub value: i32,
+    /// The type id of the fragment the reference should point to, if it is known.
+    pub expected_type: Option<u32>,
+}
+
+impl FragmentReference {
+    /// A reference to a fragment of any type.
+    pub fn new(field: &'static str, value: i32) -> Self {
+        Self {
+            field,
+            value,
+            expected_type: None,
+        }
+    }
+}
+
+/// A count field of a fragment along with the length of the list it counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentCount {
+    pub field: &'static str,
+    /// The value stored in the field.
+    pub count: usize,
+    /// The actual length of the list.
+    pub len: usize,
+}
+
+impl FragmentCount {
+    pub fn new(field: &'static str, count: usize, len: usize) -> Self {
+        Self { field, count, len }
+    }
+}
+
+impl<T: FragmentParser> FragmentRef<T> {
+    /// This reference as a [FragmentReference] found in `field`, expecting a `T`.
+    pub fn reference(&self, field: &'static str) -> FragmentReference {
+        FragmentReference {
+            field,
+            value: self.value(),
+            expected_type: Some(T::TYPE_ID),
+        }
+    }
 }
 
+/// A fragment held by a [WldDoc](crate::parser::WldDoc).
+///
+/// Fragment types implemented outside this crate must provide [Fragment::as_any_mut]
+/// and [Fragment::name_ref_mut] as well as their immutable counterparts. Neither can
+/// have a default: `self` only coerces to `dyn Any` for sized types and the name is a
+/// field of the fragment. Every implementation is the same:
+///
+/// ```ignore
+/// fn as_any_mut(&mut self) -> &mut dyn Any {
+///     self
+/// }
+///
+/// fn name_ref_mut(&mut self) -> &mut StringReference {
+///     &mut self.name_reference
+/// }
+/// ```
+///
+/// Fragments holding string references other than their name also need to override
+/// [Fragment::string_references] and [Fragment::remap_string_references], otherwise
+/// [WldDoc::gc_strings](crate::parser::WldDoc::gc_strings) removes the strings they
+/// refer to.
 pub trait Fragment {
     fn to_bytes(&self) -> Vec<u8>;
+
+    /// Serialize the fragment for a file of the given version, see
+    /// [FragmentParser::parse_versioned].
+    fn to_bytes_versioned(&self, _version: WldVersion) -> Vec<u8> {
+        self.to_bytes()
+    }
+
     fn as_any(&self) -> &dyn Any;
+
+    /// The fragment as [Any] so that it can be downcast and edited in place.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
     fn name_ref(&self) -> &StringReference;
+
+    /// The reference to the fragment's name, to rename it in place.
+    fn name_ref_mut(&mut self) -> &mut StringReference;
     fn type_id(&self) -> u32;
+
+    /// References this fragment holds into the string hash, starting with its name.
+    /// Names of fragments referenced by a [FragmentRef] are in [Fragment::references].
+    fn string_references(&self) -> Vec<StringReference> {
+        vec![*self.name_ref()]
+    }
+
+    /// Replace every reference in [Fragment::string_references] with the result of `f`
+    /// applied to it.
+    fn remap_string_references(&mut self, f: &mut dyn FnMut(StringReference) -> StringReference) {
+        let name_ref = self.name_ref_mut();
+        *name_ref = f(*name_ref);
+    }
+
+    /// References this fragment holds to other fragments, including ones with no target (0).
+    fn references(&self) -> Vec<FragmentReference> {
+        Vec::new()
+    }
+
+    /// Replace the value of every reference in [Fragment::references] with the result
+    /// of `f` applied to it.
+    fn remap_references(&mut self, _f: &mut dyn FnMut(i32) -> i32) {}
+
+    /// Fields of this fragment that count the entries of one of its lists.
+    fn counts(&self) -> Vec<FragmentCount> {
+        Vec::new()
+    }
+
+    /// Recompute count fields and the flags marking which optional fields are present
+    /// from the data they describe, so that an edited fragment serializes correctly.
+    fn normalize(&mut self) {}
+}
+
+/// Set or clear `flag` in a set of fragment flags.
+fn set_flag(flags: &mut u32, flag: u32, value: bool) {
+    if value {
+        *flags |= flag;
+    } else {
+        *flags &= !flag;
+    }
+}
+
+/// A type that a [FragmentRef] can be resolved to.
+pub trait FragmentTarget {
+    fn from_fragment(fragment: &FragmentType) -> Option<&Self>;
+    fn from_fragment_mut(fragment: &mut FragmentType) -> Option<&mut Self>;
+}
+
+impl<T: 'static + Fragment> FragmentTarget for T {
+    fn from_fragment(fragment: &FragmentType) -> Option<&Self> {
+        fragment.as_any().downcast_ref()
+    }
+
+    fn from_fragment_mut(fragment: &mut FragmentType) -> Option<&mut Self> {
+        fragment.as_any_mut().downcast_mut()
+    }
+}
+
+impl FragmentTarget for FragmentType {
+    fn from_fragment(fragment: &FragmentType) -> Option<&Self> {
+        Some(fragment)
+    }
+
+    fn from_fragment_mut(fragment: &mut FragmentType) -> Option<&mut Self> {
+        Some(fragment)
+    }
 }
 
 pub trait FragmentParser {
@@ -149,6 +345,45 @@ pub trait FragmentParser {
     const TYPE_ID: u32;
     const TYPE_NAME: &'static str;
     fn parse(input: &[u8]) -> WResult<'_, Self::T>;
+
+    /// Parse a fragment from a file of the given version. Most fragments are laid out
+    /// the same in both versions and only need to implement [FragmentParser::parse].
+    fn parse_versioned(input: &[u8], _version: WldVersion) -> WResult<'_, Self::T> {
+        Self::parse(input)
+    }
+}
+
+/// The two known versions of the .wld format, as stored in the file header.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WldVersion {
+    /// `0x00015500`
+    #[default]
+    Old,
+    /// `0x1000C800`, used from Luclin on.
+    New,
+}
+
+impl WldVersion {
+    const OLD: u32 = 0x00015500;
+    const NEW: u32 = 0x1000C800;
+
+    /// Anything other than the new version number is read as the old format.
+    pub fn from_u32(version: u32) -> Self {
+        match version {
+            Self::NEW => Self::New,
+            _ => Self::Old,
+        }
+    }
+}
+
+impl From<WldVersion> for u32 {
+    fn from(version: WldVersion) -> u32 {
+        match version {
+            WldVersion::Old => WldVersion::OLD,
+            WldVersion::New => WldVersion::NEW,
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -201,6 +436,8 @@ pub enum FragmentType {
     WorldVertices(WorldVertices),
     Sphere(Sphere),
     DirectionalLight(DirectionalLight),
+    /// A fragment of unknown type, or one that failed to parse, kept as raw bytes.
+    Raw(RawFragment),
 }
 
 impl Deref for FragmentType {
@@ -255,12 +492,128 @@ impl Deref for FragmentType {
             Self::WorldVertices(x) => x,
             Self::Sphere(x) => x,
             Self::DirectionalLight(x) => x,
+            Self::Raw(x) => x,
+        }
+    }
+}
+
+impl DerefMut for FragmentType {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Self::DmSpriteDef(x) => x,
+            Self::AmbientLight(x) => x,
+            Self::BlitSpriteDef(x) => x,
+            Self::BlitSprite(x) => x,
+            Self::Region(x) => x,
+            Self::WorldTree(x) => x,
+            Self::Sprite3DDef(x) => x,
+            Self::Sprite3D(x) => x,
+            Self::GlobalAmbientLightDef(x) => x,
+            Self::Sprite4D(x) => x,
+            Self::Sprite4DDef(x) => x,
+            Self::PointLight(x) => x,
+            Self::LightDef(x) => x,
+            Self::Light(x) => x,
+            Self::MaterialDef(x) => x,
+            Self::MaterialPalette(x) => x,
+            Self::DmSpriteDef2(x) => x,
+            Self::DmTrackDef2(x) => x,
+            Self::DmTrack(x) => x,
+            Self::DmSprite(x) => x,
+            Self::TrackDef(x) => x,
+            Self::Track(x) => x,
+            Self::ActorDef(x) => x,
+            Self::Actor(x) => x,
+            Self::ParticleSprite(x) => x,
+            Self::ParticleSpriteDef(x) => x,
+            Self::ParticleCloudDef(x) => x,
+            Self::DefaultPaletteFile(x) => x,
+            Self::PolyhedronDef(x) => x,
+            Self::Polyhedron(x) => x,
+            Self::Zone(x) => x,
+            Self::HierarchicalSpriteDef(x) => x,
+            Self::HierarchicalSprite(x) => x,
+            Self::SphereList(x) => x,
+            Self::SphereListDef(x) => x,
+            Self::SimpleSpriteDef(x) => x,
+            Self::BmInfo(x) => x,
+            Self::BmInfoRtk(x) => x,
+            Self::SimpleSprite(x) => x,
+            Self::Sprite2DDef(x) => x,
+            Self::Sprite2D(x) => x,
+            Self::DmTrackDef(x) => x,
+            Self::DmRGBTrackDef(x) => x,
+            Self::DmRGBTrack(x) => x,
+            Self::WorldVertices(x) => x,
+            Self::Sphere(x) => x,
+            Self::DirectionalLight(x) => x,
+            Self::Raw(x) => x,
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// The name of the fragment type with the given type id, if it is a known type.
+///
+/// Type 0x2c is used for a different fragment by each game, the name of the
+/// EverQuest fragment is returned for it.
+pub fn fragment_type_name(type_id: u32) -> Option<&'static str> {
+    let name = match type_id {
+        DmSpriteDef::TYPE_ID => DmSpriteDef::TYPE_NAME,
+        AmbientLight::TYPE_ID => AmbientLight::TYPE_NAME,
+        BlitSpriteDef::TYPE_ID => BlitSpriteDef::TYPE_NAME,
+        BlitSprite::TYPE_ID => BlitSprite::TYPE_NAME,
+        Region::TYPE_ID => Region::TYPE_NAME,
+        WorldTree::TYPE_ID => WorldTree::TYPE_NAME,
+        Sprite3DDef::TYPE_ID => Sprite3DDef::TYPE_NAME,
+        Sprite3D::TYPE_ID => Sprite3D::TYPE_NAME,
+        GlobalAmbientLightDef::TYPE_ID => GlobalAmbientLightDef::TYPE_NAME,
+        Sprite4D::TYPE_ID => Sprite4D::TYPE_NAME,
+        Sprite4DDef::TYPE_ID => Sprite4DDef::TYPE_NAME,
+        PointLight::TYPE_ID => PointLight::TYPE_NAME,
+        LightDef::TYPE_ID => LightDef::TYPE_NAME,
+        Light::TYPE_ID => Light::TYPE_NAME,
+        MaterialDef::TYPE_ID => MaterialDef::TYPE_NAME,
+        MaterialPalette::TYPE_ID => MaterialPalette::TYPE_NAME,
+        DmSpriteDef2::TYPE_ID => DmSpriteDef2::TYPE_NAME,
+        DmTrackDef2::TYPE_ID => DmTrackDef2::TYPE_NAME,
+        DmTrack::TYPE_ID => DmTrack::TYPE_NAME,
+        DmSprite::TYPE_ID => DmSprite::TYPE_NAME,
+        TrackDef::TYPE_ID => TrackDef::TYPE_NAME,
+        Track::TYPE_ID => Track::TYPE_NAME,
+        ActorDef::TYPE_ID => ActorDef::TYPE_NAME,
+        Actor::TYPE_ID => Actor::TYPE_NAME,
+        ParticleSprite::TYPE_ID => ParticleSprite::TYPE_NAME,
+        ParticleSpriteDef::TYPE_ID => ParticleSpriteDef::TYPE_NAME,
+        ParticleCloudDef::TYPE_ID => ParticleCloudDef::TYPE_NAME,
+        DefaultPaletteFile::TYPE_ID => DefaultPaletteFile::TYPE_NAME,
+        PolyhedronDef::TYPE_ID => PolyhedronDef::TYPE_NAME,
+        Polyhedron::TYPE_ID => Polyhedron::TYPE_NAME,
+        Zone::TYPE_ID => Zone::TYPE_NAME,
+        HierarchicalSpriteDef::TYPE_ID => HierarchicalSpriteDef::TYPE_NAME,
+        HierarchicalSprite::TYPE_ID => HierarchicalSprite::TYPE_NAME,
+        SphereList::TYPE_ID => SphereList::TYPE_NAME,
+        SphereListDef::TYPE_ID => SphereListDef::TYPE_NAME,
+        SimpleSpriteDef::TYPE_ID => SimpleSpriteDef::TYPE_NAME,
+        BmInfo::TYPE_ID => BmInfo::TYPE_NAME,
+        SimpleSprite::TYPE_ID => SimpleSprite::TYPE_NAME,
+        Sprite2DDef::TYPE_ID => Sprite2DDef::TYPE_NAME,
+        Sprite2D::TYPE_ID => Sprite2D::TYPE_NAME,
+        DmTrackDef::TYPE_ID => DmTrackDef::TYPE_NAME,
+        DmRGBTrackDef::TYPE_ID => DmRGBTrackDef::TYPE_NAME,
+        DmRGBTrack::TYPE_ID => DmRGBTrack::TYPE_NAME,
+        Sphere::TYPE_ID => Sphere::TYPE_NAME,
+        DirectionalLight::TYPE_ID => DirectionalLight::TYPE_NAME,
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// The games using the wld format. Some fragment types are laid out differently
+/// depending on the game, see [ParseOptions](super::ParseOptions).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum FragmentGame {
+    #[default]
     EverQuest,
     Tanarus,
     ReturnToKrondor,
@@ -2,12 +2,17 @@ use std::any::Any;
 
 use nom::{
     Parser,
+    combinator::map_opt,
+    error::context,
     number::complete::{le_f32, le_u8, le_u32},
 };
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::FromPrimitive;
 
-use super::{BlitSpriteDef, Fragment, FragmentParser, FragmentRef, StringReference, WResult};
+use super::{
+    BlitSpriteDef, Fragment, FragmentParser, FragmentRef, FragmentReference, StringReference,
+    WResult,
+};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -182,13 +187,29 @@ impl Fragment for ParticleCloudDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        vec![self.blitsprite.reference("blitsprite")]
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.blitsprite.remap(f);
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -202,9 +223,11 @@ pub enum ParticleMovement {
 
 impl ParticleMovement {
     pub fn parse(input: &[u8]) -> WResult<'_, Self> {
-        let (remaining, raw) = le_u32(input)?;
-
-        Ok((remaining, FromPrimitive::from_u32(raw).unwrap()))
+        context(
+            "particle_movement",
+            map_opt(le_u32, FromPrimitive::from_u32),
+        )
+        .parse(input)
     }
 
     fn to_bytes(self) -> Vec<u8> {
@@ -280,4 +303,12 @@ mod tests {
 
         assert_eq!(parsed.to_bytes(), data);
     }
+
+    #[test]
+    fn it_fails_on_unknown_particle_movement() {
+        let mut data = fixture().to_bytes();
+        data[12..16].copy_from_slice(&9u32.to_le_bytes());
+
+        assert!(ParticleCloudDef::parse(&data).is_err());
+    }
 }
@@ -1,6 +1,9 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, RenderInfo, RenderMethod, StringReference, WResult};
+use super::{
+    Fragment, FragmentCount, FragmentParser, FragmentReference, RenderInfo, RenderMethod,
+    StringReference, WResult, remap_raw, set_flag,
+};
 
 use nom::Parser;
 use nom::multi::count;
@@ -124,13 +127,57 @@ impl Fragment for ParticleSpriteDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "num_vertices",
+            self.num_vertices as usize,
+            self.vertices.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.num_vertices = self.vertices.len() as u32;
+        set_flag(
+            &mut self.flags.0,
+            ParticleSpriteDefFlags::HAS_CENTER_OFFSET,
+            self.center_offset.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            ParticleSpriteDefFlags::HAS_BOUNDING_RADIUS,
+            self.bounding_radius.is_some(),
+        );
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        self.render_info
+            .simple_sprite_reference
+            .map(|r| FragmentReference::new("render_info.simple_sprite_reference", r as i32))
+            .into_iter()
+            .collect()
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        if let Some(r) = &mut self.render_info.simple_sprite_reference {
+            remap_raw(r, f);
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -147,6 +194,10 @@ impl ParticleSpriteDefFlags {
     const HAS_CENTER_OFFSET: u32 = 0x01;
     const HAS_BOUNDING_RADIUS: u32 = 0x02;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (i, raw_flags) = le_u32(input)?;
         Ok((i, Self(raw_flags)))
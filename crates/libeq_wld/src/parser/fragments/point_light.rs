@@ -1,6 +1,8 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, FragmentRef, Light, StringReference, WResult};
+use super::{
+    Fragment, FragmentParser, FragmentRef, FragmentReference, Light, StringReference, WResult,
+};
 
 use nom::number::complete::{le_f32, le_u32};
 
@@ -82,13 +84,29 @@ impl Fragment for PointLight {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        vec![self.reference.reference("reference")]
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.reference.remap(f);
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -106,6 +124,10 @@ impl PointLightFlags {
     const STATIC_INFLUENCE: u32 = 0x40;
     const HAS_REGIONS: u32 = 0x80;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (remaining, raw_flags) = le_u32(input)?;
         Ok((remaining, Self(raw_flags)))
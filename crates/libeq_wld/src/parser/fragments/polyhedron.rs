@@ -1,6 +1,9 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, FragmentRef, PolyhedronDef, StringReference, WResult};
+use super::{
+    Fragment, FragmentParser, FragmentRef, FragmentReference, PolyhedronDef, StringReference,
+    WResult, set_flag,
+};
 
 use nom::number::complete::{le_f32, le_u32};
 
@@ -71,13 +74,37 @@ impl Fragment for Polyhedron {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        vec![self.reference.reference("reference")]
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.reference.remap(f);
+    }
+
+    fn normalize(&mut self) {
+        set_flag(
+            &mut self.flags.0,
+            PolyhedronFlags::HAS_SCALE_FACTOR,
+            self.scale_factor.is_some(),
+        );
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -93,6 +120,10 @@ impl From<PolyhedronFlags> for u32 {
 impl PolyhedronFlags {
     const HAS_SCALE_FACTOR: u32 = 0x01;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (remaining, raw_flags) = le_u32(input)?;
         Ok((remaining, Self(raw_flags)))
@@ -139,4 +170,15 @@ mod tests {
 
         assert_eq!(parsed.to_bytes(), data);
     }
+
+    #[test]
+    fn it_normalizes_flags() {
+        let mut frag = fixture();
+        frag.scale_factor = Some(0.5);
+        frag.normalize();
+
+        assert!(frag.flags.has_scale_factor());
+        let parsed = Polyhedron::parse(&frag.to_bytes()).unwrap().1;
+        assert_eq!(parsed, frag);
+    }
 }
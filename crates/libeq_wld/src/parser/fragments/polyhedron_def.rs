@@ -1,6 +1,6 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{Fragment, FragmentCount, FragmentParser, StringReference, WResult};
 
 use nom::Parser;
 use nom::multi::count;
@@ -123,13 +123,41 @@ impl Fragment for PolyhedronDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        [
+            FragmentCount::new("size1", self.size1 as usize, self.entries1.len()),
+            FragmentCount::new("size2", self.size2 as usize, self.entries2.len()),
+        ]
+        .into_iter()
+        .chain(self.entries2.iter().map(|(entry_size, entries)| {
+            FragmentCount::new("entries2.entry_size", *entry_size as usize, entries.len())
+        }))
+        .collect()
+    }
+
+    fn normalize(&mut self) {
+        self.size1 = self.entries1.len() as u32;
+        self.size2 = self.entries2.len() as u32;
+        for (entry_size, entries) in &mut self.entries2 {
+            *entry_size = entries.len() as u32;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -190,4 +218,36 @@ mod tests {
 
         assert_eq!(parsed.to_bytes(), data);
     }
+
+    #[test]
+    fn it_counts_entries() {
+        let mut frag = fixture();
+        assert!(frag.counts().iter().all(|c| c.count == c.len));
+
+        frag.entries2[1].1.pop();
+        let mismatched: Vec<_> = frag
+            .counts()
+            .into_iter()
+            .filter(|c| c.count != c.len)
+            .collect();
+        assert_eq!(mismatched.len(), 1);
+        assert_eq!(mismatched[0].field, "entries2.entry_size");
+        assert_eq!(mismatched[0].count, 4);
+        assert_eq!(mismatched[0].len, 3);
+    }
+
+    #[test]
+    fn it_normalizes() {
+        let mut frag = fixture();
+        frag.entries1.truncate(4);
+        frag.entries2.truncate(2);
+        frag.entries2[1].1 = vec![0, 1, 2];
+        frag.normalize();
+
+        assert_eq!(frag.size1, 4);
+        assert_eq!(frag.size2, 2);
+        assert_eq!(frag.entries2[1].0, 3);
+        let parsed = PolyhedronDef::parse(&frag.to_bytes()).unwrap().1;
+        assert_eq!(parsed, frag);
+    }
 }
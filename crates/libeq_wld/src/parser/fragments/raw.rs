@@ -0,0 +1,90 @@
+use std::any::Any;
+
+use super::{Fragment, StringReference};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, PartialEq)]
+/// A fragment that was kept as its original bytes because its type is unknown or
+/// its body failed to parse. See [crate::parser::WldDoc::parse_lenient].
+///
+/// Raw fragments serialize back to exactly the bytes they were read from.
+pub struct RawFragment {
+    /// Taken from the first field of the body when it looks like a name reference
+    /// (zero or negative), as it is for nearly all fragment types. Otherwise 0.
+    pub name_reference: StringReference,
+
+    /// The type id from the fragment header.
+    pub type_id: u32,
+
+    /// The complete fragment body, including any padding.
+    pub bytes: Vec<u8>,
+}
+
+impl RawFragment {
+    pub fn new(type_id: u32, bytes: &[u8]) -> Self {
+        let name_reference = match bytes.first_chunk::<4>().map(|b| i32::from_le_bytes(*b)) {
+            Some(idx) if idx <= 0 => StringReference::new(idx),
+            _ => StringReference::new(0),
+        };
+        Self {
+            name_reference,
+            type_id,
+            bytes: bytes.to_vec(),
+        }
+    }
+}
+
+impl Fragment for RawFragment {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn name_ref(&self) -> &StringReference {
+        &self.name_reference
+    }
+
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
+    fn type_id(&self) -> u32 {
+        self.type_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_the_name_reference() {
+        let frag = RawFragment::new(0x99, &[0xfe, 0xff, 0xff, 0xff, 0x01, 0x00]);
+        assert_eq!(frag.name_reference, StringReference::new(-2));
+
+        let frag = RawFragment::new(0x2c, &[0x10, 0x00, 0x00, 0x00]);
+        assert_eq!(frag.name_reference, StringReference::new(0));
+
+        let frag = RawFragment::new(0x99, &[0x01]);
+        assert_eq!(frag.name_reference, StringReference::new(0));
+    }
+
+    #[test]
+    fn it_serializes() {
+        let data = [0xfe, 0xff, 0xff, 0xff, 0x01, 0x02, 0x03, 0x04];
+        let frag = RawFragment::new(0x99, &data);
+
+        assert_eq!(frag.to_bytes(), data);
+        assert_eq!(Fragment::type_id(&frag), 0x99);
+    }
+}
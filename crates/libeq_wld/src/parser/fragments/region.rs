@@ -1,11 +1,13 @@
 use std::any::Any;
 
 use super::{
-    DmSpriteDef2, Fragment, FragmentParser, FragmentRef, RenderInfo, RenderMethod, StringReference,
-    WResult,
+    DmSpriteDef2, Fragment, FragmentCount, FragmentParser, FragmentRef, FragmentReference,
+    RenderInfo, RenderMethod, StringReference, WResult, remap_raw, set_flag,
 };
 
 use nom::Parser;
+use nom::combinator::map_opt;
+use nom::error::context;
 use nom::multi::count;
 use nom::number::complete::{le_f32, le_i32, le_u8, le_u16, le_u32};
 
@@ -97,10 +99,10 @@ pub struct Region {
     pub reverb_offset: Option<i32>,
 
     /// Length of USERDATA string
-    user_data_size: u32,
+    pub(crate) user_data_size: u32,
 
     /// USERDATA %s
-    user_data: Vec<u8>,
+    pub(crate) user_data: Vec<u8>,
 
     /// This does not appear in WLDCOM.
     /// WINDCATCHER:
@@ -297,13 +299,117 @@ impl Fragment for Region {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![
+            FragmentCount::new(
+                "num_region_vertex",
+                self.num_region_vertex as usize,
+                self.region_vertices.len(),
+            ),
+            FragmentCount::new(
+                "num_proximal_regions",
+                self.num_proximal_regions as usize,
+                self.proximal_regions.len(),
+            ),
+            FragmentCount::new("num_walls", self.num_walls as usize, self.walls.len()),
+            FragmentCount::new(
+                "num_obstacles",
+                self.num_obstacles as usize,
+                self.obstacles.len(),
+            ),
+            FragmentCount::new(
+                "num_vis_node",
+                self.num_vis_node as usize,
+                self.vis_nodes.len(),
+            ),
+            FragmentCount::new(
+                "num_vis_list",
+                self.num_vis_list as usize,
+                self.visible_lists.len(),
+            ),
+        ]
+    }
+
+    fn normalize(&mut self) {
+        self.num_region_vertex = self.region_vertices.len() as u32;
+        self.num_proximal_regions = self.proximal_regions.len() as u32;
+        self.num_walls = self.walls.len() as u32;
+        self.num_obstacles = self.obstacles.len() as u32;
+        self.num_vis_node = self.vis_nodes.len() as u32;
+        self.num_vis_list = self.visible_lists.len() as u32;
+        set_flag(
+            &mut self.flags.0,
+            RegionFlags::HAS_SPHERE,
+            self.sphere.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            RegionFlags::HAS_REVERB_VOLUME,
+            self.reverb_volume.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            RegionFlags::HAS_REVERB_OFFSET,
+            self.reverb_offset.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            RegionFlags::HAS_MESH_REFERENCE,
+            self.mesh_reference.is_some(),
+        );
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        [
+            Some(FragmentReference::new(
+                "ambient_light",
+                self.ambient_light.value(),
+            )),
+            self.mesh_reference
+                .as_ref()
+                .map(|r| r.reference("mesh_reference")),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(self.walls.iter().filter_map(|wall| {
+            wall.render_info.as_ref()?.simple_sprite_reference.map(|r| {
+                FragmentReference::new("walls.render_info.simple_sprite_reference", r as i32)
+            })
+        }))
+        .collect()
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.ambient_light.remap(f);
+        if let Some(r) = &mut self.mesh_reference {
+            r.remap(f);
+        }
+        for wall in &mut self.walls {
+            if let Some(r) = wall
+                .render_info
+                .as_mut()
+                .and_then(|i| i.simple_sprite_reference.as_mut())
+            {
+                remap_raw(r, f);
+            }
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -327,6 +433,10 @@ impl RegionFlags {
     const HAS_BYTE_ENTRIES: u32 = 0x80;
     const HAS_MESH_REFERENCE: u32 = 0x100;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (i, raw_flags) = le_u32(input)?;
         Ok((i, Self(raw_flags)))
@@ -376,23 +486,23 @@ impl RegionFlags {
 pub struct Wall {
     /// bit 0 - has FLOOR (is floor?)
     /// bit 1 - has RENDERMETHOD and NORMALABCD (is renderable?)
-    flags: WallFlags,
+    pub(crate) flags: WallFlags,
 
     /// NUMVERTICES %d
-    num_vertices: u32,
+    pub(crate) num_vertices: u32,
 
     /// RENDERMETHOD ...
-    render_method: Option<RenderMethod>,
+    pub(crate) render_method: Option<RenderMethod>,
 
     /// RENDERINFO
-    render_info: Option<RenderInfo>,
+    pub(crate) render_info: Option<RenderInfo>,
 
     /// NORMALABCD %f %f %f %f
-    normal_abcd: Option<(f32, f32, f32, f32)>,
+    pub(crate) normal_abcd: Option<(f32, f32, f32, f32)>,
 
     /// VERTEXLIST %d ...%d
     /// Binary values are 0 based. "VERTEXLIST 1" becomes vertex_list[0]
-    vertex_list: Vec<u32>,
+    pub(crate) vertex_list: Vec<u32>,
 }
 
 impl Wall {
@@ -476,6 +586,10 @@ impl WallFlags {
     const HAS_FLOOR: u32 = 0x01;
     const HAS_METHOD_AND_NORMAL: u32 = 0x02;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (i, raw_flags) = le_u32(input)?;
         Ok((i, Self(raw_flags)))
@@ -501,10 +615,10 @@ pub struct Obstacle {
     /// bit 0 - is a FLOOR
     /// bit 1 - is a GEOMETRYCUTTINGOBSTACLE
     /// bit 2 - has USERDATA %s
-    flags: ObstacleFlags,
+    pub(crate) flags: ObstacleFlags,
 
     /// NEXTREGION %d
-    next_region: i32,
+    pub(crate) next_region: i32,
 
     /// XY_VERTEX 0 %d
     /// XYZ_VERTEX 0 %d
@@ -514,34 +628,34 @@ pub struct Obstacle {
     /// PLANE 0 %d
     /// EDGEPOLYGON 0
     /// EDGEWALL 0 %d
-    obstacle_type: ObstacleType,
+    pub(crate) obstacle_type: ObstacleType,
 
     // NUMVERTICES %d
-    num_vertices: Option<u32>,
+    pub(crate) num_vertices: Option<u32>,
 
     /// VERTEXLIST %d ...%d
-    vertex_list: Option<Vec<u32>>,
+    pub(crate) vertex_list: Option<Vec<u32>>,
 
     /// NORMALABCD %f %f %f %f
-    normal_abcd: Option<(f32, f32, f32, f32)>,
+    pub(crate) normal_abcd: Option<(f32, f32, f32, f32)>,
 
     /// EDGEWALL 0 %d
     /// Binary values are 0 based. "EDGEWALL 0 1" becomes edge_wall[0]
-    edge_wall: Option<u32>,
+    pub(crate) edge_wall: Option<u32>,
 
     /// Length of USERDATA string
-    user_data_size: Option<u32>,
+    pub(crate) user_data_size: Option<u32>,
 
     /// USERDATA %s
-    user_data: Option<Vec<u8>>,
+    pub(crate) user_data: Option<Vec<u8>>,
 }
 
 impl Obstacle {
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (i, flags) = ObstacleFlags::parse(input)?;
         let (i, next_region) = le_i32(i)?;
-        let (i, obstacle_type) = le_i32(i)?;
-        let obstacle_type = FromPrimitive::from_i32(obstacle_type).unwrap();
+        let (i, obstacle_type) =
+            context("obstacle_type", map_opt(le_i32, FromPrimitive::from_i32)).parse(i)?;
 
         let (i, num_vertices) = if obstacle_type == ObstacleType::EdgePolygon
             || obstacle_type == ObstacleType::EdgePolygonNormalAbcd
@@ -649,6 +763,10 @@ impl ObstacleFlags {
     const IS_GEOMETRY_CUTTING: u32 = 0x02;
     const HAS_USER_DATA: u32 = 0x04;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (i, raw_flags) = le_u32(input)?;
         Ok((i, Self(raw_flags)))
@@ -673,7 +791,7 @@ impl ObstacleFlags {
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, PartialEq, Copy, Clone, FromPrimitive, ToPrimitive)]
-enum ObstacleType {
+pub(crate) enum ObstacleType {
     XyVertex = 8,
     XyzVertex = 9,
     XyLine = 10,
@@ -695,16 +813,16 @@ impl ObstacleType {
 #[derive(Debug, PartialEq)]
 pub struct VisNode {
     /// NORMALABCD %f %f %f %f
-    normal_abcd: (f32, f32, f32, f32),
+    pub(crate) normal_abcd: (f32, f32, f32, f32),
 
     /// VISLISTINDEX %d
-    vis_list_index: u32,
+    pub(crate) vis_list_index: u32,
 
     /// FRONTTREE %d
-    front_tree: u32,
+    pub(crate) front_tree: u32,
 
     /// BACKTREE %d
-    back_tree: u32,
+    pub(crate) back_tree: u32,
 }
 
 impl VisNode {
@@ -743,7 +861,7 @@ impl VisNode {
 #[derive(Debug, PartialEq)]
 pub struct VisibleList {
     /// RANGE %d
-    range_count: u16,
+    pub(crate) range_count: u16,
 
     /// ...%d
     /// This is a complicated field. It contains run-length-encoded data that tells the
@@ -782,12 +900,12 @@ pub struct VisibleList {
     /// It should be noted that the values in the range 0x40..0xBF allow skipping and
     /// including of no more than seven IDs at a time. Also, they are not necessary to
     /// encode a region list: they merely allow better compression.
-    ranges: Vec<RangeEntry>,
+    pub(crate) ranges: Vec<RangeEntry>,
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, PartialEq)]
-enum RangeEntry {
+pub(crate) enum RangeEntry {
     Byte(u8),
     Word(u16),
 }
@@ -1083,9 +1201,9 @@ mod tests {
         assert_eq!(frag.reverb_volume, None);
         assert_eq!(frag.reverb_offset, None);
         assert_eq!(frag.user_data_size, 0);
-        assert_eq!(frag.user_data, vec![]);
+        assert_eq!(frag.user_data, Vec::<u8>::new());
         assert_eq!(frag.mesh_reference, None);
-        assert_eq!(remaining, vec![]);
+        assert_eq!(remaining.len(), 0);
     }
 
     #[test]
@@ -1093,7 +1211,7 @@ mod tests {
         let data = &fixture_with_mesh().to_bytes()[..];
         let (remaining, frag) = Region::parse(data).unwrap();
         assert_eq!(frag.mesh_reference, Some(FragmentRef::new(132)));
-        assert_eq!(remaining, vec![]);
+        assert_eq!(remaining.len(), 0);
     }
 
     #[test]
@@ -1141,7 +1259,7 @@ mod tests {
         assert_eq!(frag.obstacles[0].edge_wall, None);
 
         assert_eq!(frag.user_data_size, 0);
-        assert_eq!(remaining, vec![]);
+        assert_eq!(remaining.len(), 0);
     }
 
     #[test]
@@ -1172,4 +1290,11 @@ mod tests {
 
         assert_eq!(parsed.to_bytes(), data);
     }
+
+    #[test]
+    fn it_fails_on_unknown_obstacle_type() {
+        let data = [0u32, 0, 7].map(u32::to_le_bytes).concat();
+
+        assert!(Obstacle::parse(&data).is_err());
+    }
 }
@@ -1,6 +1,9 @@
 use std::any::Any;
 
-use super::{BmInfo, Fragment, FragmentParser, FragmentRef, StringReference, WResult};
+use super::{
+    BmInfo, Fragment, FragmentCount, FragmentParser, FragmentRef, FragmentReference,
+    StringReference, WResult,
+};
 
 use nom::Parser;
 use nom::multi::count;
@@ -52,13 +55,12 @@ impl FragmentParser for SimpleSpriteDef {
             (StringReference::parse, SimpleSpriteDefFlags::parse, le_u32).parse(input)?;
 
         //TODO: Is this a thing? Find an example.
-        let (i, _current_frame) = if flags.has_current_frame() {
+        let (i, current_frame) = if flags.has_current_frame() {
             let (i, current_frame) = le_u32(i)?;
             (i, Some(current_frame))
         } else {
             (i, None)
         };
-        let current_frame = None;
 
         let (i, sleep) = if flags.is_animated() && flags.has_sleep() {
             let (i, sleep) = le_u32(i)?;
@@ -107,13 +109,46 @@ impl Fragment for SimpleSpriteDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "frame_count",
+            self.frame_count as usize,
+            self.frame_references.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.frame_count = self.frame_references.len() as u32;
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        self.frame_references
+            .iter()
+            .map(|r| r.reference("frame_references"))
+            .collect()
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        for r in &mut self.frame_references {
+            r.remap(f);
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -132,6 +167,10 @@ impl SimpleSpriteDefFlags {
     const HAS_SLEEP: u32 = 0x10;
     const HAS_CURRENT_FRAME: u32 = 0x20;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, SimpleSpriteDefFlags> {
         let (remaining, raw_flags) = le_u32(input)?;
         Ok((remaining, SimpleSpriteDefFlags(raw_flags)))
@@ -201,4 +240,20 @@ mod tests {
 
         assert_eq!(parsed.to_bytes(), data);
     }
+
+    #[test]
+    fn it_parses_current_frame() {
+        let mut frag = fixture();
+        // IS_ANIMATED | HAS_SLEEP | HAS_CURRENT_FRAME
+        frag.flags = SimpleSpriteDefFlags(0x38);
+        frag.current_frame = Some(1);
+        frag.sleep = Some(100);
+        let data = frag.to_bytes();
+        let parsed = SimpleSpriteDef::parse(&data).unwrap().1;
+
+        assert_eq!(parsed.current_frame, Some(1));
+        assert_eq!(parsed.sleep, Some(100));
+        assert_eq!(parsed.frame_references, vec![FragmentRef::new(0x02)]);
+        assert_eq!(parsed.to_bytes(), data);
+    }
 }
@@ -1,6 +1,6 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{Fragment, FragmentCount, FragmentParser, StringReference, WResult, set_flag};
 
 use nom::Parser;
 use nom::multi::count;
@@ -96,13 +96,38 @@ impl Fragment for SphereListDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "num_spheres",
+            self.num_spheres as usize,
+            self.spheres.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.num_spheres = self.spheres.len() as u32;
+        set_flag(
+            &mut self.flags.0,
+            SphereListDefFlags::HAS_SCALE_FACTOR,
+            self.scale_factor.is_some(),
+        );
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -118,6 +143,10 @@ impl From<SphereListDefFlags> for u32 {
 impl SphereListDefFlags {
     const HAS_SCALE_FACTOR: u32 = 0x01;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (i, raw_flags) = le_u32(input)?;
         Ok((i, Self(raw_flags)))
@@ -8,7 +8,10 @@ use nom::number::complete::{le_f32, le_i32, le_u32};
 use serde::{Deserialize, Serialize};
 
 use super::common::{RenderInfo, RenderMethod};
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{
+    Fragment, FragmentCount, FragmentParser, FragmentRef, FragmentReference, Sphere,
+    StringReference, WResult, remap_raw, set_flag,
+};
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, PartialEq)]
@@ -44,7 +47,7 @@ pub struct Sprite2DDef {
     /// _Unknown_
     /// NEW:
     /// SPHERE statement, references a 0x22 fragment
-    pub sphere_fragment: u32,
+    pub sphere_fragment: FragmentRef<Sphere>,
 
     /// Windcatcher:
     /// _Unknown_ - Only exists if bit 7 of flags is set.
@@ -100,7 +103,7 @@ impl FragmentParser for Sprite2DDef {
             le_u32,
             le_u32,
             (le_f32, le_f32),
-            le_u32,
+            FragmentRef::parse,
         )
             .parse(input)?;
 
@@ -176,7 +179,7 @@ impl Fragment for Sprite2DDef {
             &self.num_pitches.to_le_bytes()[..],
             &self.sprite_size.0.to_le_bytes()[..],
             &self.sprite_size.1.to_le_bytes()[..],
-            &self.sphere_fragment.to_le_bytes()[..],
+            &self.sphere_fragment.to_bytes()[..],
             &self
                 .depth_scale
                 .map_or(vec![], |d| d.to_le_bytes().to_vec())[..],
@@ -205,13 +208,93 @@ impl Fragment for Sprite2DDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "num_pitches",
+            self.num_pitches as usize,
+            self.pitches.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.num_pitches = self.pitches.len() as u32;
+        for pitch in &mut self.pitches {
+            pitch.num_headings = pitch.headings.len() as u32;
+        }
+        set_flag(
+            &mut self.flags.0,
+            SpriteFlags::HAS_DEPTH_SCALE,
+            self.depth_scale.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            SpriteFlags::HAS_CENTER_OFFSET,
+            self.center_offset.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            SpriteFlags::HAS_BOUNDING_RADIUS,
+            self.bounding_radius.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            SpriteFlags::HAS_CURRENT_FRAME,
+            self.current_frame.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            SpriteFlags::HAS_SLEEP,
+            self.sleep.is_some(),
+        );
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        std::iter::once(self.sphere_fragment.reference("sphere_fragment"))
+            .chain(
+                self.pitches
+                    .iter()
+                    .flat_map(|pitch| &pitch.headings)
+                    .flat_map(|heading| &heading.frames)
+                    .map(|r| FragmentReference::new("pitches.headings.frames", *r as i32)),
+            )
+            .chain(
+                self.render_info.simple_sprite_reference.map(|r| {
+                    FragmentReference::new("render_info.simple_sprite_reference", r as i32)
+                }),
+            )
+            .collect()
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.sphere_fragment.remap(f);
+        for r in self
+            .pitches
+            .iter_mut()
+            .flat_map(|pitch| &mut pitch.headings)
+            .flat_map(|heading| &mut heading.frames)
+        {
+            remap_raw(r, f);
+        }
+        if let Some(r) = &mut self.render_info.simple_sprite_reference {
+            remap_raw(r, f);
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -232,6 +315,10 @@ impl SpriteFlags {
     const SKIP_FRAMES: u32 = 0x40;
     const HAS_DEPTH_SCALE: u32 = 0x80;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (remaining, raw_flags) = le_u32(input)?;
         Ok((remaining, Self(raw_flags)))
@@ -380,7 +467,7 @@ mod tests {
             num_frames: 1,
             num_pitches: 1,
             sprite_size: (0.2, 0.2),
-            sphere_fragment: 0,
+            sphere_fragment: FragmentRef::new(0),
             depth_scale: None,
             center_offset: None,
             bounding_radius: Some(1.0198039),
@@ -394,7 +481,7 @@ mod tests {
                     frames: vec![1999], // reference to a texture
                 }],
             }],
-            render_method: RenderMethod::from_u32(1171),
+            render_method: RenderMethod::from_u32(1171).unwrap(),
             render_info: RenderInfo {
                 flags: RenderInfoFlags::new(7), // HAS_PEN | HAS_BRIGHTNESS | HAS_SCALED_AMBIENT
                 pen: Some(51),
@@ -416,7 +503,7 @@ mod tests {
         assert_eq!(frag.num_frames, 1);
         assert_eq!(frag.num_pitches, 1);
         assert_eq!(frag.sprite_size, (0.2, 0.2));
-        assert_eq!(frag.sphere_fragment, 0);
+        assert_eq!(frag.sphere_fragment, FragmentRef::new(0));
         assert_eq!(frag.depth_scale, None);
         assert_eq!(frag.center_offset, None);
         assert_eq!(frag.bounding_radius, Some(1.0198039));
@@ -427,7 +514,7 @@ mod tests {
         assert_eq!(frag.pitches[0].num_headings, 1);
         assert_eq!(frag.pitches[0].headings.len(), 1);
         assert_eq!(frag.pitches[0].headings[0].heading_cap, 64);
-        assert_eq!(frag.render_method, RenderMethod::from_u32(1171));
+        assert_eq!(frag.render_method, RenderMethod::from_u32(1171).unwrap());
         assert_eq!(
             frag.render_method,
             RenderMethod::Standard {
@@ -1,7 +1,10 @@
 use std::any::Any;
 
 use super::common::{RenderInfo, RenderMethod};
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{
+    Fragment, FragmentCount, FragmentParser, FragmentRef, FragmentReference, SphereList,
+    StringReference, WResult, remap_raw, set_flag,
+};
 
 use nom::Parser;
 use nom::multi::count;
@@ -32,7 +35,7 @@ pub struct Sprite3DDef {
     pub bsp_node_count: u32,
 
     /// SPHERELIST
-    pub sphere_list_reference: u32,
+    pub sphere_list_reference: FragmentRef<SphereList>,
 
     /// CENTEROFFSET %f %f %f
     pub center_offset: Option<(f32, f32, f32)>,
@@ -58,7 +61,7 @@ impl FragmentParser for Sprite3DDef {
         let (i, flags) = ThreeDSpriteFlags::parse(i)?;
         let (i, vertex_count) = le_u32(i)?;
         let (i, bsp_node_count) = le_u32(i)?;
-        let (i, sphere_list_reference) = le_u32(i)?;
+        let (i, sphere_list_reference) = FragmentRef::parse(i)?;
         let (i, center_offset) = if flags.has_center_offset() {
             (le_f32, le_f32, le_f32)
                 .parse(i)
@@ -98,7 +101,7 @@ impl Fragment for Sprite3DDef {
             &self.flags.to_bytes()[..],
             &self.vertex_count.to_le_bytes()[..],
             &self.bsp_node_count.to_le_bytes()[..],
-            &self.sphere_list_reference.to_le_bytes()[..],
+            &self.sphere_list_reference.to_bytes()[..],
             &self.center_offset.map_or(vec![], |c| {
                 [c.0.to_le_bytes(), c.1.to_le_bytes(), c.2.to_le_bytes()].concat()
             })[..],
@@ -123,13 +126,76 @@ impl Fragment for Sprite3DDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![
+            FragmentCount::new(
+                "vertex_count",
+                self.vertex_count as usize,
+                self.vertices.len(),
+            ),
+            FragmentCount::new(
+                "bsp_node_count",
+                self.bsp_node_count as usize,
+                self.bsp_nodes.len(),
+            ),
+        ]
+    }
+
+    fn normalize(&mut self) {
+        self.vertex_count = self.vertices.len() as u32;
+        self.bsp_node_count = self.bsp_nodes.len() as u32;
+        for node in &mut self.bsp_nodes {
+            node.vertex_count = node.vertex_indices.len() as u32;
+        }
+        set_flag(
+            &mut self.flags.0,
+            ThreeDSpriteFlags::HAS_CENTER_OFFSET,
+            self.center_offset.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            ThreeDSpriteFlags::HAS_BOUNDING_RADIUS,
+            self.bounding_radius.is_some(),
+        );
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        std::iter::once(
+            self.sphere_list_reference
+                .reference("sphere_list_reference"),
+        )
+        .chain(self.bsp_nodes.iter().filter_map(|node| {
+            node.render_info.simple_sprite_reference.map(|r| {
+                FragmentReference::new("bsp_nodes.render_info.simple_sprite_reference", r as i32)
+            })
+        }))
+        .collect()
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        self.sphere_list_reference.remap(f);
+        for node in &mut self.bsp_nodes {
+            if let Some(r) = &mut node.render_info.simple_sprite_reference {
+                remap_raw(r, f);
+            }
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -202,6 +268,10 @@ impl ThreeDSpriteFlags {
     const HAS_CENTER_OFFSET: u32 = 0x01;
     const HAS_BOUNDING_RADIUS: u32 = 0x02;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (remaining, raw_flags) = le_u32(input)?;
         Ok((remaining, Self(raw_flags)))
@@ -231,7 +301,7 @@ mod tests {
             flags: ThreeDSpriteFlags(0), // no center_offset or bounding_radius
             vertex_count: 4,
             bsp_node_count: 1,
-            sphere_list_reference: 0,
+            sphere_list_reference: FragmentRef::new(0),
             center_offset: None,
             bounding_radius: None,
             vertices: vec![
@@ -245,7 +315,7 @@ mod tests {
                 front_tree: 0,
                 back_tree: 0,
                 vertex_indices: vec![0, 1, 2, 3],
-                render_method: RenderMethod::from_u32(0),
+                render_method: RenderMethod::from_u32(0).unwrap(),
                 render_info: RenderInfo {
                     flags: RenderInfoFlags::new(1), // HAS_PEN
                     pen: Some(11),
@@ -270,7 +340,7 @@ mod tests {
         assert_eq!(frag.flags, ThreeDSpriteFlags(0));
         assert_eq!(frag.vertex_count, 4);
         assert_eq!(frag.bsp_node_count, 1);
-        assert_eq!(frag.sphere_list_reference, 0);
+        assert_eq!(frag.sphere_list_reference, FragmentRef::new(0));
         assert_eq!(frag.center_offset, None);
         assert_eq!(frag.bounding_radius, None);
         assert_eq!(
@@ -284,7 +354,10 @@ mod tests {
         );
         assert_eq!(frag.bsp_nodes.len(), 1);
         assert_eq!(frag.bsp_nodes[0].vertex_count, 4);
-        assert_eq!(frag.bsp_nodes[0].render_method, RenderMethod::from_u32(0));
+        assert_eq!(
+            frag.bsp_nodes[0].render_method,
+            RenderMethod::from_u32(0).unwrap()
+        );
         assert!(frag.bsp_nodes[0].render_info.flags.has_pen());
         assert!(!frag.bsp_nodes[0].render_info.flags.has_brightness());
         assert!(!frag.bsp_nodes[0].render_info.flags.has_scaled_ambient());
@@ -7,7 +7,9 @@ use nom::number::complete::{le_f32, le_u32};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{
+    Fragment, FragmentParser, FragmentReference, StringReference, WResult, remap_raw, set_flag,
+};
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, PartialEq)]
@@ -133,13 +135,73 @@ impl Fragment for Sprite4DDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn normalize(&mut self) {
+        if let Some(sprite_fragments) = &self.sprite_fragments {
+            self.num_frames = sprite_fragments.len() as u32;
+        }
+        set_flag(
+            &mut self.flags.0,
+            Sprite4DDefFlags::HAS_CENTER_OFFSET,
+            self.center_offset.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            Sprite4DDefFlags::HAS_BOUNDING_RADIUS,
+            self.bounding_radius.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            Sprite4DDefFlags::HAS_CURRENT_FRAME,
+            self.current_frame.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            Sprite4DDefFlags::HAS_SLEEP,
+            self.sleep.is_some(),
+        );
+        set_flag(
+            &mut self.flags.0,
+            Sprite4DDefFlags::HAS_SPRITES,
+            self.sprite_fragments.is_some(),
+        );
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        std::iter::once(FragmentReference::new(
+            "polygon_fragment",
+            self.polygon_fragment as i32,
+        ))
+        .chain(
+            self.sprite_fragments
+                .iter()
+                .flatten()
+                .map(|r| FragmentReference::new("sprite_fragments", *r as i32)),
+        )
+        .collect()
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        remap_raw(&mut self.polygon_fragment, f);
+        for r in self.sprite_fragments.iter_mut().flatten() {
+            remap_raw(r, f);
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -160,6 +222,10 @@ impl Sprite4DDefFlags {
     const HAS_SPRITES: u32 = 0x10;
     const SKIP_FRAMES: u32 = 0x40;
 
+    pub fn new(flags: u32) -> Self {
+        Self(flags)
+    }
+
     fn parse(input: &[u8]) -> WResult<'_, Self> {
         let (remaining, raw_flags) = le_u32(input)?;
         Ok((remaining, Self(raw_flags)))
@@ -1,6 +1,6 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{Fragment, FragmentCount, FragmentParser, StringReference, WResult, set_flag};
 
 use nom::Parser;
 use nom::multi::count;
@@ -52,7 +52,8 @@ pub struct TrackDef {
     pub name_reference: StringReference,
 
     /// Most flags are _unknown_.
-    /// * bit 3 - If set then the fragment uses `legacy_frame_transforms`
+    /// * bit 3 - If set then the fragment uses `frame_transforms`, otherwise
+    ///   `legacy_frame_transforms`
     pub flags: u32,
 
     /// The number of `FrameTransform` and `LegacyFrameTransform` entries there are.
@@ -65,6 +66,19 @@ pub struct TrackDef {
     pub legacy_frame_transforms: Option<Vec<LegacyFrameTransform>>,
 }
 
+impl TrackDef {
+    const HAS_FRAME_TRANSFORMS: u32 = 0x08;
+
+    /// The number of entries in whichever list of transforms is present.
+    fn transform_count(&self) -> usize {
+        self.frame_transforms
+            .as_ref()
+            .map(Vec::len)
+            .or(self.legacy_frame_transforms.as_ref().map(Vec::len))
+            .unwrap_or(0)
+    }
+}
+
 impl FragmentParser for TrackDef {
     type T = Self;
 
@@ -75,15 +89,16 @@ impl FragmentParser for TrackDef {
         let (i, name_reference) = StringReference::parse(input)?;
         let (i, flags) = le_u32(i)?;
         let (i, frame_count) = le_u32(i)?;
-        let (i, frame_transforms, legacy_frame_transforms) = if flags & 0x08 == 0x08 {
-            let (i, frame_transforms) =
-                count(FrameTransform::parse, frame_count as usize).parse(i)?;
-            (i, Some(frame_transforms), None)
-        } else {
-            let (i, legacy_frame_transforms) =
-                count(LegacyFrameTransform::parse, frame_count as usize).parse(i)?;
-            (i, None, Some(legacy_frame_transforms))
-        };
+        let (i, frame_transforms, legacy_frame_transforms) =
+            if flags & Self::HAS_FRAME_TRANSFORMS == Self::HAS_FRAME_TRANSFORMS {
+                let (i, frame_transforms) =
+                    count(FrameTransform::parse, frame_count as usize).parse(i)?;
+                (i, Some(frame_transforms), None)
+            } else {
+                let (i, legacy_frame_transforms) =
+                    count(LegacyFrameTransform::parse, frame_count as usize).parse(i)?;
+                (i, None, Some(legacy_frame_transforms))
+            };
 
         Ok((
             i,
@@ -120,13 +135,38 @@ impl Fragment for TrackDef {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "frame_count",
+            self.frame_count as usize,
+            self.transform_count(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.frame_count = self.transform_count() as u32;
+        set_flag(
+            &mut self.flags,
+            Self::HAS_FRAME_TRANSFORMS,
+            self.frame_transforms.is_some(),
+        );
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -460,4 +500,17 @@ mod tests {
 
         assert_eq!(parsed.to_bytes(), data);
     }
+
+    #[test]
+    fn it_normalizes() {
+        let mut frag = fixture_legacy_transforms();
+        frag.legacy_frame_transforms = None;
+        frag.frame_transforms = fixture_frame_transforms().frame_transforms;
+        frag.normalize();
+
+        assert_eq!(frag.flags, 0x8);
+        assert_eq!(frag.frame_count, 1);
+        let parsed = TrackDef::parse(&frag.to_bytes()).unwrap().1;
+        assert_eq!(parsed, frag);
+    }
 }
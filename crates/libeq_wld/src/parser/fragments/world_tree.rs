@@ -1,6 +1,9 @@
 use std::any::Any;
 
-use super::{Fragment, FragmentParser, FragmentRef, Region, StringReference, WResult};
+use super::{
+    Fragment, FragmentCount, FragmentParser, FragmentRef, FragmentReference, Region,
+    StringReference, WResult,
+};
 
 use nom::Parser;
 use nom::multi::count;
@@ -64,13 +67,46 @@ impl Fragment for WorldTree {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "world_node_count",
+            self.world_node_count as usize,
+            self.world_nodes.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.world_node_count = self.world_nodes.len() as u32;
+    }
+
+    fn references(&self) -> Vec<FragmentReference> {
+        self.world_nodes
+            .iter()
+            .map(|node| node.region.reference("world_nodes.region"))
+            .collect()
+    }
+
+    fn remap_references(&mut self, f: &mut dyn FnMut(i32) -> i32) {
+        for node in &mut self.world_nodes {
+            node.region.remap(f);
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
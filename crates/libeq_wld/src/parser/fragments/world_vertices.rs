@@ -1,4 +1,4 @@
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{Fragment, FragmentCount, FragmentParser, StringReference, WResult};
 use nom::Parser;
 use nom::multi::count;
 use nom::number::complete::{le_f32, le_u32};
@@ -61,13 +61,33 @@ impl Fragment for WorldVertices {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "num_vertices",
+            self.num_vertices as usize,
+            self.vertices.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.num_vertices = self.vertices.len() as u32;
+    }
 }
 
 #[cfg(test)]
@@ -1,8 +1,8 @@
 use std::any::Any;
 
-use crate::parser::strings::{decode_string, encode_string};
+use crate::parser::strings::EncodedString;
 
-use super::{Fragment, FragmentParser, StringReference, WResult};
+use super::{Fragment, FragmentCount, FragmentParser, StringReference, WResult};
 
 use nom::Parser;
 use nom::multi::count;
@@ -51,7 +51,7 @@ pub struct Zone {
     /// contain a “magic” string that told the client what was special about the included
     /// regions (e.g. WTN__01521000000000000000000000___000000000000). This field is padded
     /// with nulls to make it end on a DWORD boundary.
-    pub user_data: String,
+    pub user_data: EncodedString,
 }
 
 impl FragmentParser for Zone {
@@ -76,7 +76,7 @@ impl FragmentParser for Zone {
                 region_count,
                 regions,
                 user_data_size,
-                user_data: decode_string(&user_data).trim_end_matches("\0").to_string(),
+                user_data: EncodedString::decode(&user_data),
             },
         ))
     }
@@ -86,7 +86,7 @@ impl Fragment for Zone {
     fn to_bytes(&self) -> Vec<u8> {
         let user_data_size = self.user_data_size as usize;
         let padding = (4 - user_data_size % 4) % 4;
-        let mut user_data = encode_string(&format!("{}{}", &self.user_data, "\0"));
+        let mut user_data = self.user_data.encode(user_data_size);
         user_data.resize(user_data_size + padding, 0);
         [
             &self.name_reference.to_bytes()[..],
@@ -107,13 +107,33 @@ impl Fragment for Zone {
         self
     }
 
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     fn name_ref(&self) -> &StringReference {
         &self.name_reference
     }
 
+    fn name_ref_mut(&mut self) -> &mut StringReference {
+        &mut self.name_reference
+    }
+
     fn type_id(&self) -> u32 {
         Self::TYPE_ID
     }
+
+    fn counts(&self) -> Vec<FragmentCount> {
+        vec![FragmentCount::new(
+            "region_count",
+            self.region_count as usize,
+            self.regions.len(),
+        )]
+    }
+
+    fn normalize(&mut self) {
+        self.region_count = self.regions.len() as u32;
+    }
 }
 
 #[cfg(test)]
@@ -127,7 +147,7 @@ mod tests {
             region_count: 2,
             regions: vec![2859, 2865],
             user_data_size: 0,
-            user_data: String::new(),
+            user_data: EncodedString::default(),
         }
     }
 
@@ -138,7 +158,7 @@ mod tests {
             region_count: 2,
             regions: vec![4521, 4523],
             user_data_size: 47,
-            user_data: String::from("DRNTP00002-00030000357999999999___000000000000"),
+            user_data: "DRNTP00002-00030000357999999999___000000000000".into(),
         }
     }
 
@@ -180,6 +200,30 @@ mod tests {
         assert_eq!(parsed.to_bytes(), data);
     }
 
+    #[test]
+    fn it_keeps_user_data_bytes() {
+        let mut frag = fixture_user_data();
+        frag.user_data_size = 48;
+        let mut data = frag.to_bytes();
+        // Encoders pad with zeros, but this file pads with an encoded null
+        let user_data_end = data.len() - 1;
+        data[user_data_end] = 0x6a;
+        let parsed = Zone::parse(&data).unwrap().1;
+        assert!(parsed.user_data.has_custom_padding());
+        assert!(!parsed.user_data.is_lossy());
+        assert_eq!(parsed.to_bytes(), data);
+
+        frag.user_data_size = 47;
+        frag.user_data
+            .set("DRNTP00002-00030000357999999999___000000000001");
+        let parsed = Zone::parse(&frag.to_bytes()).unwrap().1;
+        assert_eq!(
+            parsed.user_data,
+            "DRNTP00002-00030000357999999999___000000000001"
+        );
+        assert!(!parsed.user_data.is_lossy());
+    }
+
     #[test]
     fn it_serializes_user_data() {
         let frag = fixture_user_data();
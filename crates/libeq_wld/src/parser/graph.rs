@@ -0,0 +1,75 @@
+use super::{FragmentReference, WldDoc};
+
+/// A resolved reference from one fragment to another. Both ends are 0-based positions
+/// of fragments in the document, as used by [WldDoc::at].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReferenceEdge {
+    pub source: usize,
+    pub target: usize,
+    /// The field of the source fragment holding the reference.
+    pub field: &'static str,
+}
+
+/// The references between all fragments of a [WldDoc], see [WldDoc::reference_graph].
+///
+/// This is a snapshot, it is not updated if the document changes.
+#[derive(Debug, Default)]
+pub struct ReferenceGraph {
+    outgoing: Vec<Vec<ReferenceEdge>>,
+    incoming: Vec<Vec<ReferenceEdge>>,
+    unresolved: Vec<(usize, FragmentReference)>,
+}
+
+impl ReferenceGraph {
+    pub(super) fn new(doc: &WldDoc) -> Self {
+        let count = doc.fragment_count();
+        let mut graph = Self {
+            outgoing: vec![Vec::new(); count],
+            incoming: vec![Vec::new(); count],
+            unresolved: Vec::new(),
+        };
+
+        for (source, fragment) in doc.iter().enumerate() {
+            for reference in fragment.references() {
+                if reference.value == 0 {
+                    continue;
+                }
+                match doc.resolve(reference.value) {
+                    Some(target) => {
+                        let edge = ReferenceEdge {
+                            source,
+                            target,
+                            field: reference.field,
+                        };
+                        graph.outgoing[source].push(edge);
+                        graph.incoming[target].push(edge);
+                    }
+                    None => graph.unresolved.push((source, reference)),
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// References held by the fragment at `idx`, in the order they appear in the fragment.
+    pub fn outgoing(&self, idx: usize) -> &[ReferenceEdge] {
+        self.outgoing.get(idx).map_or(&[], |edges| edges)
+    }
+
+    /// References to the fragment at `idx` from other fragments, ordered by source.
+    pub fn incoming(&self, idx: usize) -> &[ReferenceEdge] {
+        self.incoming.get(idx).map_or(&[], |edges| edges)
+    }
+
+    /// References (with the position of the fragment holding them) that point to an
+    /// index past the last fragment or to a name no fragment has.
+    pub fn unresolved(&self) -> &[(usize, FragmentReference)] {
+        &self.unresolved
+    }
+
+    /// Iterate over every resolved reference, ordered by source.
+    pub fn edges(&self) -> impl Iterator<Item = &ReferenceEdge> {
+        self.outgoing.iter().flatten()
+    }
+}
@@ -1,11 +1,15 @@
 mod error;
 pub mod fragments;
+mod graph;
 mod strings;
+mod table;
+mod validate;
+mod view;
 
 use core::fmt::Debug;
-use std::collections::BTreeMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
 
-use itertools::{Either, Itertools};
 use nom::IResult;
 use nom::Offset;
 use nom::Parser;
@@ -13,15 +17,21 @@ use nom::bytes::complete::take;
 pub use nom::error::{ErrorKind, context};
 use nom::multi::count;
 use nom::number::complete::{le_i32, le_u32};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 pub use error::WldDocError;
 pub use fragments::*;
-pub use strings::{StringHash, StringReference};
+pub use graph::{ReferenceEdge, ReferenceGraph};
+pub use strings::{EncodedString, StringHash, StringReference};
+pub use table::FragmentTableEntry;
+pub use validate::{ValidationIssue, ValidationReport};
+pub use view::WldView;
 
-pub type WResult<'a, O> = IResult<&'a [u8], O, WldDocError<'a>>;
+pub type WResult<'a, O> = IResult<&'a [u8], O, WldDocError>;
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug)]
@@ -29,65 +39,188 @@ pub struct WldDoc {
     header: WldHeader,
     strings: StringHash,
     fragments: Vec<FragmentType>,
+    /// Fragment names mapped to the position of the first fragment with that name.
+    /// Built when the document is parsed, or on first use for deserialized documents.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    names: OnceLock<HashMap<String, usize>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    game: FragmentGame,
+}
+
+/// How [WldDoc::parse_with_options] treats fragments that can't be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strictness {
+    /// Fail, reporting every such fragment. See [WldDoc::parse].
+    #[default]
+    Strict,
+    /// Keep them as [FragmentType::Raw] and report them as warnings. See
+    /// [WldDoc::parse_lenient].
+    Lenient,
+}
+
+/// Options for [WldDoc::parse_with_options].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParseOptions {
+    /// The game the file is from, or `None` to detect it.
+    pub game: Option<FragmentGame>,
+    pub strictness: Strictness,
+    /// Read fragments as this version of the format instead of the one in the header.
+    /// The header is updated to match, so the document is written back as this version.
+    pub version: Option<WldVersion>,
+}
+
+/// An empty document in the old (`0x00015500`) format, to be filled in with
+/// [WldDoc::add_fragment] and [WldDoc::intern_string].
+impl Default for WldDoc {
+    fn default() -> Self {
+        WldDoc::new(WldHeader::default(), StringHash::new(&[]), Vec::new())
+    }
 }
 
 impl WldDoc {
-    pub fn parse(input: &[u8]) -> Result<WldDoc, Vec<WldDocError<'_>>> {
-        let (i, header) = WldHeader::parse(input).map_err(|e| vec![e.into()])?;
+    fn new(header: WldHeader, strings: StringHash, fragments: Vec<FragmentType>) -> Self {
+        let doc = WldDoc {
+            header,
+            strings,
+            fragments,
+            names: OnceLock::new(),
+            game: FragmentGame::default(),
+        };
+        doc.names();
+        doc
+    }
 
-        let (i, string_hash_data) = take(header.string_hash_size)
-            .parse(i)
-            .map_err(|e| vec![e.into()])?;
-        let strings = StringHash::new(string_hash_data);
+    fn names(&self) -> &HashMap<String, usize> {
+        self.names.get_or_init(|| {
+            let mut names = HashMap::new();
+            for (idx, fragment) in self.fragments.iter().enumerate() {
+                match self.strings.get(*fragment.name_ref()) {
+                    Some(name) if !name.is_empty() => {
+                        names.entry(name.to_string()).or_insert(idx);
+                    }
+                    _ => {}
+                }
+            }
+            names
+        })
+    }
 
-        let (_i, fragment_headers) = count(FragmentHeader::parse, header.fragment_count as usize)
-            .parse(i)
-            .map_err(|e| vec![e.into()])?;
+    pub fn parse(input: &[u8]) -> Result<WldDoc, Vec<WldDocError>> {
+        Self::parse_with_options(input, ParseOptions::default()).map(|(doc, _)| doc)
+    }
 
-        let (fragments, errors): (Vec<_>, Vec<_>) = fragment_headers
-            .into_iter()
+    /// Parse a wld file, carrying on past fragments that can't be parsed.
+    ///
+    /// Fragments of an unknown type, or whose body fails to parse, are kept as
+    /// [FragmentType::Raw] so that the document still serializes back to the same
+    /// data. The errors for those fragments are returned alongside the document as
+    /// warnings. Errors in the file header, string hash or fragment headers are
+    /// still fatal.
+    #[allow(clippy::type_complexity)]
+    pub fn parse_lenient(input: &[u8]) -> Result<(WldDoc, Vec<WldDocError>), Vec<WldDocError>> {
+        Self::parse_with_options(
+            input,
+            ParseOptions {
+                strictness: Strictness::Lenient,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Parse a wld file as described by `options`.
+    ///
+    /// Unless given, the game is detected from the fragment types whose layout differs
+    /// between games (currently only 0x2c) and then used for every fragment, so that all
+    /// of them are read the same way. The game and version used are recorded on the
+    /// document, see [WldDoc::game] and [WldDoc::version].
+    ///
+    /// Warnings are only returned with [Strictness::Lenient], see [WldDoc::parse_lenient].
+    ///
+    /// With the `rayon` feature enabled fragment bodies are parsed in parallel. The
+    /// fragments and errors are returned in the same order either way.
+    #[allow(clippy::type_complexity)]
+    pub fn parse_with_options(
+        input: &[u8],
+        options: ParseOptions,
+    ) -> Result<(WldDoc, Vec<WldDocError>), Vec<WldDocError>> {
+        let (header, strings, fragment_headers) = Self::parse_headers(input)?;
+        let version = options.version.unwrap_or_else(|| header.version());
+        let game = options
+            .game
+            .unwrap_or_else(|| detect_game(&fragment_headers));
+
+        let parse_body = |(idx, (offset, h)): (usize, &(usize, FragmentHeader))| {
+            h.parse_body(version, game, idx, *offset)
+        };
+        #[cfg(feature = "rayon")]
+        let results: Vec<_> = fragment_headers
+            .par_iter()
+            .enumerate()
+            .map(parse_body)
+            .collect();
+        #[cfg(not(feature = "rayon"))]
+        let results: Vec<_> = fragment_headers
+            .iter()
             .enumerate()
-            .map(|(idx, h)| h.parse_body(idx))
-            .partition_map(|res| match res {
-                Ok(frag) => Either::Left(frag),
-                Err(e) => Either::Right(e),
-            });
+            .map(parse_body)
+            .collect();
+
+        let mut errors = vec![];
+        let fragments = results
+            .into_iter()
+            .zip(&fragment_headers)
+            .map(|(result, (_, h))| {
+                result.unwrap_or_else(|e| {
+                    errors.push(e);
+                    FragmentType::Raw(RawFragment::new(h.fragment_type, h.field_data))
+                })
+            })
+            .collect();
 
-        if !errors.is_empty() {
+        if options.strictness == Strictness::Strict && !errors.is_empty() {
             return Err(errors);
         }
 
-        Ok(WldDoc {
-            header,
-            strings,
-            fragments,
-        })
+        let mut doc = WldDoc::new(header, strings, fragments);
+        // Only an override changes the header, unknown version numbers are kept as is.
+        if let Some(version) = options.version {
+            doc.header.version = version.into();
+        }
+        doc.game = game;
+        Ok((doc, errors))
     }
 
-    pub fn fragment_headers_by_offset(input: &[u8]) -> BTreeMap<usize, FragmentHeader<'_>> {
-        let (i, header) = WldHeader::parse(input)
-            .unwrap_or_else(|_| panic!("{:?}", &input[..std::mem::size_of::<WldHeader>()]));
-        let (_, i) = i.split_at(header.string_hash_size as usize);
+    /// Parse everything up to the fragment bodies. Fragment headers are returned
+    /// along with the offset of their body in the file.
+    #[allow(clippy::type_complexity)]
+    fn parse_headers(
+        input: &[u8],
+    ) -> Result<(WldHeader, StringHash, Vec<(usize, FragmentHeader<'_>)>), Vec<WldDocError>> {
+        let header_error = |e: nom::Err<WldDocError>| match e.into() {
+            WldDocError::Parse { remaining, message } => vec![WldDocError::ParseHeader {
+                offset: input.len().saturating_sub(remaining),
+                message,
+            }],
+            e => vec![e],
+        };
 
-        //let (i, _): (&[u8], &[u8]) =
-        //    take::<u32, &[u8], nom::error::Error<&[u8]>>(header.string_hash_size)(i).unwrap();
+        let (i, header) = WldHeader::parse(input).map_err(header_error)?;
 
-        let mut fragment_headers = BTreeMap::new();
-        let mut remaining = i;
-        for idx in 0..header.fragment_count {
-            let offset = input.len() - remaining.len();
-            println!("Parsing fragment header {} at offset {:#10x}", idx, offset);
+        let (i, string_hash_data) = take(header.string_hash_size)
+            .parse(i)
+            .map_err(header_error)?;
+        let strings = StringHash::new(string_hash_data);
 
-            let (x, fragment_header) = FragmentHeader::parse(remaining).unwrap_or_else(|_| {
-                panic!(
-                    "Failed to parse fragment header {} at offset {:#10x}",
-                    idx, offset
-                )
-            });
-            fragment_headers.insert(offset, fragment_header);
-            remaining = x;
-        }
-        fragment_headers
+        let (_i, fragment_headers) = count(FragmentHeader::parse, header.fragment_count as usize)
+            .parse(i)
+            .map_err(header_error)?;
+
+        let fragment_headers = fragment_headers
+            .into_iter()
+            .map(|h| (input.offset(h.field_data), h))
+            .collect();
+
+        Ok((header, strings, fragment_headers))
     }
 
     pub fn dump_raw_fragments(input: &[u8]) -> WResult<'_, Vec<FragmentHeader<'_>>> {
@@ -105,7 +238,7 @@ impl WldDoc {
     }
 
     /// Get a fragment given a fragment reference.
-    pub fn get<T: 'static + Fragment>(&self, fragment_ref: &FragmentRef<T>) -> Option<&T> {
+    pub fn get<T: FragmentTarget>(&self, fragment_ref: &FragmentRef<T>) -> Option<&T> {
         match fragment_ref {
             FragmentRef::Name(_, _) => self.get_by_name_ref(fragment_ref),
             FragmentRef::Index(_, _) => self.get_by_index_ref(fragment_ref),
@@ -117,6 +250,81 @@ impl WldDoc {
         self.fragments.get(idx)
     }
 
+    /// Get a fragment by its name. If several fragments share a name the first is returned.
+    pub fn get_by_name(&self, name: &str) -> Option<&FragmentType> {
+        self.fragments.get(*self.names().get(name)?)
+    }
+
+    /// Get a fragment of a specific type by its name.
+    pub fn find<T: 'static + Fragment>(&self, name: &str) -> Option<&T> {
+        self.get_by_name(name)?.as_any().downcast_ref()
+    }
+
+    /// Compute the references between all fragments, to find both the fragments a
+    /// fragment refers to and the fragments that refer to it.
+    pub fn reference_graph(&self) -> ReferenceGraph {
+        ReferenceGraph::new(self)
+    }
+
+    /// Recompute the header and the count fields and flags of every fragment from the
+    /// data they describe. Use this after editing a document so that it serializes to a
+    /// valid file.
+    pub fn normalize(&mut self) {
+        for fragment in self.fragments.iter_mut() {
+            fragment.normalize();
+        }
+        self.header = self.computed_header();
+    }
+
+    /// The header as it should be for the current fragments and strings.
+    ///
+    /// `max_object_bytes` is taken to be the size of the largest fragment body and
+    /// `string_count` the number of strings in the string hash.
+    fn computed_header(&self) -> WldHeader {
+        WldHeader {
+            magic: self.header.magic,
+            version: self.header.version,
+            fragment_count: self.fragments.len() as u32,
+            region_count: self.fragment_iter::<Region>().count() as u32,
+            max_object_bytes: self
+                .fragments
+                .iter()
+                .map(|f| {
+                    f.to_bytes_versioned(self.version())
+                        .len()
+                        .next_multiple_of(4)
+                })
+                .max()
+                .unwrap_or(0) as u32,
+            string_hash_size: self.strings.to_bytes().len() as u32,
+            string_count: self.strings.len() as u32,
+        }
+    }
+
+    /// Check the document for inconsistencies: header fields and fragment count fields
+    /// that don't match the data, references that don't resolve or resolve to the wrong
+    /// type of fragment, and region or vertex indices that are out of range.
+    ///
+    /// See [WldDoc::normalize] for how the header is expected to be filled in.
+    /// `max_object_bytes` and `string_count` are not checked, as it hasn't been confirmed
+    /// that game files fill them in that way.
+    pub fn validate(&self) -> ValidationReport {
+        validate::validate(self)
+    }
+
+    /// Find the position of the fragment a reference value points to. Positive values
+    /// are 1-based indices and negative values are name references.
+    fn resolve(&self, value: i32) -> Option<usize> {
+        match value {
+            0 => None,
+            v if v > 0 => Some(v as usize - 1).filter(|idx| *idx < self.fragments.len()),
+            v => self
+                .names()
+                .get(self.strings.get(StringReference::new(v))?)
+                .copied(),
+        }
+    }
+
     /// Iterate over all fragments of a specific type
     pub fn fragment_iter<'a, T: 'static + Fragment>(&'a self) -> impl Iterator<Item = &'a T> + 'a {
         self.fragments
@@ -129,41 +337,168 @@ impl WldDoc {
         self.fragments.iter()
     }
 
-    fn get_by_index_ref<T: 'static + Fragment>(&self, fragment_ref: &FragmentRef<T>) -> Option<&T> {
+    /// Get a mutable fragment given a fragment reference.
+    pub fn get_mut<T: FragmentTarget>(&mut self, fragment_ref: &FragmentRef<T>) -> Option<&mut T> {
+        let idx = match fragment_ref {
+            FragmentRef::Index(idx, _) => (*idx as usize).checked_sub(1)?,
+            FragmentRef::Name(name_ref, _) => *self.names().get(self.strings.get(*name_ref)?)?,
+        };
+        T::from_fragment_mut(self.at_mut(idx)?)
+    }
+
+    /// Get a mutable fragment given an index
+    pub fn at_mut(&mut self, idx: usize) -> Option<&mut FragmentType> {
+        // The fragment may be renamed
+        self.names.take();
+        self.fragments.get_mut(idx)
+    }
+
+    /// Iterate mutably over all fragments of a specific type
+    pub fn fragment_iter_mut<'a, T: 'static + Fragment>(
+        &'a mut self,
+    ) -> impl Iterator<Item = &'a mut T> + 'a {
+        self.names.take();
+        self.fragments
+            .iter_mut()
+            .filter_map(|f| f.as_any_mut().downcast_mut::<T>())
+    }
+
+    /// Add a fragment to the end of the document, returning its index.
+    pub fn add_fragment(&mut self, fragment: FragmentType) -> usize {
+        self.names.take();
+        self.fragments.push(fragment);
+        self.fragments.len() - 1
+    }
+
+    /// Remove the fragment at `idx`, returning it.
+    ///
+    /// Index references to the fragments after it are renumbered to keep pointing at the
+    /// same fragments and index references to the removed fragment are set to 0 (no
+    /// reference). Name references are left as they are. References inside
+    /// [FragmentType::Raw] fragments are not known, so they are not renumbered.
+    pub fn remove_fragment(&mut self, idx: usize) -> Option<FragmentType> {
+        if idx >= self.fragments.len() {
+            return None;
+        }
+        self.names.take();
+        let removed = self.fragments.remove(idx);
+
+        let removed_ref = idx as i32 + 1;
+        let mut renumber = |value: i32| match value {
+            v if v == removed_ref => 0,
+            v if v > removed_ref => v - 1,
+            v => v,
+        };
+        for fragment in self.fragments.iter_mut() {
+            fragment.remap_references(&mut renumber);
+        }
+        Some(removed)
+    }
+
+    /// Replace the fragment at `idx`, returning the old fragment. Returns `None`, dropping
+    /// `fragment`, if there is no fragment at `idx`.
+    pub fn replace_fragment(&mut self, idx: usize, fragment: FragmentType) -> Option<FragmentType> {
+        let slot = self.at_mut(idx)?;
+        Some(std::mem::replace(slot, fragment))
+    }
+
+    /// Get a reference to `string` in the string hash, adding it if it isn't already there.
+    pub fn intern_string(&mut self, string: &str) -> StringReference {
+        self.strings.intern(string)
+    }
+
+    /// Remove the strings that nothing in the document refers to from the string hash
+    /// and update every reference to the strings that are moved. Returns the number of
+    /// strings removed.
+    ///
+    /// Returns `None` without changing anything if the document has any
+    /// [FragmentType::Raw] fragments, as the references within them can't be updated.
+    /// Call [WldDoc::normalize] afterwards to update the header.
+    pub fn gc_strings(&mut self) -> Option<usize> {
+        if self
+            .fragments
+            .iter()
+            .any(|f| matches!(f, FragmentType::Raw(_)))
+        {
+            return None;
+        }
+
+        let mut used = HashSet::new();
+        for fragment in self.fragments.iter() {
+            used.extend(
+                fragment
+                    .string_references()
+                    .iter()
+                    .map(|r| r.0.unsigned_abs() as usize),
+            );
+            used.extend(
+                fragment
+                    .references()
+                    .iter()
+                    .filter(|r| r.value < 0)
+                    .map(|r| r.value.unsigned_abs() as usize),
+            );
+        }
+
+        let count = self.strings.len();
+        let offsets = self.strings.retain(|offset, _| used.contains(&offset));
+        let removed = count - self.strings.len();
+        if removed == 0 {
+            return Some(0);
+        }
+
+        // References keep their sign, only fragment names are expected to be negative
+        let remap = |value: i32| match offsets.get(&(value.unsigned_abs() as usize)) {
+            Some(offset) => *offset as i32 * value.signum(),
+            None => value,
+        };
+        for fragment in self.fragments.iter_mut() {
+            fragment.remap_string_references(&mut |r| StringReference::new(remap(r.0)));
+            fragment.remap_references(&mut |value| if value < 0 { remap(value) } else { value });
+        }
+        Some(removed)
+    }
+
+    fn get_by_index_ref<T: FragmentTarget>(&self, fragment_ref: &FragmentRef<T>) -> Option<&T> {
         let idx = if let FragmentRef::Index(idx, _) = fragment_ref {
             idx
         } else {
             return None;
         };
 
-        self.fragments
-            .get((idx - 1) as usize)?
-            .as_any()
-            .downcast_ref()
+        T::from_fragment(self.fragments.get((*idx as usize).checked_sub(1)?)?)
     }
 
-    fn get_by_name_ref<T: 'static + Fragment>(&self, fragment_ref: &FragmentRef<T>) -> Option<&T> {
+    fn get_by_name_ref<T: FragmentTarget>(&self, fragment_ref: &FragmentRef<T>) -> Option<&T> {
         let name_ref = if let FragmentRef::Name(name_ref, _) = fragment_ref {
             *name_ref
         } else {
             return None;
         };
 
-        if let Some(target_name) = self.strings.get(name_ref) {
-            self.fragments
-                .iter()
-                .find(|f| self.strings.get(*f.name_ref()) == Some(target_name))?
-                .as_any()
-                .downcast_ref()
-        } else {
-            None
-        }
+        T::from_fragment(self.get_by_name(self.strings.get(name_ref)?)?)
     }
 
     pub fn fragment_count(&self) -> usize {
         self.fragments.len()
     }
 
+    /// The version of the format the document was read from and will be written as.
+    pub fn version(&self) -> WldVersion {
+        self.header.version()
+    }
+
+    /// Change the version of the format the document will be written as. Fragments are
+    /// converted as they are serialized.
+    pub fn set_version(&mut self, version: WldVersion) {
+        self.header.version = version.into();
+    }
+
+    /// The game the document was read as, see [WldDoc::parse_with_options].
+    pub fn game(&self) -> FragmentGame {
+        self.game
+    }
+
     pub fn header_bytes(&self) -> Vec<u8> {
         self.header.to_bytes()
     }
@@ -179,7 +514,7 @@ impl WldDoc {
             self.fragments
                 .iter()
                 .flat_map(|f| {
-                    let mut field_data = f.to_bytes();
+                    let mut field_data = f.to_bytes_versioned(self.version());
                     let size = field_data.len();
                     // Field data must be padded so that it aligns on 4 bytes
                     if (size % 4) > 0 {
@@ -228,6 +563,20 @@ pub struct WldHeader {
     string_count: u32,
 }
 
+impl Default for WldHeader {
+    fn default() -> Self {
+        WldHeader {
+            magic: 0x54503d02,
+            version: 0x00015500,
+            fragment_count: 0,
+            region_count: 0,
+            max_object_bytes: 0,
+            string_hash_size: 0,
+            string_count: 0,
+        }
+    }
+}
+
 impl WldHeader {
     pub fn parse(input: &[u8]) -> WResult<'_, WldHeader> {
         let (i, magic) = le_u32(input)?;
@@ -252,6 +601,10 @@ impl WldHeader {
         ))
     }
 
+    pub fn version(&self) -> WldVersion {
+        WldVersion::from_u32(self.version)
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         [
             &self.magic.to_le_bytes()[..],
@@ -266,6 +619,25 @@ impl WldHeader {
     }
 }
 
+/// The game whose 0x2c fragment layout matches most of the 0x2c fragments, or
+/// EverQuest if there are none. Ties go to EverQuest, then Tanarus.
+fn detect_game(fragment_headers: &[(usize, FragmentHeader<'_>)]) -> FragmentGame {
+    let detected: Vec<_> = fragment_headers
+        .iter()
+        .filter(|(_, h)| h.fragment_type == DmSpriteDef::TYPE_ID)
+        .map(|(_, h)| h.detect_0x2c_variant())
+        .collect();
+    [
+        FragmentGame::EverQuest,
+        FragmentGame::Tanarus,
+        FragmentGame::ReturnToKrondor,
+    ]
+    .into_iter()
+    .rev()
+    .max_by_key(|game| detected.iter().filter(|d| *d == game).count())
+    .unwrap_or_default()
+}
+
 type FragmentTypeId = u32;
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -315,191 +687,248 @@ impl<'a> FragmentHeader<'a> {
         ))
     }
 
-    fn parse_body(self, index: usize) -> Result<FragmentType, WldDocError<'a>> {
+    /// The name reference of the fragment, read without parsing the rest of the body.
+    /// Every fragment starts with its name reference except for those that have no name.
+    fn name_reference(&self, game: FragmentGame) -> StringReference {
+        let unnamed = self.fragment_type == DefaultPaletteFile::TYPE_ID
+            || (self.fragment_type == DmSpriteDef::TYPE_ID && game == FragmentGame::Tanarus);
+        match self.field_data.first_chunk::<4>() {
+            Some(b) if !unnamed && i32::from_le_bytes(*b) <= 0 => {
+                StringReference::new(i32::from_le_bytes(*b))
+            }
+            _ => StringReference::new(0),
+        }
+    }
+
+    /// Parse the fragment body as laid out in a file of the given `version` from the
+    /// given `game`. `fragment_offset` is the offset of the body in the file and is only
+    /// used for error reporting.
+    fn parse_body(
+        &self,
+        version: WldVersion,
+        game: FragmentGame,
+        index: usize,
+        fragment_offset: usize,
+    ) -> Result<FragmentType, WldDocError> {
         let parsed = match self.fragment_type {
-            DmSpriteDef::TYPE_ID => match self.detect_0x2c_variant() {
+            DmSpriteDef::TYPE_ID => match game {
                 FragmentGame::EverQuest => Some(
-                    DmSpriteDef::parse(self.field_data)
+                    DmSpriteDef::parse_versioned(self.field_data, version)
                         .map(|f| (f.0, FragmentType::DmSpriteDef(f.1))),
                 ),
                 FragmentGame::ReturnToKrondor => Some(
-                    BmInfoRtk::parse(self.field_data).map(|f| (f.0, FragmentType::BmInfoRtk(f.1))),
+                    BmInfoRtk::parse_versioned(self.field_data, version)
+                        .map(|f| (f.0, FragmentType::BmInfoRtk(f.1))),
                 ),
                 FragmentGame::Tanarus => Some(
-                    WorldVertices::parse(self.field_data)
+                    WorldVertices::parse_versioned(self.field_data, version)
                         .map(|f| (f.0, FragmentType::WorldVertices(f.1))),
                 ),
             },
             BlitSpriteDef::TYPE_ID => Some(
-                BlitSpriteDef::parse(self.field_data)
+                BlitSpriteDef::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::BlitSpriteDef(f.1))),
             ),
             BlitSprite::TYPE_ID => Some(
-                BlitSprite::parse(self.field_data).map(|f| (f.0, FragmentType::BlitSprite(f.1))),
+                BlitSprite::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::BlitSprite(f.1))),
             ),
             DmRGBTrack::TYPE_ID => Some(
-                DmRGBTrack::parse(self.field_data).map(|f| (f.0, FragmentType::DmRGBTrack(f.1))),
+                DmRGBTrack::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::DmRGBTrack(f.1))),
             ),
             DmRGBTrackDef::TYPE_ID => Some(
-                DmRGBTrackDef::parse(self.field_data)
+                DmRGBTrackDef::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::DmRGBTrackDef(f.1))),
             ),
             DmTrackDef2::TYPE_ID => Some(
-                DmTrackDef2::parse(self.field_data).map(|f| (f.0, FragmentType::DmTrackDef2(f.1))),
+                DmTrackDef2::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::DmTrackDef2(f.1))),
+            ),
+            DmTrack::TYPE_ID => Some(
+                DmTrack::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::DmTrack(f.1))),
             ),
-            DmTrack::TYPE_ID => {
-                Some(DmTrack::parse(self.field_data).map(|f| (f.0, FragmentType::DmTrack(f.1))))
-            }
             AmbientLight::TYPE_ID => Some(
-                AmbientLight::parse(self.field_data)
+                AmbientLight::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::AmbientLight(f.1))),
             ),
-            Zone::TYPE_ID => {
-                Some(Zone::parse(self.field_data).map(|f| (f.0, FragmentType::Zone(f.1))))
-            }
+            Zone::TYPE_ID => Some(
+                Zone::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::Zone(f.1))),
+            ),
             PointLight::TYPE_ID => Some(
-                PointLight::parse(self.field_data).map(|f| (f.0, FragmentType::PointLight(f.1))),
+                PointLight::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::PointLight(f.1))),
+            ),
+            Light::TYPE_ID => Some(
+                Light::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::Light(f.1))),
+            ),
+            LightDef::TYPE_ID => Some(
+                LightDef::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::LightDef(f.1))),
             ),
-            Light::TYPE_ID => {
-                Some(Light::parse(self.field_data).map(|f| (f.0, FragmentType::Light(f.1))))
-            }
-            LightDef::TYPE_ID => {
-                Some(LightDef::parse(self.field_data).map(|f| (f.0, FragmentType::LightDef(f.1))))
-            }
             Polyhedron::TYPE_ID => Some(
-                Polyhedron::parse(self.field_data).map(|f| (f.0, FragmentType::Polyhedron(f.1))),
+                Polyhedron::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::Polyhedron(f.1))),
             ),
             PolyhedronDef::TYPE_ID => Some(
-                PolyhedronDef::parse(self.field_data)
+                PolyhedronDef::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::PolyhedronDef(f.1))),
             ),
             GlobalAmbientLightDef::TYPE_ID => Some(
-                GlobalAmbientLightDef::parse(self.field_data)
+                GlobalAmbientLightDef::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::GlobalAmbientLightDef(f.1))),
             ),
-            Sphere::TYPE_ID => {
-                Some(Sphere::parse(self.field_data).map(|f| (f.0, FragmentType::Sphere(f.1))))
-            }
+            Sphere::TYPE_ID => Some(
+                Sphere::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::Sphere(f.1))),
+            ),
             HierarchicalSprite::TYPE_ID => Some(
-                HierarchicalSprite::parse(self.field_data)
+                HierarchicalSprite::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::HierarchicalSprite(f.1))),
             ),
-            Sprite3D::TYPE_ID => {
-                Some(Sprite3D::parse(self.field_data).map(|f| (f.0, FragmentType::Sprite3D(f.1))))
-            }
+            Sprite3D::TYPE_ID => Some(
+                Sprite3D::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::Sprite3D(f.1))),
+            ),
             Sprite3DDef::TYPE_ID => Some(
-                Sprite3DDef::parse(self.field_data).map(|f| (f.0, FragmentType::Sprite3DDef(f.1))),
+                Sprite3DDef::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::Sprite3DDef(f.1))),
+            ),
+            Sprite2D::TYPE_ID => Some(
+                Sprite2D::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::Sprite2D(f.1))),
             ),
-            Sprite2D::TYPE_ID => {
-                Some(Sprite2D::parse(self.field_data).map(|f| (f.0, FragmentType::Sprite2D(f.1))))
-            }
             Sprite2DDef::TYPE_ID => Some(
-                Sprite2DDef::parse(self.field_data).map(|f| (f.0, FragmentType::Sprite2DDef(f.1))),
+                Sprite2DDef::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::Sprite2DDef(f.1))),
+            ),
+            Actor::TYPE_ID => Some(
+                Actor::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::Actor(f.1))),
+            ),
+            Track::TYPE_ID => Some(
+                Track::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::Track(f.1))),
+            ),
+            TrackDef::TYPE_ID => Some(
+                TrackDef::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::TrackDef(f.1))),
             ),
-            Actor::TYPE_ID => {
-                Some(Actor::parse(self.field_data).map(|f| (f.0, FragmentType::Actor(f.1))))
-            }
-            Track::TYPE_ID => {
-                Some(Track::parse(self.field_data).map(|f| (f.0, FragmentType::Track(f.1))))
-            }
-            TrackDef::TYPE_ID => {
-                Some(TrackDef::parse(self.field_data).map(|f| (f.0, FragmentType::TrackDef(f.1))))
-            }
             HierarchicalSpriteDef::TYPE_ID => Some(
-                HierarchicalSpriteDef::parse(self.field_data)
+                HierarchicalSpriteDef::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::HierarchicalSpriteDef(f.1))),
             ),
-            ActorDef::TYPE_ID => {
-                Some(ActorDef::parse(self.field_data).map(|f| (f.0, FragmentType::ActorDef(f.1))))
-            }
-            WorldTree::TYPE_ID => {
-                Some(WorldTree::parse(self.field_data).map(|f| (f.0, FragmentType::WorldTree(f.1))))
-            }
-            Region::TYPE_ID => {
-                Some(Region::parse(self.field_data).map(|f| (f.0, FragmentType::Region(f.1))))
-            }
+            ActorDef::TYPE_ID => Some(
+                ActorDef::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::ActorDef(f.1))),
+            ),
+            WorldTree::TYPE_ID => Some(
+                WorldTree::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::WorldTree(f.1))),
+            ),
+            Region::TYPE_ID => Some(
+                Region::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::Region(f.1))),
+            ),
             DmSpriteDef2::TYPE_ID => Some(
-                DmSpriteDef2::parse(self.field_data)
+                DmSpriteDef2::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::DmSpriteDef2(f.1))),
             ),
             MaterialPalette::TYPE_ID => Some(
-                MaterialPalette::parse(self.field_data)
+                MaterialPalette::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::MaterialPalette(f.1))),
             ),
             MaterialDef::TYPE_ID => Some(
-                MaterialDef::parse(self.field_data).map(|f| (f.0, FragmentType::MaterialDef(f.1))),
+                MaterialDef::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::MaterialDef(f.1))),
             ),
             SimpleSprite::TYPE_ID => Some(
-                SimpleSprite::parse(self.field_data)
+                SimpleSprite::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::SimpleSprite(f.1))),
             ),
-            DmSprite::TYPE_ID => {
-                Some(DmSprite::parse(self.field_data).map(|f| (f.0, FragmentType::DmSprite(f.1))))
-            }
+            DmSprite::TYPE_ID => Some(
+                DmSprite::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::DmSprite(f.1))),
+            ),
             SimpleSpriteDef::TYPE_ID => Some(
-                SimpleSpriteDef::parse(self.field_data)
+                SimpleSpriteDef::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::SimpleSpriteDef(f.1))),
             ),
-            BmInfo::TYPE_ID => {
-                Some(BmInfo::parse(self.field_data).map(|f| (f.0, FragmentType::BmInfo(f.1))))
-            }
+            BmInfo::TYPE_ID => Some(
+                BmInfo::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::BmInfo(f.1))),
+            ),
             ParticleCloudDef::TYPE_ID => Some(
-                ParticleCloudDef::parse(self.field_data)
+                ParticleCloudDef::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::ParticleCloudDef(f.1))),
             ),
             DmTrackDef::TYPE_ID => Some(
-                DmTrackDef::parse(self.field_data).map(|f| (f.0, FragmentType::DmTrackDef(f.1))),
+                DmTrackDef::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::DmTrackDef(f.1))),
             ),
             SphereList::TYPE_ID => Some(
-                SphereList::parse(self.field_data).map(|f| (f.0, FragmentType::SphereList(f.1))),
+                SphereList::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::SphereList(f.1))),
             ),
             SphereListDef::TYPE_ID => Some(
-                SphereListDef::parse(self.field_data)
+                SphereListDef::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::SphereListDef(f.1))),
             ),
             ParticleSprite::TYPE_ID => Some(
-                ParticleSprite::parse(self.field_data)
+                ParticleSprite::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::ParticleSprite(f.1))),
             ),
             ParticleSpriteDef::TYPE_ID => Some(
-                ParticleSpriteDef::parse(self.field_data)
+                ParticleSpriteDef::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::ParticleSpriteDef(f.1))),
             ),
             DefaultPaletteFile::TYPE_ID => Some(
-                DefaultPaletteFile::parse(self.field_data)
+                DefaultPaletteFile::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::DefaultPaletteFile(f.1))),
             ),
-            Sprite4D::TYPE_ID => {
-                Some(Sprite4D::parse(self.field_data).map(|f| (f.0, FragmentType::Sprite4D(f.1))))
-            }
+            Sprite4D::TYPE_ID => Some(
+                Sprite4D::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::Sprite4D(f.1))),
+            ),
             Sprite4DDef::TYPE_ID => Some(
-                Sprite4DDef::parse(self.field_data).map(|f| (f.0, FragmentType::Sprite4DDef(f.1))),
+                Sprite4DDef::parse_versioned(self.field_data, version)
+                    .map(|f| (f.0, FragmentType::Sprite4DDef(f.1))),
             ),
             DirectionalLight::TYPE_ID => Some(
-                DirectionalLight::parse(self.field_data)
+                DirectionalLight::parse_versioned(self.field_data, version)
                     .map(|f| (f.0, FragmentType::DirectionalLight(f.1))),
             ),
             _ => None,
         };
 
         match parsed {
-            Some(res) => res.map(|r| r.1).map_err(|e| match e.into() {
-                WldDocError::Parse { input, message } => WldDocError::ParseFragment {
+            Some(res) => res.map(|r| r.1).map_err(|e| {
+                let (offset, message) = match e.into() {
+                    WldDocError::Parse { remaining, message } => {
+                        (self.field_data.len().saturating_sub(remaining), message)
+                    }
+                    // The fragment parsers only generate WldDocError::Parse errors
+                    e => (0, e.to_string()),
+                };
+                WldDocError::ParseFragment {
                     index,
-                    offset: self.field_data.offset(input),
-                    header: self,
+                    fragment_type: self.fragment_type,
+                    fragment_offset,
+                    offset,
                     message,
-                },
-                // This should never happen, the parse functions _only_
-                // generate WldDocError::Parse errors
-                e => panic!(
-                    "Invalid error type encountered when parsing fragment body: {:?}",
-                    e
-                ),
+                    excerpt: WldDocError::excerpt(
+                        self.field_data.get(offset..).unwrap_or_default(),
+                    ),
+                }
             }),
             None => Err(WldDocError::UnknownFragment {
                 index,
-                header: self,
+                fragment_type: self.fragment_type,
+                fragment_offset,
+                excerpt: WldDocError::excerpt(self.field_data),
             }),
         }
     }
@@ -530,8 +959,9 @@ impl<'a> FragmentHeader<'a> {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::builder::WldBuilder;
 
     #[cfg(feature = "gamedata")]
     #[test]
@@ -551,6 +981,21 @@ mod tests {
         assert_eq!(wld_doc.strings.get(StringReference::new(2)), None);
     }
 
+    #[cfg(feature = "gamedata")]
+    #[test]
+    fn it_validates_the_header_of_game_files() {
+        let data = &include_bytes!("../../fixtures/gamedata/gfaydark.wld")[..];
+        let wld_doc = WldDoc::parse(data).unwrap();
+        let report = wld_doc.validate();
+
+        let header_issues: Vec<_> = report
+            .issues
+            .iter()
+            .filter(|issue| matches!(issue, ValidationIssue::Header { .. }))
+            .collect();
+        assert_eq!(header_issues, Vec::<&ValidationIssue>::new());
+    }
+
     #[cfg(feature = "gamedata")]
     #[test]
     fn it_serializes() {
@@ -573,6 +1018,575 @@ mod tests {
         );
     }
 
+    /// Build a wld file with the given strings and fragments (type id, field data).
+    /// The first string is at offset 1 of the string hash.
+    pub(crate) fn wld_with_fragments(strings: &[&str], fragments: &[(u32, &[u8])]) -> Vec<u8> {
+        let decoded: String = std::iter::once("")
+            .chain(strings.iter().copied())
+            .map(|s| format!("{}\0", s))
+            .collect();
+        let mut string_hash = strings::encode_string(&decoded);
+        string_hash.resize(string_hash.len().next_multiple_of(4), 0);
+        let header = [
+            0x54503d02,
+            0x00015500,
+            fragments.len() as u32,
+            0,
+            0,
+            string_hash.len() as u32,
+            strings.len() as u32 + 1,
+        ];
+        let mut data: Vec<u8> = header.iter().flat_map(|v: &u32| v.to_le_bytes()).collect();
+        data.extend(string_hash);
+        for (fragment_type, field_data) in fragments {
+            data.extend((field_data.len() as u32).to_le_bytes());
+            data.extend(fragment_type.to_le_bytes());
+            data.extend(*field_data);
+        }
+        data.extend([0xff, 0xff, 0xff, 0xff]);
+        data
+    }
+
+    #[test]
+    fn it_parses_leniently() {
+        let sphere = [&0i32.to_le_bytes()[..], &50.0f32.to_le_bytes()[..]].concat();
+        let data = wld_with_fragments(
+            &[],
+            &[
+                (0x99, &[0xfe, 0xff, 0xff, 0xff, 1, 2, 3, 4]),
+                (Sphere::TYPE_ID, &sphere),
+                (MaterialDef::TYPE_ID, &[0; 4]),
+            ],
+        );
+
+        assert_eq!(WldDoc::parse(&data).unwrap_err().len(), 2);
+
+        let (wld_doc, warnings) = WldDoc::parse_lenient(&data).unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(
+            warnings[0],
+            WldDocError::UnknownFragment { index: 0, .. }
+        ));
+        assert!(matches!(
+            warnings[1],
+            WldDocError::ParseFragment { index: 2, .. }
+        ));
+
+        assert_eq!(wld_doc.fragment_count(), 3);
+        assert!(matches!(wld_doc.at(0), Some(FragmentType::Raw(f)) if f.type_id == 0x99));
+        assert!(matches!(wld_doc.at(1), Some(FragmentType::Sphere(_))));
+        assert!(matches!(
+            wld_doc.at(2),
+            Some(FragmentType::Raw(f)) if f.type_id == MaterialDef::TYPE_ID
+        ));
+        assert_eq!(wld_doc.to_bytes(), data);
+    }
+
+    #[test]
+    fn it_resolves_any_fragment_refs() {
+        let sphere =
+            |name_ref: i32| [&name_ref.to_le_bytes()[..], &1.0f32.to_le_bytes()[..]].concat();
+        let data = wld_with_fragments(
+            &["SPHERE"],
+            &[
+                (Sphere::TYPE_ID, &sphere(0)),
+                (Sphere::TYPE_ID, &sphere(-1)),
+            ],
+        );
+        let wld_doc = WldDoc::parse(&data).unwrap();
+
+        assert!(matches!(
+            wld_doc.get(&AnyFragmentRef::new(1)),
+            Some(FragmentType::Sphere(_))
+        ));
+        assert!(std::ptr::eq(
+            wld_doc.get(&AnyFragmentRef::new(-1)).unwrap(),
+            wld_doc.at(1).unwrap()
+        ));
+        assert!(wld_doc.get(&AnyFragmentRef::new(3)).is_none());
+        assert!(wld_doc.get(&AnyFragmentRef::new(0)).is_none());
+    }
+
+    #[test]
+    fn it_validates() {
+        let le_bytes = |values: &[i32]| values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let simple_sprite: Vec<u8> = le_bytes(&[0, 0, 0]);
+        let palette: Vec<u8> = le_bytes(&[0, 0, 2, 1, 99]);
+        let zone: Vec<u8> = le_bytes(&[0, 0, 1, 3, 0]);
+        let data = wld_with_fragments(
+            &[],
+            &[
+                (SimpleSprite::TYPE_ID, &simple_sprite),
+                (MaterialPalette::TYPE_ID, &palette),
+                (Zone::TYPE_ID, &zone),
+            ],
+        );
+        let mut wld_doc = WldDoc::parse(&data).unwrap();
+        if let FragmentType::MaterialPalette(palette) = &mut wld_doc.fragments[1] {
+            palette.size1 = 5;
+        }
+
+        let report = wld_doc.validate();
+        assert!(!report.is_valid());
+        assert_eq!(
+            report.issues,
+            [
+                ValidationIssue::Count {
+                    index: 1,
+                    field: "size1",
+                    count: 5,
+                    len: 2,
+                },
+                ValidationIssue::ReferenceType {
+                    index: 1,
+                    field: "fragments",
+                    target: 0,
+                    expected_type: MaterialDef::TYPE_ID,
+                    actual_type: SimpleSprite::TYPE_ID,
+                },
+                ValidationIssue::DanglingReference {
+                    index: 1,
+                    field: "fragments",
+                    value: 99,
+                },
+                ValidationIssue::RegionOutOfRange {
+                    index: 2,
+                    field: "regions",
+                    region: 3,
+                    region_count: 0,
+                },
+            ]
+        );
+        assert_eq!(
+            report.issues[1].to_string(),
+            "fragment 1: fragments refers to fragment 0 (0x05 SimpleSprite) but should refer to a 0x30 MaterialDef"
+        );
+    }
+
+    #[test]
+    fn it_normalizes() {
+        let le_bytes = |values: &[i32]| values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let palette: Vec<u8> = le_bytes(&[0, 0, 1, 0]);
+        let data = wld_with_fragments(&["PALETTE"], &[(MaterialPalette::TYPE_ID, &palette)]);
+        let mut wld_doc = WldDoc::parse(&data).unwrap();
+
+        if let FragmentType::MaterialPalette(palette) = &mut wld_doc.fragments[0] {
+            palette.fragments.push(FragmentRef::new(0));
+        }
+        wld_doc
+            .fragments
+            .push(FragmentType::MaterialPalette(MaterialPalette {
+                name_reference: StringReference::new(-1),
+                flags: 0,
+                size1: 0,
+                fragments: vec![],
+            }));
+        assert!(!wld_doc.validate().is_valid());
+
+        wld_doc.normalize();
+        assert_eq!(wld_doc.validate(), ValidationReport::default());
+
+        let reparsed = WldDoc::parse(&wld_doc.to_bytes()).unwrap();
+        assert_eq!(reparsed.header, wld_doc.header);
+        assert_eq!(reparsed.fragment_count(), 2);
+        let palette = reparsed
+            .at(0)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<MaterialPalette>();
+        assert_eq!(palette.unwrap().fragments.len(), 2);
+    }
+
+    #[test]
+    fn it_edits_fragments() {
+        let le_bytes = |values: &[i32]| values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let simple_sprite = |name_ref: i32| -> Vec<u8> { le_bytes(&[name_ref, 0, 0]) };
+        let palette: Vec<u8> = le_bytes(&[0, 0, 4, 1, 2, 3, -1]);
+        let data = wld_with_fragments(
+            &["SPRITE"],
+            &[
+                (SimpleSprite::TYPE_ID, &simple_sprite(-1)),
+                (SimpleSprite::TYPE_ID, &simple_sprite(0)),
+                (SimpleSprite::TYPE_ID, &simple_sprite(0)),
+                (MaterialPalette::TYPE_ID, &palette),
+            ],
+        );
+        let mut wld_doc = WldDoc::parse(&data).unwrap();
+        let palette_refs = |doc: &WldDoc| {
+            doc.fragment_iter::<MaterialPalette>()
+                .flat_map(|p| p.fragments.iter().map(|r| r.value()))
+                .collect::<Vec<_>>()
+        };
+
+        let removed = wld_doc.remove_fragment(1).unwrap();
+        assert!(matches!(removed, FragmentType::SimpleSprite(_)));
+        assert_eq!(wld_doc.fragment_count(), 3);
+        assert_eq!(palette_refs(&wld_doc), [1, 0, 2, -1]);
+        assert!(wld_doc.remove_fragment(3).is_none());
+
+        let name_reference = wld_doc.intern_string("OTHER");
+        assert_eq!(name_reference, StringReference::new(-8));
+        wld_doc
+            .get_mut(&FragmentRef::<SimpleSprite>::new(-1))
+            .unwrap()
+            .name_reference = name_reference;
+        assert!(wld_doc.get_by_name("SPRITE").is_none());
+        assert!(wld_doc.find::<SimpleSprite>("OTHER").is_some());
+
+        let name_reference = wld_doc.intern_string("SPRITE");
+        let idx = wld_doc.add_fragment(FragmentType::SimpleSprite(SimpleSprite {
+            name_reference,
+            reference: FragmentRef::new(0),
+            flags: 0,
+        }));
+        assert_eq!(idx, 3);
+        assert!(std::ptr::eq(
+            wld_doc.get_by_name("SPRITE").unwrap(),
+            wld_doc.at(3).unwrap()
+        ));
+
+        for palette in wld_doc.fragment_iter_mut::<MaterialPalette>() {
+            palette.fragments.retain(|r| r.value() != 0);
+        }
+        assert_eq!(palette_refs(&wld_doc), [1, 2, -1]);
+
+        let old = wld_doc.replace_fragment(1, removed).unwrap();
+        assert!(matches!(old, FragmentType::SimpleSprite(_)));
+        assert!(wld_doc.replace_fragment(10, old).is_none());
+
+        wld_doc.normalize();
+        assert!(WldDoc::parse(&wld_doc.to_bytes()).is_ok());
+    }
+
+    #[test]
+    fn it_collects_unused_strings() {
+        let le_bytes =
+            |values: &[i32]| -> Vec<u8> { values.iter().flat_map(|v| v.to_le_bytes()).collect() };
+        // "" at 0, "UNUSED" at 1, "SPRITE" at 8 and "PALETTE" at 15
+        let data = wld_with_fragments(
+            &["UNUSED", "SPRITE", "PALETTE"],
+            &[
+                (SimpleSprite::TYPE_ID, &le_bytes(&[-8, 0, 0])),
+                (MaterialPalette::TYPE_ID, &le_bytes(&[-15, 0, 1, -8])),
+            ],
+        );
+        let mut wld_doc = WldDoc::parse(&data).unwrap();
+        assert_eq!(wld_doc.to_bytes(), data);
+
+        assert_eq!(wld_doc.gc_strings(), Some(1));
+        assert_eq!(wld_doc.strings.find("UNUSED"), None);
+        let sprite = wld_doc.find::<SimpleSprite>("SPRITE").unwrap();
+        assert_eq!(sprite.name_reference, StringReference::new(-1));
+        let palette = wld_doc.find::<MaterialPalette>("PALETTE").unwrap();
+        assert_eq!(palette.name_reference, StringReference::new(-8));
+        assert_eq!(palette.fragments[0].value(), -1);
+        assert_eq!(wld_doc.gc_strings(), Some(0));
+
+        wld_doc.normalize();
+        let reparsed = WldDoc::parse(&wld_doc.to_bytes()).unwrap();
+        assert_eq!(reparsed.strings.len(), 3);
+        assert!(reparsed.find::<SimpleSprite>("SPRITE").is_some());
+        assert!(reparsed.find::<MaterialPalette>("PALETTE").is_some());
+
+        let data = wld_with_fragments(&["UNUSED"], &[(0x99, &le_bytes(&[0]))]);
+        let (mut wld_doc, _) = WldDoc::parse_lenient(&data).unwrap();
+        assert_eq!(wld_doc.gc_strings(), None);
+        assert_eq!(wld_doc.to_bytes(), data);
+    }
+
+    #[test]
+    fn it_lists_every_string_reference() {
+        // Every string reference a fragment holds shows up in its debug output, the
+        // fixtures fill in the optional ones. Names held by a FragmentRef are listed
+        // by Fragment::references instead.
+        let count = |debug: String, value: &str| {
+            debug.matches(&format!("StringReference({}", value)).count()
+                - debug
+                    .matches(&format!("Name(StringReference({}", value))
+                    .count()
+        };
+        for game in [
+            FragmentGame::EverQuest,
+            FragmentGame::Tanarus,
+            FragmentGame::ReturnToKrondor,
+        ] {
+            let mut wld_doc = crate::fixtures::wld_doc(game);
+            for fragment in wld_doc.fragments.iter_mut() {
+                let held = count(format!("{:?}", fragment), "");
+                assert_eq!(fragment.string_references().len(), held, "{:?}", fragment);
+
+                fragment.remap_string_references(&mut |_| StringReference::new(-9999));
+                assert_eq!(
+                    count(format!("{:?}", fragment), "-9999"),
+                    held,
+                    "{:?}",
+                    fragment
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_builds_a_reference_graph() {
+        let simple_sprite = |name_ref: i32, reference: i32| {
+            [name_ref, reference, 0]
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect::<Vec<_>>()
+        };
+        let palette = [0i32, 0, 4, 1, -1, 99, -100]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        let data = wld_with_fragments(
+            &["SPRITE"],
+            &[
+                (SimpleSprite::TYPE_ID, &simple_sprite(0, 0)),
+                (SimpleSprite::TYPE_ID, &simple_sprite(-1, 0)),
+                (MaterialPalette::TYPE_ID, &palette),
+                (SimpleSprite::TYPE_ID, &simple_sprite(0, 3)),
+            ],
+        );
+        let wld_doc = WldDoc::parse(&data).unwrap();
+        let graph = wld_doc.reference_graph();
+
+        let edge = |source, target, field| ReferenceEdge {
+            source,
+            target,
+            field,
+        };
+        assert_eq!(
+            graph.outgoing(2),
+            [edge(2, 0, "fragments"), edge(2, 1, "fragments")]
+        );
+        assert_eq!(graph.incoming(0), [edge(2, 0, "fragments")]);
+        assert_eq!(graph.incoming(1), [edge(2, 1, "fragments")]);
+        assert_eq!(graph.incoming(2), [edge(3, 2, "reference")]);
+        assert!(graph.outgoing(0).is_empty());
+        assert!(graph.incoming(3).is_empty());
+        assert!(graph.outgoing(10).is_empty());
+        assert_eq!(graph.edges().count(), 3);
+        assert_eq!(
+            graph.unresolved(),
+            [
+                (
+                    2,
+                    FragmentRef::<MaterialDef>::new(99).reference("fragments")
+                ),
+                (
+                    2,
+                    FragmentRef::<MaterialDef>::new(-100).reference("fragments")
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn it_round_trips_both_versions() {
+        use crate::builder::{MeshData, PrimitiveData};
+
+        let mut builder = WldBuilder::new();
+        let material = builder.add_material("PLAIN", None, MaterialType::Diffuse);
+        builder
+            .add_mesh(MeshData {
+                name: "MESH".to_string(),
+                positions: vec![[0.0; 3], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+                texture_coordinates: vec![[0.0, 0.0], [1.0, 0.0], [0.0, -1.5]],
+                primitives: vec![PrimitiveData {
+                    material,
+                    indices: vec![0, 1, 2],
+                    passable: false,
+                }],
+                ..Default::default()
+            })
+            .unwrap();
+        let mut wld_doc = builder.build();
+        assert_eq!(wld_doc.version(), WldVersion::Old);
+
+        let old = wld_doc.to_bytes();
+        let parsed = WldDoc::parse(&old).unwrap();
+        assert_eq!(parsed.version(), WldVersion::Old);
+        assert_eq!(parsed.to_bytes(), old);
+
+        wld_doc.set_version(WldVersion::New);
+        wld_doc.normalize();
+        assert!(wld_doc.validate().is_valid());
+        let new = wld_doc.to_bytes();
+        assert_eq!(&new[4..8], &0x1000C800u32.to_le_bytes());
+        let parsed = WldDoc::parse(&new).unwrap();
+        assert_eq!(parsed.version(), WldVersion::New);
+        assert_eq!(parsed.to_bytes(), new);
+
+        let mesh = parsed.fragment_iter::<DmSpriteDef2>().next().unwrap();
+        assert_eq!(mesh.texture_coordinates, vec![(0, 0), (256, 0), (0, -384)]);
+        // Read as the old format, the same bytes don't line up.
+        let misread = DmSpriteDef2::parse(&mesh.to_bytes_versioned(WldVersion::New))
+            .unwrap()
+            .1;
+        assert_ne!(misread.texture_coordinates, mesh.texture_coordinates);
+    }
+
+    #[test]
+    fn it_detects_the_game_for_the_whole_document() {
+        let world_vertices = |count: u32| {
+            std::iter::once(count.to_le_bytes())
+                .chain((0..count * 3).map(|_| 1.0f32.to_le_bytes()))
+                .flatten()
+                .collect::<Vec<_>>()
+        };
+        // On its own the last fragment is small enough to look like a RtK 0x2c.
+        let data = wld_with_fragments(
+            &[],
+            &[
+                (0x2c, &world_vertices(5)),
+                (0x2c, &world_vertices(5)),
+                (0x2c, &world_vertices(1)),
+            ],
+        );
+
+        let wld_doc = WldDoc::parse(&data).unwrap();
+        assert_eq!(wld_doc.game(), FragmentGame::Tanarus);
+        assert_eq!(wld_doc.fragment_iter::<WorldVertices>().count(), 3);
+        assert_eq!(wld_doc.to_bytes(), data);
+    }
+
+    #[test]
+    fn it_parses_with_options() {
+        // A single vertex, which is too small to be detected as a Tanarus 0x2c.
+        let vertices = [
+            1u32.to_le_bytes(),
+            1.0f32.to_le_bytes(),
+            1.0f32.to_le_bytes(),
+            1.0f32.to_le_bytes(),
+        ]
+        .concat();
+        let data = wld_with_fragments(&[], &[(0x2c, &vertices)]);
+
+        let errors = WldDoc::parse(&data).unwrap_err();
+        assert_eq!(errors[0].fragment_type(), Some(0x2c));
+
+        let (wld_doc, warnings) = WldDoc::parse_with_options(
+            &data,
+            ParseOptions {
+                strictness: Strictness::Lenient,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(wld_doc.game(), FragmentGame::ReturnToKrondor);
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(wld_doc.at(0), Some(FragmentType::Raw(_))));
+
+        let (wld_doc, warnings) = WldDoc::parse_with_options(
+            &data,
+            ParseOptions {
+                game: Some(FragmentGame::Tanarus),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(wld_doc.game(), FragmentGame::Tanarus);
+        assert!(warnings.is_empty());
+        assert!(matches!(
+            wld_doc.at(0),
+            Some(FragmentType::WorldVertices(_))
+        ));
+    }
+
+    #[test]
+    fn it_overrides_the_version() {
+        use crate::builder::{MeshData, PrimitiveData};
+
+        let mut builder = WldBuilder::new();
+        let material = builder.add_material("PLAIN", None, MaterialType::Diffuse);
+        builder
+            .add_mesh(MeshData {
+                name: "MESH".to_string(),
+                positions: vec![[0.0; 3], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+                texture_coordinates: vec![[0.0, 0.0], [1.0, 0.0], [0.0, -1.5]],
+                primitives: vec![PrimitiveData {
+                    material,
+                    indices: vec![0, 1, 2],
+                    passable: false,
+                }],
+                ..Default::default()
+            })
+            .unwrap();
+        let mut wld_doc = builder.build();
+        wld_doc.set_version(WldVersion::New);
+        let new = wld_doc.to_bytes();
+        // A new format file with an old header
+        let mut data = new.clone();
+        data[4..8].copy_from_slice(&0x00015500u32.to_le_bytes());
+
+        let (wld_doc, _) = WldDoc::parse_with_options(
+            &data,
+            ParseOptions {
+                version: Some(WldVersion::New),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(wld_doc.version(), WldVersion::New);
+        // The header is corrected so the file is written back as it was read.
+        assert_eq!(wld_doc.to_bytes(), new);
+        let parsed = WldDoc::parse(&wld_doc.to_bytes()).unwrap();
+        let mesh = parsed.fragment_iter::<DmSpriteDef2>().next().unwrap();
+        assert_eq!(mesh.texture_coordinates, vec![(0, 0), (256, 0), (0, -384)]);
+    }
+
+    #[test]
+    fn it_keeps_unknown_versions() {
+        let mut data = WldBuilder::new().build().to_bytes();
+        data[4..8].copy_from_slice(&0x00015501u32.to_le_bytes());
+
+        let wld_doc = WldDoc::parse(&data).unwrap();
+        assert_eq!(wld_doc.header.version, 0x00015501);
+        assert_eq!(wld_doc.version(), WldVersion::Old);
+        assert_eq!(wld_doc.to_bytes(), data);
+    }
+
+    #[test]
+    fn it_looks_up_fragments_by_name() {
+        let sphere =
+            |name_ref: i32| [&name_ref.to_le_bytes()[..], &1.0f32.to_le_bytes()[..]].concat();
+        let data = wld_with_fragments(
+            &["SPHERE_A", "SPHERE_B"],
+            &[
+                (Sphere::TYPE_ID, &sphere(-1)),
+                (Sphere::TYPE_ID, &sphere(-10)),
+                (Sphere::TYPE_ID, &sphere(-1)),
+                (Sphere::TYPE_ID, &sphere(0)),
+            ],
+        );
+        let wld_doc = WldDoc::parse(&data).unwrap();
+
+        assert!(std::ptr::eq(
+            wld_doc.get_by_name("SPHERE_B").unwrap(),
+            wld_doc.at(1).unwrap()
+        ));
+        // The first fragment with a name wins
+        assert!(std::ptr::eq(
+            wld_doc.find::<Sphere>("SPHERE_A").unwrap(),
+            wld_doc
+                .at(0)
+                .unwrap()
+                .as_any()
+                .downcast_ref::<Sphere>()
+                .unwrap()
+        ));
+        assert!(wld_doc.find::<MaterialDef>("SPHERE_A").is_none());
+        assert!(wld_doc.get_by_name("SPHERE_C").is_none());
+        assert!(wld_doc.get_by_name("").is_none());
+        assert_eq!(
+            wld_doc
+                .get(&FragmentRef::<Sphere>::new(-10))
+                .unwrap()
+                .name_reference,
+            StringReference::new(-10)
+        );
+    }
+
     #[test]
     fn it_detects_eq_0x2c() {
         // EverQuest 0x2c starts with name_reference (negative i32)
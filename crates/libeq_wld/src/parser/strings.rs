@@ -1,4 +1,6 @@
 use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Deref;
 
 use super::WResult;
 use encoding_rs::WINDOWS_1252;
@@ -25,9 +27,25 @@ impl StringReference {
     }
 }
 
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, PartialEq)]
-pub struct StringHash(BTreeMap<usize, String>);
+/// The strings of a wld file, keyed by their offset in the string hash.
+///
+/// The data the hash was read from is kept and written back as is until the hash is
+/// modified, so that an unmodified hash serializes to exactly the same bytes.
+///
+/// With the `serde` feature the hash serializes as a map of strings keyed by offset. If
+/// the strings don't encode back to the data they were read from, the map is nested
+/// under `strings` next to that data under `original`.
+#[derive(Debug)]
+pub struct StringHash {
+    strings: BTreeMap<usize, String>,
+    original: Option<Vec<u8>>,
+}
+
+impl PartialEq for StringHash {
+    fn eq(&self, other: &Self) -> bool {
+        self.strings == other.strings
+    }
+}
 
 const XOR_KEY: [u8; 8] = [0x95, 0x3a, 0xc5, 0x2a, 0x95, 0x7a, 0x95, 0x6a];
 
@@ -50,6 +68,212 @@ pub fn encode_string(decoded_data: &str) -> Vec<u8> {
         .collect()
 }
 
+fn encodes_losslessly(decoded_data: &str) -> bool {
+    let (_, _, had_errors) = WINDOWS_1252.encode(decoded_data);
+    !had_errors
+}
+
+/// The length of the string held in `raw`, leaving out the padding after it. Padding
+/// starts with an encoded null terminator and holds nothing but encoded nulls and zeros.
+fn string_len(raw: &[u8]) -> usize {
+    let mut len = raw.len();
+    for (i, byte) in raw.iter().enumerate().rev() {
+        if *byte == XOR_KEY[i % XOR_KEY.len()] {
+            len = i;
+        } else if *byte != 0 {
+            break;
+        }
+    }
+    len
+}
+
+/// The length of the strings held in string hash data, up to and including the null
+/// terminator of the last one.
+fn strings_len(data: &[u8]) -> usize {
+    data.iter()
+        .enumerate()
+        .rposition(|(i, byte)| *byte == XOR_KEY[i % XOR_KEY.len()])
+        .map_or(0, |i| i + 1)
+}
+
+/// An encoded string stored within a fragment, such as a file name or user data.
+///
+/// The bytes the string was read from are kept and written back as is until the string
+/// is changed with [EncodedString::set], so that strings that don't decode cleanly are
+/// still written back exactly. Derefs to the decoded string, without its null terminator
+/// and padding.
+///
+/// With the `serde` feature the string serializes as its value, along with the bytes it
+/// was read from when they differ from what [EncodedString::encode] would write.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "EncodedStringRepr", into = "EncodedStringRepr")
+)]
+#[derive(Debug, Clone, Default)]
+pub struct EncodedString {
+    value: String,
+    raw: Option<Vec<u8>>,
+}
+
+impl EncodedString {
+    pub fn new(value: &str) -> Self {
+        Self {
+            value: value.to_string(),
+            raw: None,
+        }
+    }
+
+    /// Decode a string from the bytes of a fragment field, keeping the bytes.
+    pub fn decode(raw: &[u8]) -> Self {
+        Self {
+            value: decode_string(&raw[..string_len(raw)]),
+            raw: Some(raw.to_vec()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+
+    /// The bytes the string was read from, unless it has been changed since.
+    pub fn raw(&self) -> Option<&[u8]> {
+        self.raw.as_deref()
+    }
+
+    pub fn set(&mut self, value: &str) {
+        self.value = value.to_string();
+        self.raw = None;
+    }
+
+    /// Whether the string doesn't encode back to the bytes it was read from, or if it
+    /// has been changed, whether it can't be encoded. The padding after the string is
+    /// not taken into account, see [EncodedString::has_custom_padding].
+    pub fn is_lossy(&self) -> bool {
+        match &self.raw {
+            Some(raw) => encode_string(&self.value) != raw[..string_len(raw)],
+            None => !encodes_losslessly(&self.value),
+        }
+    }
+
+    /// Whether the string was read with padding other than the null terminator followed
+    /// by zeros that [EncodedString::encode] pads with, such as more encoded nulls.
+    pub fn has_custom_padding(&self) -> bool {
+        self.raw.as_ref().is_some_and(|raw| {
+            let len = string_len(raw);
+            raw[len..] != self.encode_value(raw.len())[len..]
+        })
+    }
+
+    /// The encoded string, null terminated and then padded or cut to `len` bytes.
+    /// Returns the bytes it was read from if it hasn't been changed and they are `len`
+    /// bytes long.
+    pub fn encode(&self, len: usize) -> Vec<u8> {
+        match &self.raw {
+            Some(raw) if raw.len() == len => raw.clone(),
+            _ => self.encode_value(len),
+        }
+    }
+
+    fn encode_value(&self, len: usize) -> Vec<u8> {
+        let mut encoded = encode_string(&format!("{}{}", &self.value, "\0"));
+        encoded.resize(len, 0);
+        encoded
+    }
+}
+
+/// How an [EncodedString] is serialized, with the bytes it was read from only when
+/// they are needed to write it back exactly.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum EncodedStringRepr {
+    Value(String),
+    Raw { value: String, raw: Vec<u8> },
+}
+
+#[cfg(feature = "serde")]
+impl From<EncodedString> for EncodedStringRepr {
+    fn from(string: EncodedString) -> Self {
+        match string.raw {
+            Some(raw) if raw != string.encode_value(raw.len()) => Self::Raw {
+                value: string.value,
+                raw,
+            },
+            _ => Self::Value(string.value),
+        }
+    }
+}
+
+/// The bytes are dropped if the value no longer matches them, as it has been edited.
+#[cfg(feature = "serde")]
+impl From<EncodedStringRepr> for EncodedString {
+    fn from(repr: EncodedStringRepr) -> Self {
+        match repr {
+            EncodedStringRepr::Value(value) => value.into(),
+            EncodedStringRepr::Raw { value, raw } => {
+                let decoded = EncodedString::decode(&raw);
+                if decoded.value == value {
+                    decoded
+                } else {
+                    value.into()
+                }
+            }
+        }
+    }
+}
+
+impl Deref for EncodedString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for EncodedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.value)
+    }
+}
+
+impl From<&str> for EncodedString {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for EncodedString {
+    fn from(value: String) -> Self {
+        Self { value, raw: None }
+    }
+}
+
+/// Strings are equal if their decoded values are.
+impl PartialEq for EncodedString {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl PartialEq<str> for EncodedString {
+    fn eq(&self, other: &str) -> bool {
+        self.value == other
+    }
+}
+
+impl PartialEq<String> for EncodedString {
+    fn eq(&self, other: &String) -> bool {
+        self.value == *other
+    }
+}
+
+impl PartialEq<&str> for EncodedString {
+    fn eq(&self, other: &&str) -> bool {
+        self.value == *other
+    }
+}
+
 impl StringHash {
     pub fn new(encoded_data: &[u8]) -> StringHash {
         let decoded_string = decode_string(encoded_data);
@@ -57,19 +281,54 @@ impl StringHash {
         let indices = decoded_string.match_indices("\0");
 
         let mut reference_idx = 0;
-        StringHash(strings.zip(indices).fold(
-            BTreeMap::new(),
-            |mut hash, (string, (end_idx, _))| {
-                hash.insert(reference_idx, string.to_string());
-                reference_idx = end_idx + 1;
-                hash
-            },
-        ))
+        StringHash {
+            strings: strings.zip(indices).fold(
+                BTreeMap::new(),
+                |mut hash, (string, (end_idx, _))| {
+                    hash.insert(reference_idx, string.to_string());
+                    reference_idx = end_idx + 1;
+                    hash
+                },
+            ),
+            original: Some(encoded_data.to_vec()),
+        }
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        let decoded_string: String = self.0.values().cloned().map(|s| s + "\0").collect();
-        let mut encoded_string = encode_string(&decoded_string);
+        match &self.original {
+            Some(original) => original.clone(),
+            None => self.encode(),
+        }
+    }
+
+    /// Whether the strings don't encode back to the data they were read from, or for a
+    /// modified hash, whether any of them can't be encoded. The padding after the last
+    /// string is not taken into account, see [StringHash::has_custom_padding].
+    pub fn is_lossy(&self) -> bool {
+        match &self.original {
+            Some(original) => original[..strings_len(original)] != self.encode_strings(),
+            None => self.strings.values().any(|s| !encodes_losslessly(s)),
+        }
+    }
+
+    /// Whether the data was read with padding after the last string other than the zeros
+    /// up to a multiple of 4 bytes that the hash is written with. Such padding is not kept
+    /// by [StringHash::get] and the like, though [StringHash::to_bytes] writes it back
+    /// until the hash is modified.
+    pub fn has_custom_padding(&self) -> bool {
+        self.original.as_ref().is_some_and(|original| {
+            let len = strings_len(original);
+            original[len..] != vec![0; len.next_multiple_of(4) - len]
+        })
+    }
+
+    fn encode_strings(&self) -> Vec<u8> {
+        let decoded_string: String = self.strings.values().cloned().map(|s| s + "\0").collect();
+        encode_string(&decoded_string)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut encoded_string = self.encode_strings();
         let size = encoded_string.len();
         // String data must be padded so that it aligns on 4 bytes
         if !size.is_multiple_of(4) {
@@ -79,11 +338,171 @@ impl StringHash {
         encoded_string
     }
 
+    /// The number of strings in the hash.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
     pub fn get(&self, string_reference: StringReference) -> Option<&str> {
-        self.0
+        self.strings
             .get(&(string_reference.0.unsigned_abs() as usize))
             .map(|s| s.as_ref())
     }
+
+    /// Find a reference to the first occurrence of `string`.
+    pub fn find(&self, string: &str) -> Option<StringReference> {
+        self.strings
+            .iter()
+            .find(|(_, s)| *s == string)
+            .map(|(offset, _)| StringReference::new(-(*offset as i32)))
+    }
+
+    /// Get a reference to `string`, adding it to the end of the hash if it isn't already in it.
+    pub fn intern(&mut self, string: &str) -> StringReference {
+        if let Some(reference) = self.find(string) {
+            return reference;
+        }
+        self.original = None;
+        // Offset 0 is always the empty string, which is how a missing name is written.
+        if self.strings.is_empty() {
+            self.strings.insert(0, String::new());
+            if string.is_empty() {
+                return StringReference::new(0);
+            }
+        }
+        let offset = self
+            .strings
+            .last_key_value()
+            .map_or(0, |(offset, s)| offset + encode_string(s).len() + 1);
+        self.strings.insert(offset, string.to_string());
+        StringReference::new(-(offset as i32))
+    }
+
+    /// Keep only the strings for which `keep` returns true, along with the string at
+    /// offset 0, and pack them together in their original order. Returns the new offset
+    /// of each kept string keyed by its old offset.
+    pub fn retain(&mut self, mut keep: impl FnMut(usize, &str) -> bool) -> BTreeMap<usize, usize> {
+        let count = self.strings.len();
+        self.strings
+            .retain(|offset, s| *offset == 0 || keep(*offset, s));
+        if self.strings.len() == count {
+            return self
+                .strings
+                .keys()
+                .map(|offset| (*offset, *offset))
+                .collect();
+        }
+
+        self.original = None;
+        let mut offsets = BTreeMap::new();
+        let mut next_offset = 0;
+        for (offset, s) in std::mem::take(&mut self.strings) {
+            offsets.insert(offset, next_offset);
+            let len = encode_string(&s).len() + 1;
+            self.strings.insert(next_offset, s);
+            next_offset += len;
+        }
+        offsets
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for StringHash {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        match &self.original {
+            Some(original) if *original != self.encode() => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("strings", &self.strings)?;
+                map.serialize_entry("original", original)?;
+                map.end()
+            }
+            _ => self.strings.serialize(serializer),
+        }
+    }
+}
+
+/// The original data is dropped if the strings no longer match it, as they have been
+/// edited.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for StringHash {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{self, MapAccess, Visitor};
+
+        /// A key of either form, formats like json only have string keys.
+        enum Key {
+            Offset(usize),
+            Strings,
+            Original,
+        }
+
+        impl<'de> Deserialize<'de> for Key {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct KeyVisitor;
+
+                impl Visitor<'_> for KeyVisitor {
+                    type Value = Key;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str("a string offset, `strings` or `original`")
+                    }
+
+                    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Key, E> {
+                        usize::try_from(value)
+                            .map(Key::Offset)
+                            .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+                    }
+
+                    fn visit_str<E: de::Error>(self, value: &str) -> Result<Key, E> {
+                        match value {
+                            "strings" => Ok(Key::Strings),
+                            "original" => Ok(Key::Original),
+                            _ => value
+                                .parse()
+                                .map(Key::Offset)
+                                .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self)),
+                        }
+                    }
+                }
+
+                deserializer.deserialize_any(KeyVisitor)
+            }
+        }
+
+        struct StringHashVisitor;
+
+        impl<'de> Visitor<'de> for StringHashVisitor {
+            type Value = StringHash;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of strings keyed by offset")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<StringHash, A::Error> {
+                let mut strings = BTreeMap::new();
+                let mut original: Option<Vec<u8>> = None;
+                while let Some(key) = map.next_key()? {
+                    match key {
+                        Key::Offset(offset) => {
+                            strings.insert(offset, map.next_value()?);
+                        }
+                        Key::Strings => strings = map.next_value()?,
+                        Key::Original => original = Some(map.next_value()?),
+                    }
+                }
+                let original =
+                    original.filter(|original| StringHash::new(original).strings == strings);
+                Ok(StringHash { strings, original })
+            }
+        }
+
+        deserializer.deserialize_map(StringHashVisitor)
+    }
 }
 
 #[cfg(test)]
@@ -119,4 +538,141 @@ mod tests {
         let serialized = string_hash.to_bytes();
         assert_eq!(data, serialized);
     }
+
+    #[test]
+    fn it_interns() {
+        let mut string_hash = StringHash::new(&fixture_data());
+        assert_eq!(string_hash.intern("BAR"), StringReference::new(-5));
+        assert_eq!(string_hash.intern("BAZ"), StringReference::new(-9));
+        assert_eq!(string_hash.intern("BAZ"), StringReference::new(-9));
+        assert_eq!(string_hash.get(StringReference::new(-9)), Some("BAZ"));
+
+        let reparsed = StringHash::new(&string_hash.to_bytes());
+        assert_eq!(reparsed.get(StringReference::new(-9)), Some("BAZ"));
+
+        let mut string_hash = StringHash::new(&[]);
+        assert_eq!(string_hash.intern("FOO"), StringReference::new(-1));
+        assert_eq!(string_hash.intern(""), StringReference::new(0));
+    }
+
+    #[test]
+    fn it_preserves_the_original_data() {
+        // Padding that isn't zero is kept until the hash is modified
+        let mut data = encode_string("\0FOO\0BAR\0");
+        data.extend([0x12, 0x34, 0x56]);
+        let mut string_hash = StringHash::new(&data);
+        assert_eq!(string_hash.to_bytes(), data);
+
+        assert!(!string_hash.is_lossy());
+        assert!(string_hash.has_custom_padding());
+        assert!(!StringHash::new(&fixture_data()).is_lossy());
+        assert!(!StringHash::new(&fixture_data()).has_custom_padding());
+
+        string_hash.retain(|_, _| true);
+        assert_eq!(string_hash.to_bytes(), data);
+
+        string_hash.intern("BAZ");
+        assert_eq!(
+            string_hash.to_bytes(),
+            StringHash::new(&string_hash.to_bytes()).to_bytes()
+        );
+        assert_ne!(string_hash.to_bytes(), data);
+    }
+
+    #[test]
+    fn it_keeps_encoded_string_bytes() {
+        // Padding is left out when decoding and written as zeros when encoding
+        let raw = encode_string("FIRE1.BMP\0\0");
+        let mut string = EncodedString::decode(&raw);
+        assert_eq!(string, "FIRE1.BMP");
+        assert!(!string.is_lossy());
+        assert!(string.has_custom_padding());
+        assert_eq!(string.raw(), Some(&raw[..]));
+        assert_eq!(string.encode(raw.len()), raw);
+
+        let clean = EncodedString::decode(&encode_string("FIRE1.BMP\0"));
+        assert!(!clean.is_lossy());
+        assert!(!clean.has_custom_padding());
+
+        let mut zero_padded = encode_string("FIRE1.BMP\0");
+        zero_padded.extend([0, 0]);
+        let zero_padded = EncodedString::decode(&zero_padded);
+        assert_eq!(zero_padded, "FIRE1.BMP");
+        assert!(!zero_padded.has_custom_padding());
+
+        // A string that ends in an encoded zero is not padding
+        let colon = EncodedString::decode(&encode_string("C:"));
+        assert_eq!(colon, "C:");
+        assert!(!colon.has_custom_padding());
+
+        string.set("FIRE2.BMP");
+        assert_eq!(string.raw(), None);
+        assert!(!string.is_lossy());
+        assert_eq!(string.encode(10), encode_string("FIRE2.BMP\0"));
+
+        assert!(EncodedString::new("火.BMP").is_lossy());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_serializes_raw_bytes_only_when_needed() {
+        let clean = EncodedString::decode(&encode_string("FIRE1.BMP\0"));
+        assert_eq!(serde_json::to_string(&clean).unwrap(), "\"FIRE1.BMP\"");
+
+        let raw = encode_string("FIRE1.BMP\0\0");
+        for json in [
+            serde_json::to_string(&EncodedString::decode(&raw)).unwrap(),
+            ron::to_string(&EncodedString::decode(&raw)).unwrap(),
+        ] {
+            assert!(json.contains("raw"), "{}", json);
+        }
+        let json = serde_json::to_string(&EncodedString::decode(&raw)).unwrap();
+        let string: EncodedString = serde_json::from_str(&json).unwrap();
+        assert_eq!(string.encode(raw.len()), raw);
+
+        // An edited value replaces the bytes
+        let json = json.replace("FIRE1", "FIRE2");
+        let string: EncodedString = serde_json::from_str(&json).unwrap();
+        assert_eq!(string, "FIRE2.BMP");
+        assert_eq!(string.raw(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_serializes_original_data_only_when_needed() {
+        let string_hash = StringHash::new(&fixture_data());
+        let json = serde_json::to_string(&string_hash).unwrap();
+        assert_eq!(json, r#"{"0":"","1":"FOO","5":"BAR"}"#);
+        let deserialized: StringHash = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.to_bytes(), fixture_data());
+
+        let mut data = encode_string("\0FOO\0BAR\0");
+        data.extend([0x12, 0x34, 0x56]);
+        let string_hash = StringHash::new(&data);
+        let json = serde_json::to_string(&string_hash).unwrap();
+        let deserialized: StringHash = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.to_bytes(), data);
+        let ron = ron::to_string(&string_hash).unwrap();
+        let deserialized: StringHash = ron::from_str(&ron).unwrap();
+        assert_eq!(deserialized.to_bytes(), data);
+
+        // Edited strings replace the original data
+        let json = json.replace("BAR", "BAZ");
+        let deserialized: StringHash = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.get(StringReference::new(-5)), Some("BAZ"));
+        assert_ne!(deserialized.to_bytes(), data);
+    }
+
+    #[test]
+    fn it_retains() {
+        let mut string_hash = StringHash::new(&fixture_data());
+        let offsets = string_hash.retain(|_, s| s != "FOO");
+        assert_eq!(offsets, BTreeMap::from([(0, 0), (5, 1)]));
+        assert_eq!(string_hash.len(), 2);
+        assert_eq!(string_hash.get(StringReference::new(-1)), Some("BAR"));
+        assert_eq!(string_hash.find("FOO"), None);
+
+        let reparsed = StringHash::new(&string_hash.to_bytes());
+        assert_eq!(reparsed, string_hash);
+    }
 }
@@ -0,0 +1,110 @@
+use super::{
+    BmInfoRtk, DmSpriteDef, FragmentGame, FragmentParser, WldDoc, WldDocError, WorldVertices,
+    detect_game, fragment_type_name,
+};
+
+/// A row of the fragment header table returned by [WldDoc::fragment_table].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FragmentTableEntry {
+    /// The position of the fragment in the file, starting at 0.
+    pub index: usize,
+
+    /// The offset of the fragment header in the file.
+    pub offset: usize,
+
+    /// The fragment type id from the header.
+    pub type_id: u32,
+
+    /// The name of the fragment type, if it is a known type. Type 0x2c is named after
+    /// the fragment used by the game the file was detected as.
+    pub type_name: Option<&'static str>,
+
+    /// The size of the fragment body in bytes, as given in the header.
+    pub size: u32,
+
+    /// The name of the fragment from the string hash, if it has one.
+    pub name: Option<String>,
+}
+
+impl WldDoc {
+    /// List the fragments of a wld file from their headers without parsing any
+    /// fragment bodies. Fails only if the file header, string hash or fragment
+    /// headers can't be read.
+    pub fn fragment_table(input: &[u8]) -> Result<Vec<FragmentTableEntry>, Vec<WldDocError>> {
+        let (_, strings, fragment_headers) = Self::parse_headers(input)?;
+        let game = detect_game(&fragment_headers);
+
+        let type_name = |type_id: u32| match (type_id, game) {
+            (DmSpriteDef::TYPE_ID, FragmentGame::Tanarus) => Some(WorldVertices::TYPE_NAME),
+            (DmSpriteDef::TYPE_ID, FragmentGame::ReturnToKrondor) => Some(BmInfoRtk::TYPE_NAME),
+            _ => fragment_type_name(type_id),
+        };
+
+        Ok(fragment_headers
+            .iter()
+            .enumerate()
+            .map(|(index, (offset, header))| FragmentTableEntry {
+                index,
+                // The size and type id precede the body
+                offset: offset - 8,
+                type_id: header.fragment_type,
+                type_name: type_name(header.fragment_type),
+                size: header.size,
+                name: strings
+                    .get(header.name_reference(game))
+                    .filter(|name| !name.is_empty())
+                    .map(String::from),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::WldBuilder;
+    use crate::parser::{MaterialDef, MaterialType, SimpleSpriteDef};
+
+    #[test]
+    fn it_lists_fragment_headers() {
+        let mut builder = WldBuilder::new();
+        let texture = builder.add_texture("BRICK", "brick.bmp");
+        builder.add_material("BRICK", Some(texture), MaterialType::Diffuse);
+        let data = builder.build().to_bytes();
+
+        let table = WldDoc::fragment_table(&data).unwrap();
+        let wld_doc = WldDoc::parse(&data).unwrap();
+        assert_eq!(table.len(), wld_doc.fragment_count());
+
+        let sprite_def = &table[1];
+        assert_eq!(sprite_def.index, 1);
+        assert_eq!(sprite_def.type_id, SimpleSpriteDef::TYPE_ID);
+        assert_eq!(sprite_def.type_name, Some("SimpleSpriteDef"));
+        assert_eq!(sprite_def.name.as_deref(), Some("BRICK_SPRITE"));
+
+        let material = table.last().unwrap();
+        assert_eq!(material.type_id, MaterialDef::TYPE_ID);
+        assert_eq!(material.name.as_deref(), Some("BRICK_MDF"));
+        let body_len = wld_doc.at(material.index).unwrap().to_bytes().len();
+        assert_eq!(material.size as usize, body_len);
+        assert_eq!(
+            &data[material.offset..material.offset + 4],
+            material.size.to_le_bytes()
+        );
+
+        // Unnamed fragments
+        assert_eq!(table[0].name, None);
+        assert_eq!(table[2].name, None);
+    }
+
+    #[test]
+    fn it_fails_without_panicking() {
+        let mut builder = WldBuilder::new();
+        builder.add_texture("BRICK", "brick.bmp");
+        let data = builder.build().to_bytes();
+
+        // Cut into the last fragment, before the trailing 4 bytes
+        assert!(WldDoc::fragment_table(&data[..data.len() - 5]).is_err());
+        assert!(WldDoc::fragment_table(&data[..10]).is_err());
+    }
+}
@@ -0,0 +1,296 @@
+use std::fmt;
+
+use super::{
+    AmbientLight, DirectionalLight, Fragment, FragmentType, Region, StringReference, WldDoc, Zone,
+    fragment_type_name,
+};
+
+/// A problem found by [WldDoc::validate].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    /// A field of the file header does not match the document.
+    Header {
+        field: &'static str,
+        stored: u32,
+        expected: u32,
+    },
+    /// A count field of a fragment does not match the length of the list it counts.
+    Count {
+        index: usize,
+        field: &'static str,
+        count: usize,
+        len: usize,
+    },
+    /// A reference to a fragment index that does not exist or to a name that isn't in the
+    /// string hash.
+    DanglingReference {
+        index: usize,
+        field: &'static str,
+        value: i32,
+    },
+    /// A reference to a name that no fragment in this file has. This is not an error, as
+    /// the fragment may be in another file, e.g. zone objects refer to models by name.
+    UnresolvedName {
+        index: usize,
+        field: &'static str,
+        name: String,
+    },
+    /// A reference to a fragment of the wrong type.
+    ReferenceType {
+        index: usize,
+        field: &'static str,
+        target: usize,
+        expected_type: u32,
+        actual_type: u32,
+    },
+    /// A region id that is not below the number of [Region] fragments.
+    RegionOutOfRange {
+        index: usize,
+        field: &'static str,
+        region: u32,
+        region_count: usize,
+    },
+    /// A vertex index that is not below the number of vertices.
+    VertexOutOfRange {
+        index: usize,
+        field: &'static str,
+        vertex: u32,
+        vertex_count: usize,
+    },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe_type = |type_id: u32| match fragment_type_name(type_id) {
+            Some(name) => format!("{:#04x} {}", type_id, name),
+            None => format!("{:#04x}", type_id),
+        };
+
+        match self {
+            Self::Header {
+                field,
+                stored,
+                expected,
+            } => write!(
+                f,
+                "header {} is {} but should be {}",
+                field, stored, expected
+            ),
+            Self::Count {
+                index,
+                field,
+                count,
+                len,
+            } => write!(
+                f,
+                "fragment {}: {} is {} but the list has {} entries",
+                index, field, count, len
+            ),
+            Self::DanglingReference {
+                index,
+                field,
+                value,
+            } => write!(
+                f,
+                "fragment {}: {} ({}) does not refer to any fragment",
+                index, field, value
+            ),
+            Self::UnresolvedName { index, field, name } => write!(
+                f,
+                "fragment {}: {} refers to {} which is not in this file",
+                index, field, name
+            ),
+            Self::ReferenceType {
+                index,
+                field,
+                target,
+                expected_type,
+                actual_type,
+            } => write!(
+                f,
+                "fragment {}: {} refers to fragment {} ({}) but should refer to a {}",
+                index,
+                field,
+                target,
+                describe_type(*actual_type),
+                describe_type(*expected_type)
+            ),
+            Self::RegionOutOfRange {
+                index,
+                field,
+                region,
+                region_count,
+            } => write!(
+                f,
+                "fragment {}: {} contains region {} but there are {} regions",
+                index, field, region, region_count
+            ),
+            Self::VertexOutOfRange {
+                index,
+                field,
+                vertex,
+                vertex_count,
+            } => write!(
+                f,
+                "fragment {}: {} contains vertex {} but there are {} vertices",
+                index, field, vertex, vertex_count
+            ),
+        }
+    }
+}
+
+/// The result of [WldDoc::validate].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationIssue {
+    /// Whether the issue makes the document invalid, see [ValidationIssue::UnresolvedName].
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Self::UnresolvedName { .. })
+    }
+}
+
+impl ValidationReport {
+    /// Whether none of the issues are errors.
+    pub fn is_valid(&self) -> bool {
+        !self.issues.iter().any(ValidationIssue::is_error)
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+pub(super) fn validate(doc: &WldDoc) -> ValidationReport {
+    let mut issues = Vec::new();
+    let region_count = doc.fragment_iter::<Region>().count();
+
+    // max_object_bytes and string_count are left out, see WldDoc::validate.
+    let header = &doc.header;
+    let expected = doc.computed_header();
+    for (field, stored, expected) in [
+        (
+            "fragment_count",
+            header.fragment_count,
+            expected.fragment_count,
+        ),
+        ("region_count", header.region_count, expected.region_count),
+        (
+            "string_hash_size",
+            header.string_hash_size,
+            expected.string_hash_size,
+        ),
+    ] {
+        if stored != expected {
+            issues.push(ValidationIssue::Header {
+                field,
+                stored,
+                expected,
+            });
+        }
+    }
+
+    for (index, fragment) in doc.fragments.iter().enumerate() {
+        for count in fragment.counts() {
+            if count.count != count.len {
+                issues.push(ValidationIssue::Count {
+                    index,
+                    field: count.field,
+                    count: count.count,
+                    len: count.len,
+                });
+            }
+        }
+
+        for reference in fragment.references() {
+            if reference.value == 0 {
+                continue;
+            }
+            let Some(target) = doc.resolve(reference.value) else {
+                let name = (reference.value < 0)
+                    .then(|| doc.strings.get(StringReference::new(reference.value)))
+                    .flatten();
+                issues.push(match name {
+                    Some(name) => ValidationIssue::UnresolvedName {
+                        index,
+                        field: reference.field,
+                        name: name.to_string(),
+                    },
+                    None => ValidationIssue::DanglingReference {
+                        index,
+                        field: reference.field,
+                        value: reference.value,
+                    },
+                });
+                continue;
+            };
+            let actual_type = Fragment::type_id(&*doc.fragments[target]);
+            if let Some(expected_type) = reference.expected_type
+                && expected_type != actual_type
+            {
+                issues.push(ValidationIssue::ReferenceType {
+                    index,
+                    field: reference.field,
+                    target,
+                    expected_type,
+                    actual_type,
+                });
+            }
+        }
+
+        let regions = match fragment {
+            FragmentType::Zone(Zone { regions, .. })
+            | FragmentType::AmbientLight(AmbientLight { regions, .. })
+            | FragmentType::DirectionalLight(DirectionalLight { regions, .. }) => &regions[..],
+            _ => &[],
+        };
+        for region in regions {
+            if *region as usize >= region_count {
+                issues.push(ValidationIssue::RegionOutOfRange {
+                    index,
+                    field: "regions",
+                    region: *region,
+                    region_count,
+                });
+            }
+        }
+
+        let mut check_vertex = |field, vertex: u32, vertex_count: usize| {
+            if vertex as usize >= vertex_count {
+                issues.push(ValidationIssue::VertexOutOfRange {
+                    index,
+                    field,
+                    vertex,
+                    vertex_count,
+                });
+            }
+        };
+        match fragment {
+            FragmentType::DmSpriteDef2(mesh) => {
+                for face in &mesh.faces {
+                    let (a, b, c) = face.vertex_indexes;
+                    for vertex in [a, b, c] {
+                        check_vertex("faces.vertex_indexes", vertex.into(), mesh.positions.len());
+                    }
+                }
+            }
+            FragmentType::Sprite3DDef(sprite) => {
+                for node in &sprite.bsp_nodes {
+                    for vertex in &node.vertex_indices {
+                        check_vertex("bsp_nodes.vertex_indices", *vertex, sprite.vertices.len());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ValidationReport { issues }
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::{
+    Fragment, FragmentGame, FragmentHeader, FragmentParser, FragmentRef, FragmentTarget,
+    FragmentType, ParseOptions, StringHash, StringReference, WldDoc, WldDocError, WldHeader,
+    WldVersion, detect_game,
+};
+
+/// A wld file that is decoded as it is used.
+///
+/// Only the header, string hash and fragment headers are parsed up front. Each fragment
+/// body is parsed the first time it is accessed and kept for later accesses. This makes
+/// it much cheaper than [WldDoc] for tools that only look at a few fragments.
+///
+/// Fragments that fail to parse are skipped by [WldView::at], [WldView::get] and the
+/// iterators. [WldView::try_at] returns the error instead.
+#[derive(Debug)]
+pub struct WldView<'a> {
+    header: WldHeader,
+    strings: StringHash,
+    /// Fragment headers along with the offset of their body in the file.
+    fragment_headers: Vec<(usize, FragmentHeader<'a>)>,
+    fragments: Vec<OnceLock<Result<FragmentType, WldDocError>>>,
+    /// Fragment names mapped to the position of the first fragment with that name.
+    names: OnceLock<HashMap<String, usize>>,
+    version: WldVersion,
+    game: FragmentGame,
+}
+
+impl<'a> WldView<'a> {
+    pub fn parse(input: &'a [u8]) -> Result<WldView<'a>, Vec<WldDocError>> {
+        Self::parse_with_options(input, ParseOptions::default())
+    }
+
+    /// Parse the headers of a wld file, see [WldDoc::parse_with_options].
+    ///
+    /// `options.strictness` has no effect as fragments are only parsed when accessed.
+    pub fn parse_with_options(
+        input: &'a [u8],
+        options: ParseOptions,
+    ) -> Result<WldView<'a>, Vec<WldDocError>> {
+        let (header, strings, fragment_headers) = WldDoc::parse_headers(input)?;
+        let version = options.version.unwrap_or_else(|| header.version());
+        let game = options
+            .game
+            .unwrap_or_else(|| detect_game(&fragment_headers));
+
+        Ok(WldView {
+            header,
+            strings,
+            fragments: fragment_headers.iter().map(|_| OnceLock::new()).collect(),
+            fragment_headers,
+            names: OnceLock::new(),
+            version,
+            game,
+        })
+    }
+
+    fn names(&self) -> &HashMap<String, usize> {
+        self.names.get_or_init(|| {
+            let mut names = HashMap::new();
+            for (idx, (_, header)) in self.fragment_headers.iter().enumerate() {
+                match self.strings.get(header.name_reference(self.game)) {
+                    Some(name) if !name.is_empty() => {
+                        names.entry(name.to_string()).or_insert(idx);
+                    }
+                    _ => {}
+                }
+            }
+            names
+        })
+    }
+
+    pub fn header(&self) -> &WldHeader {
+        &self.header
+    }
+
+    /// The version of the format the fragments are read as.
+    pub fn version(&self) -> WldVersion {
+        self.version
+    }
+
+    /// The game the fragments are read as, see [WldDoc::parse_with_options].
+    pub fn game(&self) -> FragmentGame {
+        self.game
+    }
+
+    pub fn fragment_count(&self) -> usize {
+        self.fragment_headers.len()
+    }
+
+    /// The header of the fragment at `idx` and the offset of its body in the file.
+    pub fn fragment_header(&self, idx: usize) -> Option<(usize, &FragmentHeader<'a>)> {
+        self.fragment_headers
+            .get(idx)
+            .map(|(offset, header)| (*offset, header))
+    }
+
+    /// Get a string given a string reference
+    pub fn get_string(&self, string_reference: StringReference) -> Option<&str> {
+        self.strings.get(string_reference)
+    }
+
+    /// Parse the fragment at `idx`, or return the result of parsing it earlier.
+    pub fn try_at(&self, idx: usize) -> Option<Result<&FragmentType, &WldDocError>> {
+        let (offset, header) = self.fragment_headers.get(idx)?;
+        let fragment = self.fragments[idx]
+            .get_or_init(|| header.parse_body(self.version, self.game, idx, *offset));
+        Some(fragment.as_ref())
+    }
+
+    /// Get a fragment given an index
+    pub fn at(&self, idx: usize) -> Option<&FragmentType> {
+        self.try_at(idx)?.ok()
+    }
+
+    /// Get a fragment given a fragment reference.
+    pub fn get<T: FragmentTarget>(&self, fragment_ref: &FragmentRef<T>) -> Option<&T> {
+        let idx = match fragment_ref {
+            FragmentRef::Index(idx, _) => (*idx as usize).checked_sub(1)?,
+            FragmentRef::Name(name_ref, _) => *self.names().get(self.strings.get(*name_ref)?)?,
+        };
+        T::from_fragment(self.at(idx)?)
+    }
+
+    /// Get a fragment by its name. If several fragments share a name the first is returned.
+    pub fn get_by_name(&self, name: &str) -> Option<&FragmentType> {
+        self.at(*self.names().get(name)?)
+    }
+
+    /// Get a fragment of a specific type by its name.
+    pub fn find<T: 'static + Fragment>(&self, name: &str) -> Option<&T> {
+        self.get_by_name(name)?.as_any().downcast_ref()
+    }
+
+    /// Iterate over all fragments of a given type. Only fragments with the type id of `T`
+    /// are parsed.
+    pub fn fragment_iter<T: 'static + Fragment + FragmentParser>(
+        &self,
+    ) -> impl Iterator<Item = &T> + '_ {
+        self.fragment_headers
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, header))| header.fragment_type == T::TYPE_ID)
+            .filter_map(|(idx, _)| self.at(idx)?.as_any().downcast_ref::<T>())
+    }
+
+    /// Iterate over all fragments, parsing each of them.
+    pub fn iter(&self) -> impl Iterator<Item = &FragmentType> + '_ {
+        (0..self.fragment_count()).filter_map(|idx| self.at(idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{MeshData, PrimitiveData, WldBuilder};
+    use crate::parser::{DmSpriteDef2, MaterialDef, MaterialPalette, MaterialType};
+
+    fn fixture() -> Vec<u8> {
+        let mut builder = WldBuilder::new();
+        let texture = builder.add_texture("BRICK", "brick.bmp");
+        let brick = builder.add_material("BRICK", Some(texture), MaterialType::Diffuse);
+        let plain = builder.add_material("PLAIN", None, MaterialType::Diffuse);
+        let mesh = builder
+            .add_mesh(MeshData {
+                name: "WALL".to_string(),
+                positions: vec![[0.0; 3], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                primitives: vec![
+                    PrimitiveData {
+                        material: brick,
+                        indices: vec![0, 1, 2],
+                        passable: false,
+                    },
+                    PrimitiveData {
+                        material: plain,
+                        indices: vec![2, 1, 0],
+                        passable: false,
+                    },
+                ],
+                ..Default::default()
+            })
+            .unwrap();
+        builder.add_model("WALL", mesh);
+        builder.build().to_bytes()
+    }
+
+    fn decoded(view: &WldView) -> usize {
+        view.fragments.iter().filter(|f| f.get().is_some()).count()
+    }
+
+    #[test]
+    fn it_parses_fragments_on_access() {
+        let data = fixture();
+        let wld_doc = WldDoc::parse(&data).unwrap();
+        let view = WldView::parse(&data).unwrap();
+        assert_eq!(view.fragment_count(), wld_doc.fragment_count());
+        assert_eq!(decoded(&view), 0);
+
+        let materials = view.fragment_iter::<MaterialDef>().collect::<Vec<_>>();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(decoded(&view), 2);
+
+        let wall = view.find::<DmSpriteDef2>("WALL_DMSPRITEDEF").unwrap();
+        assert_eq!(decoded(&view), 3);
+        let palette: &MaterialPalette = view.get(&wall.material_list_ref).unwrap();
+        assert_eq!(view.get_string(palette.name_reference), Some("WALL_MP"));
+        let brick = view.get(&palette.fragments[0]).unwrap();
+        assert_eq!(brick.to_bytes(), materials[0].to_bytes());
+        assert_eq!(decoded(&view), 4);
+
+        for (idx, fragment) in view.iter().enumerate() {
+            assert_eq!(fragment.to_bytes(), wld_doc.at(idx).unwrap().to_bytes());
+        }
+        assert_eq!(decoded(&view), view.fragment_count());
+    }
+
+    #[test]
+    fn it_keeps_parse_errors() {
+        let mut data = fixture();
+        // Claim more actions than the actor def holds so it runs past the end of its body.
+        let view = WldView::parse(&data).unwrap();
+        let (offset, header) = view.fragment_header(view.fragment_count() - 1).unwrap();
+        assert_eq!(header.fragment_type, 0x14);
+        let action_count = offset + 12;
+        data[action_count..action_count + 4].copy_from_slice(&1000u32.to_le_bytes());
+
+        let view = WldView::parse(&data).unwrap();
+        let idx = view.fragment_count() - 1;
+        assert!(view.at(idx).is_none());
+        assert!(matches!(
+            view.try_at(idx),
+            Some(Err(WldDocError::ParseFragment {
+                fragment_type: 0x14,
+                ..
+            }))
+        ));
+        assert!(view.find::<MaterialDef>("BRICK_MDF").is_some());
+        assert_eq!(view.iter().count(), view.fragment_count() - 1);
+    }
+}
@@ -0,0 +1,85 @@
+#![no_main]
+
+use libeq_wld::parser::*;
+use libfuzzer_sys::fuzz_target;
+
+/// Parse `data` as a `P` fragment body and serialize whatever comes out of it.
+fn parse<P>(data: &[u8], version: WldVersion)
+where
+    P: FragmentParser,
+    P::T: Fragment,
+{
+    if let Ok((_, fragment)) = P::parse_versioned(data, version) {
+        let _ = fragment.to_bytes_versioned(version);
+    }
+}
+
+macro_rules! fragment_parsers {
+    ($($parser:ty),* $(,)?) => {
+        &[$(parse::<$parser>),*]
+    };
+}
+
+const PARSERS: &[fn(&[u8], WldVersion)] = fragment_parsers![
+    Actor,
+    ActorDef,
+    AmbientLight,
+    BlitSprite,
+    BlitSpriteDef,
+    BmInfo,
+    BmInfoRtk,
+    DefaultPaletteFile,
+    DirectionalLight,
+    DmRGBTrack,
+    DmRGBTrackDef,
+    DmSprite,
+    DmSpriteDef,
+    DmSpriteDef2,
+    DmTrack,
+    DmTrackDef,
+    DmTrackDef2,
+    GlobalAmbientLightDef,
+    HierarchicalSprite,
+    HierarchicalSpriteDef,
+    Light,
+    LightDef,
+    MaterialDef,
+    MaterialPalette,
+    ParticleCloudDef,
+    ParticleSprite,
+    ParticleSpriteDef,
+    PointLight,
+    Polyhedron,
+    PolyhedronDef,
+    Region,
+    SimpleSprite,
+    SimpleSpriteDef,
+    Sphere,
+    SphereList,
+    SphereListDef,
+    Sprite2D,
+    Sprite2DDef,
+    Sprite3D,
+    Sprite3DDef,
+    Sprite4D,
+    Sprite4DDef,
+    Track,
+    TrackDef,
+    WorldTree,
+    WorldVertices,
+    Zone,
+];
+
+// The first byte picks the fragment parser and the file version, the rest is the body.
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, body)) = data.split_first() else {
+        return;
+    };
+    let version = if selector & 0x80 == 0 {
+        WldVersion::Old
+    } else {
+        WldVersion::New
+    };
+    let parser = PARSERS[(selector & 0x7f) as usize % PARSERS.len()];
+    parser(body, version);
+});
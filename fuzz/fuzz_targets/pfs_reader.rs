@@ -0,0 +1,24 @@
+#![no_main]
+
+use std::io::{Cursor, Read};
+
+use libeq_pfs::PfsReader;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(mut archive) = PfsReader::open(Cursor::new(data)) else {
+        return;
+    };
+    let _ = archive.archive_info();
+    let _ = archive.get("missing.bmp");
+    let Ok(filenames) = archive.filenames() else {
+        return;
+    };
+    for filename in filenames {
+        let _ = archive.info(&filename);
+        let _ = archive.get(&filename);
+        if let Ok(Some(mut reader)) = archive.get_reader(&filename) {
+            let _ = reader.read_to_end(&mut Vec::new());
+        }
+    }
+});
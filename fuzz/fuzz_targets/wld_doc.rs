@@ -0,0 +1,52 @@
+#![no_main]
+
+use libeq_wld::parser::WldDoc;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(wld_doc) = WldDoc::parse(data) {
+        let _ = wld_doc.to_bytes();
+        let _ = wld_doc.validate();
+    }
+    if let Ok((wld_doc, _)) = WldDoc::parse_lenient(data) {
+        let _ = wld_doc.to_bytes();
+        let _ = wld_doc.reference_graph();
+    }
+
+    // Walk everything reachable through the high level api as well, its accessors follow
+    // references and indices taken straight from the file.
+    let Ok(wld) = libeq_wld::load(data) else {
+        return;
+    };
+    for mesh in wld.meshes() {
+        let _ = mesh.name();
+        let _ = mesh.positions();
+        let _ = mesh.normals();
+        let _ = mesh.texture_coordinates();
+        let _ = mesh.indices();
+        let _ = mesh.collision_indices();
+        let _ = mesh.materials();
+        for primitive in mesh.primitives() {
+            let _ = primitive.indices();
+            let _ = primitive.material();
+        }
+        if let Some(animated_vertices) = mesh.animated_vertices() {
+            let _ = animated_vertices.frames();
+        }
+    }
+    for material in wld.materials() {
+        let _ = material.name();
+        if let Some(texture) = material.base_color_texture() {
+            let _ = texture.iter_sources().count();
+        }
+    }
+    for object in wld.objects() {
+        let _ = object.model_name();
+        let _ = (object.center(), object.rotation(), object.scale());
+    }
+    for model in wld.models() {
+        let _ = model.name();
+        let _ = model.type_name();
+        let _ = model.mesh().map(|mesh| mesh.positions());
+    }
+});
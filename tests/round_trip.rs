@@ -0,0 +1,260 @@
+//! Round trips every fragment of every wld file in a corpus, reporting fragments that
+//! don't serialize back to the bytes they were read from.
+//!
+//! Point `WLD_CORPUS` at a directory to run it over real game data. `.wld` files and
+//! the `.wld` files within `.s3d` archives are read from it and its subdirectories:
+//!
+//! ```shell
+//! WLD_CORPUS=~/everquest cargo test --test round_trip -- --nocapture
+//! ```
+#![cfg(all(feature = "wld", feature = "pfs"))]
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use libeq::pfs::{PfsReader, PfsWriter};
+use libeq::wld::builder::{MeshData, ObjectPlacement, PrimitiveData, WldBuilder};
+use libeq::wld::parser::{MaterialType, WldDoc};
+
+/// A fragment that didn't serialize back to the bytes it was read from.
+struct Mismatch {
+    source: String,
+    index: usize,
+    offset: usize,
+    detail: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: fragment {} at {:#010x}: {}",
+            self.source, self.index, self.offset, self.detail
+        )
+    }
+}
+
+#[derive(Default)]
+struct TypeReport {
+    type_name: Option<&'static str>,
+    fragments: usize,
+    mismatches: Vec<Mismatch>,
+}
+
+#[derive(Default)]
+struct Report {
+    files: usize,
+    /// Files that couldn't be read or whose header, strings or file as a whole didn't
+    /// round trip.
+    file_errors: Vec<String>,
+    types: BTreeMap<u32, TypeReport>,
+}
+
+impl Report {
+    fn is_clean(&self) -> bool {
+        self.file_errors.is_empty() && self.types.values().all(|t| t.mismatches.is_empty())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} files", self.files)?;
+        for error in &self.file_errors {
+            writeln!(f, "  {}", error)?;
+        }
+        for (type_id, report) in &self.types {
+            writeln!(
+                f,
+                "{:#04x} {}: {} fragments, {} mismatched",
+                type_id,
+                report.type_name.unwrap_or("Unknown"),
+                report.fragments,
+                report.mismatches.len()
+            )?;
+            for mismatch in report.mismatches.iter().take(10) {
+                writeln!(f, "  {}", mismatch)?;
+            }
+            if report.mismatches.len() > 10 {
+                writeln!(f, "  ...")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn round_trip(source: &str, data: &[u8], report: &mut Report) {
+    report.files += 1;
+    let table = match WldDoc::fragment_table(data) {
+        Ok(table) => table,
+        Err(errors) => {
+            report
+                .file_errors
+                .push(format!("{}: {}", source, errors[0]));
+            return;
+        }
+    };
+    let (wld_doc, warnings) = match WldDoc::parse_lenient(data) {
+        Ok(parsed) => parsed,
+        Err(errors) => {
+            report
+                .file_errors
+                .push(format!("{}: {}", source, errors[0]));
+            return;
+        }
+    };
+    let warnings = warnings
+        .iter()
+        .filter_map(|w| Some((w.index()?, w.to_string())))
+        .collect::<BTreeMap<_, _>>();
+
+    for entry in table {
+        let type_report = report.types.entry(entry.type_id).or_default();
+        type_report.type_name = entry.type_name;
+        type_report.fragments += 1;
+
+        let mismatch = |detail| Mismatch {
+            source: source.to_string(),
+            index: entry.index,
+            offset: entry.offset,
+            detail,
+        };
+        if let Some(warning) = warnings.get(&entry.index) {
+            type_report.mismatches.push(mismatch(warning.clone()));
+            continue;
+        }
+
+        let body_offset = entry.offset + 8;
+        let original = &data[body_offset..body_offset + entry.size as usize];
+        let fragment = wld_doc.at(entry.index).unwrap();
+        let mut serialized = fragment.to_bytes_versioned(wld_doc.version());
+        serialized.resize(serialized.len().next_multiple_of(4), 0);
+
+        if serialized != original {
+            let position = original
+                .iter()
+                .zip(&serialized)
+                .position(|(a, b)| a != b)
+                .unwrap_or(original.len().min(serialized.len()));
+            type_report.mismatches.push(mismatch(format!(
+                "{} bytes read, {} written, first difference at {:#010x}",
+                original.len(),
+                serialized.len(),
+                body_offset + position
+            )));
+        }
+    }
+
+    if wld_doc.to_bytes() != data {
+        report
+            .file_errors
+            .push(format!("{}: file does not round trip", source));
+    }
+}
+
+fn round_trip_archive(path: &Path, report: &mut Report) {
+    let source = path.display().to_string();
+    let archive = File::open(path)
+        .map_err(|e| e.into())
+        .and_then(PfsReader::open)
+        .and_then(|mut archive| Ok((archive.filenames()?, archive)));
+    let (filenames, mut archive) = match archive {
+        Ok(archive) => archive,
+        Err(e) => {
+            report.file_errors.push(format!("{}: {}", source, e));
+            return;
+        }
+    };
+    for filename in filenames.iter().filter(|f| f.ends_with(".wld")) {
+        let source = format!("{}/{}", source, filename);
+        match archive.get(filename) {
+            Ok(Some(data)) => round_trip(&source, &data, report),
+            Ok(None) => report.file_errors.push(format!("{}: missing", source)),
+            Err(e) => report.file_errors.push(format!("{}: {}", source, e)),
+        }
+    }
+}
+
+fn round_trip_dir(dir: &Path, report: &mut Report) {
+    let mut paths = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("Could not read {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    for path in paths {
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            _ if path.is_dir() => round_trip_dir(&path, report),
+            Some("wld") => match fs::read(&path) {
+                Ok(data) => round_trip(&path.display().to_string(), &data, report),
+                Err(e) => report
+                    .file_errors
+                    .push(format!("{}: {}", path.display(), e)),
+            },
+            Some("s3d") => round_trip_archive(&path, report),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn it_round_trips_the_corpus() {
+    let Some(corpus) = std::env::var_os("WLD_CORPUS").map(PathBuf::from) else {
+        eprintln!("WLD_CORPUS is not set, skipping");
+        return;
+    };
+
+    let mut report = Report::default();
+    round_trip_dir(&corpus, &mut report);
+    println!("{}", report);
+    assert!(
+        report.files > 0,
+        "No wld files found in {}",
+        corpus.display()
+    );
+    assert!(report.is_clean(), "{}", report);
+}
+
+#[test]
+fn it_round_trips_a_generated_corpus() {
+    let mut builder = WldBuilder::new();
+    let texture = builder.add_texture("CRATE", "crate.bmp");
+    let material = builder.add_material("CRATE", Some(texture), MaterialType::Diffuse);
+    let mesh = builder
+        .add_mesh(MeshData {
+            name: "CRATE".to_string(),
+            positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            primitives: vec![PrimitiveData {
+                material,
+                indices: vec![0, 1, 2],
+                passable: false,
+            }],
+            ..Default::default()
+        })
+        .unwrap();
+    let model = builder.add_model("CRATE", mesh);
+    builder.add_object(model, ObjectPlacement::at([10.0, 0.0, 5.0]));
+    let data = builder.build().to_bytes();
+
+    let corpus = std::env::temp_dir().join(format!("libeq-round-trip-{}", std::process::id()));
+    fs::create_dir_all(corpus.join("zones")).unwrap();
+    fs::write(corpus.join("crate.wld"), &data).unwrap();
+    let archive = File::create(corpus.join("zones/crate.s3d")).unwrap();
+    let mut writer = PfsWriter::create(archive).unwrap();
+    writer.insert("crate.wld", &data[..]).unwrap();
+    writer.insert("crate.bmp", &[0u8; 4][..]).unwrap();
+    writer.finish().unwrap();
+
+    let mut report = Report::default();
+    round_trip_dir(&corpus, &mut report);
+    fs::remove_dir_all(&corpus).unwrap();
+
+    assert!(report.is_clean(), "{}", report);
+    assert_eq!(report.files, 2);
+    assert_eq!(
+        report.types.values().map(|t| t.fragments).sum::<usize>(),
+        18
+    );
+}
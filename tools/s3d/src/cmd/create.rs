@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs::{self, File};
-use std::io;
+use std::io::{self, Cursor};
 use std::path::{Path, PathBuf};
 
 use libeq_pfs::PfsWriter;
 
+use crate::convert::{ImageFormat, convert_image, has_extension, with_extension};
+
 const HELP: &str = "\
 s3d create — Create archive from files
 
@@ -16,6 +18,11 @@ Directories are traversed recursively; files are flattened
 to basenames.
 
 Options:
+  -c, --convert <format> Convert PNG textures to the given format (bmp)
+                         before adding them. The converted files are
+                         stored with the new extension. Textures that
+                         were DDS in the original archive are stored
+                         as BMP, DDS can't be written.
   -f, --force            Overwrite existing archive
   -v, --verbose          Print filenames as added
   -h, --help             Show this help
@@ -34,6 +41,7 @@ pub fn eprint_help() {
 pub fn run(
     archive: &str,
     inputs: &[String],
+    convert: Option<ImageFormat>,
     verbose: bool,
     force: bool,
 ) -> Result<(), Box<dyn Error>> {
@@ -56,9 +64,8 @@ pub fn run(
     // Detect duplicate basenames — last one wins, warn about earlier ones
     let mut seen: HashMap<String, PathBuf> = HashMap::new();
     for path in &files {
-        let basename = match path.file_name() {
-            Some(name) => name.to_string_lossy().to_string(),
-            None => continue,
+        let Some(basename) = archive_name(path, convert) else {
+            continue;
         };
         if let Some(prev) = seen.insert(basename.clone(), path.clone()) {
             eprintln!(
@@ -74,15 +81,27 @@ pub fn run(
     let mut writer = PfsWriter::create(file).map_err(|e| format!("{}: {}", archive, e))?;
 
     for path in &files {
-        let basename = match path.file_name() {
-            Some(name) => name.to_string_lossy().to_string(),
-            None => continue,
+        let Some(basename) = archive_name(path, convert) else {
+            continue;
         };
 
-        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
-        writer
-            .insert(&basename, file)
-            .map_err(|e| format!("{}: {}", basename, e))?;
+        match convert {
+            Some(format) if needs_conversion(path) => {
+                let data = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                let converted = convert_image(&data, format)
+                    .map_err(|e| format!("{}: {}", path.display(), e))?
+                    .ok_or_else(|| format!("{}: not a PNG image", path.display()))?;
+                writer
+                    .insert(&basename, Cursor::new(converted))
+                    .map_err(|e| format!("{}: {}", basename, e))?;
+            }
+            _ => {
+                let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                writer
+                    .insert(&basename, file)
+                    .map_err(|e| format!("{}: {}", basename, e))?;
+            }
+        }
 
         if verbose {
             println!("{}", basename);
@@ -94,6 +113,23 @@ pub fn run(
     Ok(())
 }
 
+/// Whether the file at `path` is a PNG texture that should be converted.
+fn needs_conversion(path: &Path) -> bool {
+    has_extension(&path.to_string_lossy(), ImageFormat::Png)
+}
+
+/// The filename that `path` will be stored as in the archive.
+///
+/// Files are flattened to their basenames. PNG textures that are converted
+/// take on the extension of the target format.
+fn archive_name(path: &Path, convert: Option<ImageFormat>) -> Option<String> {
+    let basename = path.file_name()?.to_string_lossy().to_string();
+    match convert {
+        Some(format) if needs_conversion(path) => Some(with_extension(&basename, format)),
+        _ => Some(basename),
+    }
+}
+
 fn collect_files(path: &Path) -> io::Result<Vec<PathBuf>> {
     let mut files = Vec::new();
     if path.is_file() {
@@ -1,8 +1,9 @@
 use std::error::Error;
 use std::fs::{self, File};
-use std::io;
+use std::io::{self, Read};
 use std::path::Path;
 
+use crate::convert::{ImageFormat, convert_image, with_extension};
 use crate::open_archive;
 
 const HELP: &str = "\
@@ -15,6 +16,11 @@ all files are extracted.
 
 Options:
   -o, --output <dir>     Output directory (created if needed)
+  -c, --convert <format> Convert textures to the given format (png).
+                         The real image format (BMP or DDS) is detected
+                         from the file contents. Other files are
+                         extracted as-is. Creating an archive from the
+                         converted files stores DDS textures as BMP.
   -v, --verbose          Print filenames as extracted
   -h, --help             Show this help
 
@@ -32,6 +38,7 @@ pub fn run(
     archive: &str,
     files: &[String],
     output: Option<&str>,
+    convert: Option<ImageFormat>,
     verbose: bool,
 ) -> Result<(), Box<dyn Error>> {
     let (mut reader, all_filenames) = open_archive(archive)?;
@@ -52,11 +59,39 @@ pub fn run(
             .map_err(|e| format!("{}: {}: {}", archive, name, e))?
             .ok_or_else(|| format!("{}: {}: not found in archive", archive, name))?;
 
-        let out_path = match output {
+        let out_path = |name: &str| match output {
             Some(dir) => Path::new(dir).join(name),
             None => Path::new(name).to_path_buf(),
         };
 
+        if let Some(format) = convert {
+            let mut data = Vec::new();
+            file_reader
+                .read_to_end(&mut data)
+                .map_err(|e| format!("{}: {}: {}", archive, name, e))?;
+
+            let (out_name, data) = match convert_image(&data, format) {
+                Ok(Some(converted)) => (with_extension(name, format), converted),
+                Ok(None) => (name.to_string(), data),
+                Err(e) => {
+                    eprintln!(
+                        "warning: {}: {}: could not convert, extracting as-is: {}",
+                        archive, name, e
+                    );
+                    (name.to_string(), data)
+                }
+            };
+
+            let out_path = out_path(&out_name);
+            fs::write(&out_path, data).map_err(|e| format!("{}: {}", out_path.display(), e))?;
+
+            if verbose {
+                println!("{}", out_name);
+            }
+            continue;
+        }
+
+        let out_path = out_path(name);
         let mut out_file =
             File::create(&out_path).map_err(|e| format!("{}: {}", out_path.display(), e))?;
         io::copy(&mut file_reader, &mut out_file)
@@ -9,7 +9,9 @@ s3d get — Extract single file to stdout
 Usage: s3d get <archive> <filename>
 
 Writes the raw contents of a single file to stdout.
-Errors are printed to stderr.";
+Errors are printed to stderr.
+
+Aliases: cat";
 
 pub fn print_help() {
     println!("{}", HELP);
@@ -0,0 +1,259 @@
+use std::error::Error;
+use std::io::{self, Read};
+
+use crate::open_archive;
+
+const HELP: &str = "\
+s3d grep — Search archive file contents
+
+Usage: s3d grep [options] <pattern> <archive>...
+
+Searches the decompressed contents of every file in the given
+archives for a pattern. Each match is printed as
+<archive>: <file>: <offset>, where offset is the byte offset
+of the match within the decompressed file.
+
+Options:
+  -x, --hex                 Treat pattern as hex bytes (e.g. \"44 44 53 20\")
+  -i, --ignore-case         Ignore ASCII case when matching
+  -l, --files-with-matches  Only print the names of matching files
+  -h, --help                Show this help";
+
+/// Size of the chunks read from each file while searching.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+pub fn print_help() {
+    println!("{}", HELP);
+}
+
+pub fn eprint_help() {
+    eprintln!("{}", HELP);
+}
+
+/// Parse a pattern given as hex digits into bytes.
+///
+/// Whitespace between digits and a leading `0x` are ignored.
+pub fn parse_hex(pattern: &str) -> Result<Vec<u8>, String> {
+    let digits: String = pattern.split_whitespace().collect();
+    let digits = digits.strip_prefix("0x").unwrap_or(&digits);
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("invalid hex pattern '{}'", pattern));
+    }
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return Err(format!(
+            "invalid hex pattern '{}': expected an even number of hex digits",
+            pattern
+        ));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("invalid hex pattern '{}'", pattern))
+        })
+        .collect()
+}
+
+/// Search all files in the given archives for `pattern`.
+pub fn run(
+    pattern: &[u8],
+    files: &[String],
+    ignore_case: bool,
+    files_with_matches: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut failed = Vec::new();
+
+    for path in files {
+        if let Err(e) = grep_archive(path, pattern, ignore_case, files_with_matches) {
+            eprintln!("{}", e);
+            failed.push(path.as_str());
+        }
+    }
+
+    if !failed.is_empty() {
+        return Err(format!("{} archive(s) failed", failed.len()).into());
+    }
+
+    Ok(())
+}
+
+fn grep_archive(
+    path: &str,
+    pattern: &[u8],
+    ignore_case: bool,
+    files_with_matches: bool,
+) -> Result<(), Box<dyn Error>> {
+    let (mut reader, filenames) = open_archive(path)?;
+
+    let mut failed: usize = 0;
+
+    for name in &filenames {
+        let mut file_reader = match reader.get_reader(name) {
+            Ok(Some(r)) => r,
+            Ok(None) => {
+                eprintln!("{}: {}: not found in index", path, name);
+                failed += 1;
+                continue;
+            }
+            Err(e) => {
+                eprintln!("{}: {}: {}", path, name, e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let result = search(&mut file_reader, pattern, ignore_case, |offset| {
+            if files_with_matches {
+                println!("{}: {}", path, name);
+                false
+            } else {
+                println!("{}: {}: {}", path, name, offset);
+                true
+            }
+        });
+
+        if let Err(e) = result {
+            eprintln!("{}: {}: {}", path, name, e);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{}: {} file(s) could not be searched", path, failed).into());
+    }
+
+    Ok(())
+}
+
+/// Stream `reader` looking for `pattern`, calling `on_match` with the
+/// offset of each match.
+///
+/// The reader is consumed in chunks so that only a small window of the file
+/// is held in memory at once. The search stops early if `on_match`
+/// returns `false`.
+fn search(
+    reader: &mut impl Read,
+    pattern: &[u8],
+    ignore_case: bool,
+    mut on_match: impl FnMut(u64) -> bool,
+) -> io::Result<()> {
+    let mut chunk = [0u8; CHUNK_SIZE];
+    let mut window = Vec::with_capacity(CHUNK_SIZE + pattern.len());
+    // Offset of the first byte in `window` within the file
+    let mut window_offset: u64 = 0;
+
+    loop {
+        let n = reader.read(&mut chunk)?;
+        if n == 0 {
+            return Ok(());
+        }
+        window.extend_from_slice(&chunk[..n]);
+        if window.len() < pattern.len() {
+            continue;
+        }
+
+        for (i, candidate) in window.windows(pattern.len()).enumerate() {
+            let is_match = if ignore_case {
+                candidate.eq_ignore_ascii_case(pattern)
+            } else {
+                candidate == pattern
+            };
+            if is_match && !on_match(window_offset + i as u64) {
+                return Ok(());
+            }
+        }
+
+        // Keep the tail of the window, a match may straddle the next chunk.
+        let consumed = window.len() - (pattern.len() - 1);
+        window.drain(..consumed);
+        window_offset += consumed as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn matches(data: &[u8], pattern: &[u8], ignore_case: bool) -> Vec<u64> {
+        let mut offsets = Vec::new();
+        search(&mut Cursor::new(data), pattern, ignore_case, |offset| {
+            offsets.push(offset);
+            true
+        })
+        .unwrap();
+        offsets
+    }
+
+    #[test]
+    fn it_parses_hex_patterns() {
+        assert_eq!(parse_hex("44 44 53 20"), Ok(vec![0x44, 0x44, 0x53, 0x20]));
+        assert_eq!(parse_hex("0x4444"), Ok(vec![0x44, 0x44]));
+        assert_eq!(parse_hex("aB"), Ok(vec![0xab]));
+    }
+
+    #[test]
+    fn it_rejects_invalid_hex_patterns() {
+        assert!(parse_hex("").is_err());
+        assert!(parse_hex("0x").is_err());
+        assert!(parse_hex("444").is_err());
+        assert!(parse_hex("4g").is_err());
+        assert!(parse_hex("+1").is_err());
+        assert!(parse_hex("aéb").is_err());
+    }
+
+    #[test]
+    fn it_finds_matches_across_chunks() {
+        let mut data = vec![0u8; CHUNK_SIZE * 2];
+        data[CHUNK_SIZE - 2..CHUNK_SIZE + 2].copy_from_slice(b"DDS ");
+        data[CHUNK_SIZE + 10..CHUNK_SIZE + 14].copy_from_slice(b"DDS ");
+
+        assert_eq!(
+            matches(&data, b"DDS ", false),
+            vec![CHUNK_SIZE as u64 - 2, CHUNK_SIZE as u64 + 10]
+        );
+    }
+
+    #[test]
+    fn it_finds_overlapping_matches() {
+        assert_eq!(matches(b"aaaa", b"aa", false), vec![0, 1, 2]);
+
+        let data = vec![b'a'; CHUNK_SIZE + 2];
+        let offsets = matches(&data, b"aaa", false);
+        assert_eq!(offsets, (0..CHUNK_SIZE as u64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_finds_patterns_longer_than_a_chunk() {
+        let pattern: Vec<u8> = (0..CHUNK_SIZE + 100).map(|i| (i % 251) as u8).collect();
+        let mut data = vec![0xff; 5];
+        data.extend_from_slice(&pattern);
+        data.extend_from_slice(&pattern);
+
+        assert_eq!(
+            matches(&data, &pattern, false),
+            vec![5, 5 + pattern.len() as u64]
+        );
+        assert_eq!(matches(&pattern[1..], &pattern, false), vec![]);
+    }
+
+    #[test]
+    fn it_ignores_ascii_case() {
+        assert_eq!(matches(b"Fire1.BMP fire1.bmp", b"FIRE1.BMP", false), vec![]);
+        assert_eq!(
+            matches(b"Fire1.BMP fire1.bmp", b"FIRE1.BMP", true),
+            vec![0, 10]
+        );
+    }
+
+    #[test]
+    fn it_stops_when_asked() {
+        let mut offsets = Vec::new();
+        search(&mut Cursor::new(b"abab"), b"ab", false, |offset| {
+            offsets.push(offset);
+            false
+        })
+        .unwrap();
+        assert_eq!(offsets, vec![0]);
+    }
+}
@@ -1,7 +1,10 @@
 use std::error::Error;
 use std::fs;
 
-use crate::fmt::{format_number, format_size, format_timestamp};
+use libeq_pfs::PfsInfo;
+
+use crate::fmt::{format_number, format_size, format_timestamp, format_total_ratio};
+use crate::layout::{ArchiveMap, HEADER_SIZE, SectionKind};
 use crate::open_archive;
 
 const HELP: &str = "\
@@ -10,6 +13,8 @@ s3d info — Display archive metadata
 Usage: s3d info [options] <archive>...
 
 Options:
+  -l, --layout           Show the on-disk layout of the archive, including
+                         gaps and orphaned data not referenced by the index
   -r, --raw              Show raw numeric values
   -h, --help             Show this help
 
@@ -23,7 +28,7 @@ pub fn eprint_help() {
     eprintln!("{}", HELP);
 }
 
-pub fn run(files: &[String], human: bool) -> Result<(), Box<dyn Error>> {
+pub fn run(files: &[String], layout: bool, human: bool) -> Result<(), Box<dyn Error>> {
     let mut failed = Vec::new();
 
     for (i, path) in files.iter().enumerate() {
@@ -31,7 +36,7 @@ pub fn run(files: &[String], human: bool) -> Result<(), Box<dyn Error>> {
             println!();
         }
 
-        if let Err(e) = show_info(path, human) {
+        if let Err(e) = show_info(path, layout, human) {
             eprintln!("{}", e);
             failed.push(path.as_str());
         }
@@ -44,12 +49,12 @@ pub fn run(files: &[String], human: bool) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn show_info(path: &str, human: bool) -> Result<(), Box<dyn Error>> {
+fn show_info(path: &str, layout: bool, human: bool) -> Result<(), Box<dyn Error>> {
     let file_size = fs::metadata(path)
         .map_err(|e| format!("{}: {}", path, e))?
         .len();
 
-    let (mut reader, _filenames) = open_archive(path)?;
+    let (mut reader, filenames) = open_archive(path)?;
 
     let info = reader
         .archive_info()
@@ -81,5 +86,97 @@ fn show_info(path: &str, human: bool) -> Result<(), Box<dyn Error>> {
         println!("  timestamp:     {}", format_timestamp(ts, human));
     }
 
+    if layout {
+        println!();
+        show_layout(path, &info, &filenames, human)?;
+    }
+
+    Ok(())
+}
+
+fn show_layout(
+    path: &str,
+    info: &PfsInfo,
+    filenames: &[String],
+    human: bool,
+) -> Result<(), Box<dyn Error>> {
+    let data = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    let map = ArchiveMap::new(&data).map_err(|e| format!("{}: {}", path, e))?;
+    let sections = map.sections();
+
+    println!(
+        "{:>10}  {:>10}  {:>10}  {:>6}  {:>6}  name",
+        "start", "end", "size", "blocks", "ratio"
+    );
+    for section in &sections {
+        let (blocks, ratio) = match &section.kind {
+            SectionKind::File {
+                blocks,
+                compressed_size,
+                uncompressed_size,
+                ..
+            } => (
+                blocks.to_string(),
+                format_total_ratio(*compressed_size, *uncompressed_size),
+            ),
+            _ => ("-".to_string(), "-".to_string()),
+        };
+        let label = match &section.kind {
+            SectionKind::Header => "[header]",
+            SectionKind::File { name, .. } => name,
+            SectionKind::Orphaned => "[orphaned]",
+            SectionKind::Index => "[index]",
+            SectionKind::Footer => "[footer]",
+            SectionKind::Trailing => "[trailing]",
+        };
+        println!(
+            "{:>10}  {:>10}  {:>10}  {:>6}  {:>6}  {}",
+            format_number(section.start, human),
+            format_number(section.end, human),
+            format_size(section.end - section.start, human),
+            blocks,
+            ratio,
+            label
+        );
+    }
+
+    let block_section = (info.index_offset as u64).saturating_sub(HEADER_SIZE);
+    let orphaned_regions = sections
+        .iter()
+        .filter(|s| s.kind == SectionKind::Orphaned)
+        .count();
+    let trailing_size: u64 = sections
+        .iter()
+        .filter(|s| s.kind == SectionKind::Trailing)
+        .map(|s| s.end - s.start)
+        .sum();
+
+    println!();
+    println!("  block section: {}", format_size(block_section, human));
+    println!(
+        "  orphaned:      {} in {} region(s) ({} of block section)",
+        format_size(map.orphaned, human),
+        orphaned_regions,
+        format_total_ratio(map.orphaned, block_section)
+    );
+    if map.overlapping > 0 {
+        println!("  overlapping:   {}", format_size(map.overlapping, human));
+    }
+    if trailing_size > 0 {
+        println!("  trailing:      {}", format_size(trailing_size, human));
+    }
+    println!(
+        "  wasted:        {}",
+        format_size(map.orphaned + trailing_size, human)
+    );
+    let unnamed = (info.file_count as usize).saturating_sub(filenames.len());
+    if unnamed > 0 {
+        println!(
+            "  warning:       {} index entries have no name in the directory, \
+             their data is reported as orphaned",
+            unnamed
+        );
+    }
+
     Ok(())
 }
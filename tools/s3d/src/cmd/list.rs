@@ -1,6 +1,8 @@
 use std::error::Error;
 
-use crate::fmt::{format_number, format_ratio, format_size, format_total_ratio};
+use crate::fmt::{
+    format_content_type, format_number, format_ratio, format_size, format_total_ratio,
+};
 use crate::open_archive;
 
 const HELP: &str = "\
@@ -9,7 +11,9 @@ s3d list — List archive contents
 Usage: s3d list [options] <archive>...
 
 Options:
-  -v, --verbose          Show compressed/uncompressed sizes and ratio
+  -v, --verbose          Show compressed/uncompressed sizes, ratio and the
+                         content type detected from the file data. Types
+                         marked with '*' don't match the file extension.
   -vv                    Also show offsets and block counts
   -r, --raw              Show raw numeric values
   -h, --help             Show this help
@@ -72,8 +76,8 @@ fn list_verbose(path: &str, show_header: bool, human: bool) -> Result<(), Box<dy
     }
 
     println!(
-        "{:>10}  {:>12}  {:>5}  name",
-        "compressed", "uncompressed", "ratio"
+        "{:>10}  {:>12}  {:>5}  {:<8}  name",
+        "compressed", "uncompressed", "ratio", "type"
     );
 
     let mut total_compressed: u64 = 0;
@@ -86,12 +90,18 @@ fn list_verbose(path: &str, show_header: bool, human: bool) -> Result<(), Box<dy
             .map_err(|e| format!("{}: {}: {}", path, name, e))?
             .ok_or_else(|| format!("{}: {}: not found in index", path, name))?;
 
+        let content_type = reader
+            .content_type(name)
+            .map_err(|e| format!("{}: {}: {}", path, name, e))?
+            .ok_or_else(|| format!("{}: {}: not found in index", path, name))?;
+
         let ratio = format_ratio(&info);
         println!(
-            "{:>10}  {:>12}  {:>5}  {}",
+            "{:>10}  {:>12}  {:>5}  {:<8}  {}",
             format_size(info.compressed_size as u64, human),
             format_size(info.uncompressed_size as u64, human),
             ratio,
+            format_content_type(content_type, name),
             name
         );
 
@@ -107,10 +117,11 @@ fn list_verbose(path: &str, show_header: bool, human: bool) -> Result<(), Box<dy
         "----------", "------------", "-----"
     );
     println!(
-        "{:>10}  {:>12}  {:>5}  {} files",
+        "{:>10}  {:>12}  {:>5}  {:<8}  {} files",
         format_size(total_compressed, human),
         format_size(total_uncompressed, human),
         total_ratio,
+        "",
         file_count
     );
 
@@ -125,8 +136,8 @@ fn list_very_verbose(path: &str, show_header: bool, human: bool) -> Result<(), B
     }
 
     println!(
-        "{:>10}  {:>10}  {:>12}  {:>6}  {:>5}  name",
-        "offset", "compressed", "uncompressed", "blocks", "ratio"
+        "{:>10}  {:>10}  {:>12}  {:>6}  {:>5}  {:<8}  name",
+        "offset", "compressed", "uncompressed", "blocks", "ratio", "type"
     );
 
     let mut total_compressed: u64 = 0;
@@ -139,14 +150,20 @@ fn list_very_verbose(path: &str, show_header: bool, human: bool) -> Result<(), B
             .map_err(|e| format!("{}: {}: {}", path, name, e))?
             .ok_or_else(|| format!("{}: {}: not found in index", path, name))?;
 
+        let content_type = reader
+            .content_type(name)
+            .map_err(|e| format!("{}: {}: {}", path, name, e))?
+            .ok_or_else(|| format!("{}: {}: not found in index", path, name))?;
+
         let ratio = format_ratio(&info);
         println!(
-            "{:>10}  {:>10}  {:>12}  {:>6}  {:>5}  {}",
+            "{:>10}  {:>10}  {:>12}  {:>6}  {:>5}  {:<8}  {}",
             format_number(info.data_offset as u64, human),
             format_size(info.compressed_size as u64, human),
             format_size(info.uncompressed_size as u64, human),
             info.block_count,
             ratio,
+            format_content_type(content_type, name),
             name
         );
 
@@ -161,12 +178,13 @@ fn list_very_verbose(path: &str, show_header: bool, human: bool) -> Result<(), B
 
     let ratio = format_ratio(&dir_info);
     println!(
-        "{:>10}  {:>10}  {:>12}  {:>6}  {:>5}  [directory]",
+        "{:>10}  {:>10}  {:>12}  {:>6}  {:>5}  {:<8}  [directory]",
         format_number(dir_info.data_offset as u64, human),
         format_size(dir_info.compressed_size as u64, human),
         format_size(dir_info.uncompressed_size as u64, human),
         dir_info.block_count,
-        ratio
+        ratio,
+        ""
     );
     total_compressed += dir_info.compressed_size as u64;
     total_uncompressed += dir_info.uncompressed_size as u64;
@@ -178,12 +196,13 @@ fn list_very_verbose(path: &str, show_header: bool, human: bool) -> Result<(), B
         "", "----------", "------------", "", "-----"
     );
     println!(
-        "{:>10}  {:>10}  {:>12}  {:>6}  {:>5}  {} files + directory",
+        "{:>10}  {:>10}  {:>12}  {:>6}  {:>5}  {:<8}  {} files + directory",
         "",
         format_size(total_compressed, human),
         format_size(total_uncompressed, human),
         "",
         total_ratio,
+        "",
         file_count
     );
 
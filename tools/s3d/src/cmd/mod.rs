@@ -1,6 +1,7 @@
 pub mod create;
 pub mod extract;
 pub mod get;
+pub mod grep;
 pub mod info;
 pub mod list;
 pub mod verify;
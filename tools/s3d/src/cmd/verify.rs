@@ -3,6 +3,8 @@ use std::io::{self, Cursor};
 
 use libeq_pfs::PfsReader;
 
+use crate::fmt::format_hex_dump;
+use crate::layout::{ArchiveMap, Location};
 use crate::open_archive;
 
 const HELP: &str = "\
@@ -11,9 +13,13 @@ s3d verify — Verify archive integrity
 Usage: s3d verify [options] <archive>...
 
 Reads every file entry and performs a bitwise round-trip check.
+If the round trip does not reproduce the archive, the first
+difference is mapped back to the archive structure and the
+likely cause is reported.
 
 Options:
   -v, --verbose          Show per-file results
+  -x, --hex              Dump the differing region of both archives as hex
   -h, --help             Show this help
 
 Aliases: v";
@@ -27,7 +33,7 @@ pub fn eprint_help() {
 }
 
 /// Verify all files in the given archives.
-pub fn run(files: &[String], verbose: bool) -> Result<(), Box<dyn Error>> {
+pub fn run(files: &[String], verbose: bool, hex: bool) -> Result<(), Box<dyn Error>> {
     let mut failed_archives = Vec::new();
 
     for (i, path) in files.iter().enumerate() {
@@ -35,7 +41,7 @@ pub fn run(files: &[String], verbose: bool) -> Result<(), Box<dyn Error>> {
             println!();
         }
 
-        if let Err(e) = verify_archive(path, verbose) {
+        if let Err(e) = verify_archive(path, verbose, hex) {
             eprintln!("{}", e);
             failed_archives.push(path.as_str());
         }
@@ -48,7 +54,7 @@ pub fn run(files: &[String], verbose: bool) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn verify_archive(path: &str, verbose: bool) -> Result<(), Box<dyn Error>> {
+fn verify_archive(path: &str, verbose: bool, hex: bool) -> Result<(), Box<dyn Error>> {
     // Phase 1: Read check — decompress every file, verify sizes
     let (mut reader, filenames) = open_archive(path)?;
 
@@ -141,13 +147,29 @@ fn verify_archive(path: &str, verbose: bool) -> Result<(), Box<dyn Error>> {
             .position(|(a, b)| a != b)
             .unwrap_or(std::cmp::min(original.len(), roundtripped.len()));
 
-        return Err(format!(
+        let mut message = format!(
             "{}: round-trip FAILED (original {} bytes, roundtripped {} bytes, first difference at byte {})",
             path,
             original.len(),
             roundtripped.len(),
             first_diff
-        ).into());
+        );
+        match diagnose(&original, &roundtripped, first_diff as u64) {
+            Ok(diagnosis) => message.push_str(&diagnosis),
+            Err(e) => message.push_str(&format!("\n  could not map difference: {}", e)),
+        }
+        if hex {
+            // Show a little context before the difference, aligned to a line
+            let start = first_diff.saturating_sub(HEX_CONTEXT) & !0xf;
+            let end = first_diff + HEX_CONTEXT;
+            message.push_str("\n  original bytes:\n");
+            message.push_str(&format_hex_dump(&original, start, end, "    "));
+            message.push_str("  roundtripped bytes:\n");
+            message.push_str(&format_hex_dump(&roundtripped, start, end, "    "));
+            // The dumps end with a newline, the caller adds its own.
+            message.pop();
+        }
+        return Err(message.into());
     }
 
     println!(
@@ -159,3 +181,206 @@ fn verify_archive(path: &str, verbose: bool) -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+/// Number of bytes shown on each side of a difference in hex dumps.
+const HEX_CONTEXT: usize = 64;
+
+/// Describe where in the archive structure `offset` falls in both the
+/// original and roundtripped archives, and the likely cause of the mismatch.
+fn diagnose(original: &[u8], roundtripped: &[u8], offset: u64) -> Result<String, Box<dyn Error>> {
+    let original_map = ArchiveMap::new(original)?;
+    let original_location = original_map.locate(offset).map(|s| &s.location);
+    let describe = |location: Option<&Location>| match location {
+        Some(l) => l.to_string(),
+        None => "end of file".to_string(),
+    };
+
+    let roundtripped_map = match ArchiveMap::new(roundtripped) {
+        Ok(map) => map,
+        Err(e) => {
+            return Ok(format!(
+                "\n  original:      {}\n  roundtripped:  unreadable ({})",
+                describe(original_location),
+                e
+            ));
+        }
+    };
+    let roundtripped_location = roundtripped_map.locate(offset).map(|s| &s.location);
+    let cause = explain(&original_map, original_location, roundtripped_location);
+
+    Ok(format!(
+        "\n  original:      {}\n  roundtripped:  {}\n  cause:         {}",
+        describe(original_location),
+        describe(roundtripped_location),
+        cause
+    ))
+}
+
+/// Categorize a mismatch based on where it occurs in each archive.
+fn explain(
+    original: &ArchiveMap,
+    original_location: Option<&Location>,
+    roundtripped_location: Option<&Location>,
+) -> String {
+    let orphaned_note = || {
+        if original.orphaned > 0 {
+            format!(
+                "; the original has {} bytes of orphaned data which are not copied",
+                original.orphaned
+            )
+        } else {
+            String::new()
+        }
+    };
+
+    match (original_location, roundtripped_location) {
+        (None, _) => "roundtripped archive is longer than the original".to_string(),
+        (Some(Location::Trailing), _) => {
+            "the original has trailing data after the index and footer, which is not preserved"
+                .to_string()
+        }
+        (_, None) => "roundtripped archive is shorter than the original".to_string(),
+        (
+            Some(Location::Header {
+                field: "index_offset",
+            }),
+            _,
+        ) => {
+            format!("block section size differs{}", orphaned_note())
+        }
+        (Some(Location::Header { field }), _) => format!("header {} differs", field),
+        (Some(Location::Orphaned), _) => {
+            "orphaned data in the original is not copied by the round trip".to_string()
+        }
+        (
+            Some(Location::BlockHeader { file, .. } | Location::BlockData { file, .. }),
+            Some(other),
+        ) if other.file() != Some(file.as_str()) => format!(
+            "files are stored in a different order in the block section{}",
+            orphaned_note()
+        ),
+        (Some(Location::BlockHeader { file, .. } | Location::BlockData { file, .. }), _)
+            if file == "[directory]" && !original.directory_sorted =>
+        {
+            "directory sort order: the original directory is not sorted by filename CRC".to_string()
+        }
+        (Some(Location::BlockHeader { .. }), _) => "block header differs".to_string(),
+        (Some(Location::BlockData { .. }), _) => "compression difference".to_string(),
+        (Some(Location::IndexCount), _) => "index entry count differs".to_string(),
+        (Some(Location::IndexEntry { .. }), _) if !original.index_sorted => {
+            "index sort order: the original index is not sorted by filename CRC".to_string()
+        }
+        (Some(Location::IndexEntry { field, .. }), _) if *field == "data_offset" => format!(
+            "file data offsets differ, the block section layout changed{}",
+            orphaned_note()
+        ),
+        (Some(Location::IndexEntry { field, .. }), _) => {
+            format!("index entry {} differs", field)
+        }
+        (Some(Location::Footer { field: "timestamp" }), Some(Location::Footer { .. })) => {
+            "footer timestamp differs, the archive was treated as modified".to_string()
+        }
+        (Some(Location::Footer { field }), Some(Location::Footer { .. })) => {
+            format!("footer {} differs", field)
+        }
+        (Some(Location::Footer { .. }), _) => {
+            "footer presence differs between the archives".to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::tests::archive;
+
+    fn block(file: &str) -> Location {
+        Location::BlockData {
+            file: file.to_string(),
+            block: 0,
+        }
+    }
+
+    #[test]
+    fn it_explains_length_differences() {
+        let map = ArchiveMap::new(&archive(&["a.txt"], &[])).unwrap();
+
+        assert_eq!(
+            explain(&map, None, None),
+            "roundtripped archive is longer than the original"
+        );
+        assert_eq!(
+            explain(&map, Some(&Location::IndexCount), None),
+            "roundtripped archive is shorter than the original"
+        );
+    }
+
+    #[test]
+    fn it_explains_header_differences() {
+        let map = ArchiveMap::new(&archive(&["a.txt", "b.txt"], &["a.txt"])).unwrap();
+        let header = |field| Location::Header { field };
+
+        assert_eq!(
+            explain(
+                &map,
+                Some(&header("index_offset")),
+                Some(&header("index_offset"))
+            ),
+            format!(
+                "block section size differs; the original has {} bytes of orphaned data which are not copied",
+                map.orphaned
+            )
+        );
+        assert_eq!(
+            explain(&map, Some(&header("version")), Some(&header("version"))),
+            "header version differs"
+        );
+    }
+
+    #[test]
+    fn it_explains_block_differences() {
+        let map = ArchiveMap::new(&archive(&["a.txt", "b.txt"], &[])).unwrap();
+
+        assert_eq!(
+            explain(&map, Some(&block("a.txt")), Some(&block("b.txt"))),
+            "files are stored in a different order in the block section"
+        );
+        assert_eq!(
+            explain(&map, Some(&block("a.txt")), Some(&block("a.txt"))),
+            "compression difference"
+        );
+        assert_eq!(
+            explain(&map, Some(&Location::Orphaned), Some(&block("b.txt"))),
+            "orphaned data in the original is not copied by the round trip"
+        );
+    }
+
+    #[test]
+    fn it_explains_footer_differences() {
+        let map = ArchiveMap::new(&archive(&["a.txt"], &[])).unwrap();
+        let footer = |field| Location::Footer { field };
+
+        assert_eq!(
+            explain(&map, Some(&footer("timestamp")), Some(&footer("timestamp"))),
+            "footer timestamp differs, the archive was treated as modified"
+        );
+        assert_eq!(
+            explain(
+                &map,
+                Some(&footer("footer_string")),
+                Some(&Location::Trailing)
+            ),
+            "footer presence differs between the archives"
+        );
+    }
+
+    #[test]
+    fn it_diagnoses_dropped_orphaned_data() {
+        let original = archive(&["a.txt", "b.txt"], &["a.txt"]);
+        let roundtripped = archive(&["b.txt"], &[]);
+
+        let diagnosis = diagnose(&original, &roundtripped, 0).unwrap();
+        assert!(diagnosis.contains("original:      header (index_offset)"));
+        assert!(diagnosis.contains("cause:         block section size differs; the original has"));
+    }
+}
@@ -0,0 +1,185 @@
+use std::error::Error;
+use std::io::Cursor;
+use std::path::Path;
+
+use libeq_pfs::ContentType;
+
+/// Image formats that textures can be converted between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImageFormat {
+    Bmp,
+    Dds,
+    Png,
+}
+
+impl ImageFormat {
+    /// Parse a conversion target format name as given on the command line.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "bmp" => Some(ImageFormat::Bmp),
+            "png" => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+
+    /// Detect the real format of image data from its contents.
+    ///
+    /// Textures are nearly always stored with a `.bmp` extension even when
+    /// they contain DDS data, so the filename can't be trusted.
+    pub(crate) fn detect(data: &[u8]) -> Option<Self> {
+        match ContentType::detect(data) {
+            ContentType::Bmp => Some(ImageFormat::Bmp),
+            ContentType::Dds => Some(ImageFormat::Dds),
+            ContentType::Png => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Dds => "dds",
+            ImageFormat::Png => "png",
+        }
+    }
+
+    fn to_image_format(self) -> image::ImageFormat {
+        match self {
+            ImageFormat::Bmp => image::ImageFormat::Bmp,
+            ImageFormat::Dds => image::ImageFormat::Dds,
+            ImageFormat::Png => image::ImageFormat::Png,
+        }
+    }
+}
+
+/// Convert image `data` into the `target` format.
+///
+/// Returns `Ok(None)` if the data isn't an image format that we recognize.
+/// Data already in the target format is returned unchanged.
+pub(crate) fn convert_image(
+    data: &[u8],
+    target: ImageFormat,
+) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+    let Some(source) = ImageFormat::detect(data) else {
+        return Ok(None);
+    };
+    if source == target {
+        return Ok(Some(data.to_vec()));
+    }
+
+    let image = image::load_from_memory_with_format(data, source.to_image_format())?;
+    // The BMP encoder only handles 8-bit channels. Normalize to RGB(A)
+    // and only keep an alpha channel if the source image had one.
+    let image = match target {
+        ImageFormat::Bmp if !image.color().has_alpha() => image.to_rgb8().into(),
+        ImageFormat::Bmp => image.to_rgba8().into(),
+        _ => image,
+    };
+
+    let mut out = Cursor::new(Vec::new());
+    image.write_to(&mut out, target.to_image_format())?;
+    Ok(Some(out.into_inner()))
+}
+
+/// Replace the extension of `filename` with the one for `format`.
+pub(crate) fn with_extension(filename: &str, format: ImageFormat) -> String {
+    Path::new(filename)
+        .with_extension(format.extension())
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Whether `filename` has the extension of `format`, ignoring case.
+pub(crate) fn has_extension(filename: &str, format: ImageFormat) -> bool {
+    Path::new(filename)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(format.extension()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4x4 red PNG image.
+    fn png() -> Vec<u8> {
+        let image = image::RgbImage::from_pixel(4, 4, image::Rgb([255, 0, 0]));
+        let mut out = Cursor::new(Vec::new());
+        image.write_to(&mut out, image::ImageFormat::Png).unwrap();
+        out.into_inner()
+    }
+
+    /// A 4x4 red DXT1 compressed DDS image, a single block of 4x4 pixels.
+    fn dds() -> Vec<u8> {
+        let mut data = b"DDS ".to_vec();
+        let mut header = [0u32; 31];
+        header[0] = 124; // size
+        header[1] = 0x1 | 0x2 | 0x4 | 0x1000 | 0x80000; // caps, height, width, pixel format, linear size
+        header[2] = 4; // height
+        header[3] = 4; // width
+        header[4] = 8; // linear size
+        header[18] = 32; // pixel format size
+        header[19] = 0x4; // four cc
+        header[20] = u32::from_le_bytes(*b"DXT1");
+        header[26] = 0x1000; // texture
+        data.extend(header.iter().flat_map(|v| v.to_le_bytes()));
+        // Both endpoint colors are red and every pixel uses the first
+        data.extend(0xf800u16.to_le_bytes());
+        data.extend(0xf800u16.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data
+    }
+
+    fn pixel(data: &[u8], x: u32, y: u32) -> [u8; 3] {
+        image::load_from_memory(data)
+            .unwrap()
+            .to_rgb8()
+            .get_pixel(x, y)
+            .0
+    }
+
+    #[test]
+    fn it_converts_png_to_bmp_and_back() {
+        let bmp = convert_image(&png(), ImageFormat::Bmp).unwrap().unwrap();
+        assert_eq!(ImageFormat::detect(&bmp), Some(ImageFormat::Bmp));
+        assert_eq!(pixel(&bmp, 3, 3), [255, 0, 0]);
+
+        let png = convert_image(&bmp, ImageFormat::Png).unwrap().unwrap();
+        assert_eq!(ImageFormat::detect(&png), Some(ImageFormat::Png));
+        assert_eq!(pixel(&png, 3, 3), [255, 0, 0]);
+    }
+
+    #[test]
+    fn it_converts_dds_textures_to_bmp_on_the_way_back() {
+        let dds = dds();
+        assert_eq!(ImageFormat::detect(&dds), Some(ImageFormat::Dds));
+
+        let png = convert_image(&dds, ImageFormat::Png).unwrap().unwrap();
+        assert_eq!(pixel(&png, 0, 0), [255, 0, 0]);
+
+        // DDS can't be encoded so the texture comes back as BMP
+        let bmp = convert_image(&png, ImageFormat::Bmp).unwrap().unwrap();
+        assert_eq!(ImageFormat::detect(&bmp), Some(ImageFormat::Bmp));
+        assert_eq!(pixel(&bmp, 0, 0), [255, 0, 0]);
+    }
+
+    #[test]
+    fn it_leaves_data_in_the_target_format_unchanged() {
+        let png = png();
+        assert_eq!(convert_image(&png, ImageFormat::Png).unwrap(), Some(png));
+    }
+
+    #[test]
+    fn it_skips_data_that_is_not_an_image() {
+        assert_eq!(
+            convert_image(b"not an image", ImageFormat::Png).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn it_replaces_extensions() {
+        assert_eq!(with_extension("crate.bmp", ImageFormat::Png), "crate.png");
+        assert!(has_extension("CRATE.PNG", ImageFormat::Png));
+        assert!(!has_extension("crate.bmp", ImageFormat::Png));
+    }
+}
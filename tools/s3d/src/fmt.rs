@@ -1,6 +1,6 @@
 use jiff::Timestamp;
 use jiff::tz::TimeZone;
-use libeq_pfs::FileInfo;
+use libeq_pfs::{ContentType, FileInfo};
 
 pub(crate) fn format_ratio(info: &FileInfo) -> String {
     if info.uncompressed_size > 0 {
@@ -13,6 +13,16 @@ pub(crate) fn format_ratio(info: &FileInfo) -> String {
     }
 }
 
+/// Formats a detected content type, marking it with `*` if it doesn't
+/// match the extension of `filename`.
+pub(crate) fn format_content_type(content_type: ContentType, filename: &str) -> String {
+    if content_type == ContentType::Unknown || content_type.matches_extension(filename) {
+        content_type.to_string()
+    } else {
+        format!("{}*", content_type)
+    }
+}
+
 pub(crate) fn format_total_ratio(compressed: u64, uncompressed: u64) -> String {
     if uncompressed > 0 {
         format!("{:.1}%", compressed as f64 / uncompressed as f64 * 100.0)
@@ -58,6 +68,35 @@ pub(crate) fn format_number(n: u64, human: bool) -> String {
     result
 }
 
+/// Formats `data[start..end]` as a hex dump, 16 bytes per line, with
+/// offsets relative to the start of `data`.
+pub(crate) fn format_hex_dump(data: &[u8], start: usize, end: usize, indent: &str) -> String {
+    let end = end.min(data.len());
+    let start = start.min(end);
+    let mut out = String::new();
+    for (i, line) in data[start..end].chunks(16).enumerate() {
+        let hex: Vec<_> = line.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = line
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        out.push_str(&format!(
+            "{}{:08x}  {:<47}  |{}|\n",
+            indent,
+            start + i * 16,
+            hex.join(" "),
+            ascii
+        ));
+    }
+    out
+}
+
 const DAY_NAMES: [&str; 7] = [
     "Monday",
     "Tuesday",
@@ -181,3 +220,36 @@ pub(crate) fn format_timestamp(ts: u32, human: bool) -> String {
         format!("{} ({})", ts, formatted)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_formats_a_hex_dump() {
+        let data = b"Hello, world!\0\x01\x7fPFS";
+        assert_eq!(
+            format_hex_dump(data, 0, data.len(), "  "),
+            "  00000000  48 65 6c 6c 6f 2c 20 77 6f 72 6c 64 21 00 01 7f  |Hello, world!...|\n\
+             \x20 00000010  50 46 53                                         |PFS|\n"
+        );
+    }
+
+    #[test]
+    fn it_offsets_a_hex_dump_from_the_start_of_the_data() {
+        let data: Vec<u8> = (0..64).collect();
+        let dump = format_hex_dump(&data, 32, 36, "");
+        assert_eq!(dump, format!("00000020  20 21 22 23{:36}  | !\"#|\n", ""));
+    }
+
+    #[test]
+    fn it_clamps_a_hex_dump_to_the_data() {
+        let data = b"PFS ";
+        assert_eq!(
+            format_hex_dump(data, 0, 100, ""),
+            format_hex_dump(data, 0, data.len(), "")
+        );
+        assert_eq!(format_hex_dump(data, 10, 100, ""), "");
+        assert_eq!(format_hex_dump(data, 3, 1, ""), "");
+    }
+}
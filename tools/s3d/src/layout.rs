@@ -0,0 +1,563 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::Cursor;
+
+use libeq_pfs::PfsReader;
+
+/// Size of the archive header in bytes.
+pub(crate) const HEADER_SIZE: u64 = 12;
+/// Size of the header preceding each compressed block in bytes.
+pub(crate) const BLOCK_HEADER_SIZE: u64 = 8;
+/// Size of a single index entry in bytes.
+pub(crate) const INDEX_ENTRY_SIZE: u64 = 12;
+/// Size of the footer in bytes.
+pub(crate) const FOOTER_SIZE: u64 = 9;
+
+/// The structural element of an archive that a byte belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Location {
+    Header {
+        field: &'static str,
+    },
+    BlockHeader {
+        file: String,
+        block: usize,
+    },
+    BlockData {
+        file: String,
+        block: usize,
+    },
+    /// Data in the block section not referenced by any index entry.
+    Orphaned,
+    IndexCount,
+    IndexEntry {
+        entry: usize,
+        file: Option<String>,
+        field: &'static str,
+    },
+    Footer {
+        field: &'static str,
+    },
+    /// Data after the end of the index and footer.
+    Trailing,
+}
+
+impl Location {
+    /// The file (or `[directory]`) a location belongs to, if any.
+    pub(crate) fn file(&self) -> Option<&str> {
+        match self {
+            Location::BlockHeader { file, .. } | Location::BlockData { file, .. } => Some(file),
+            Location::IndexEntry { file, .. } => file.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Header { field } => write!(f, "header ({})", field),
+            Location::BlockHeader { file, block } => {
+                write!(f, "{} block {} header", file, block)
+            }
+            Location::BlockData { file, block } => {
+                write!(f, "{} block {} compressed data", file, block)
+            }
+            Location::Orphaned => write!(f, "orphaned data in block section"),
+            Location::IndexCount => write!(f, "index entry count"),
+            Location::IndexEntry { entry, file, field } => write!(
+                f,
+                "index entry {} ({}) for {}",
+                entry,
+                field,
+                file.as_deref().unwrap_or("unknown file")
+            ),
+            Location::Footer { field } => write!(f, "footer ({})", field),
+            Location::Trailing => write!(f, "trailing data"),
+        }
+    }
+}
+
+/// A contiguous byte range of an archive.
+#[derive(Debug)]
+pub(crate) struct Span {
+    pub start: u64,
+    pub end: u64,
+    pub location: Location,
+}
+
+/// The kind of data a section of an archive holds.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum SectionKind {
+    Header,
+    /// A file (or the directory) stored in the block section.
+    File {
+        name: String,
+        blocks: usize,
+        compressed_size: u64,
+        uncompressed_size: u64,
+    },
+    /// Data in the block section not referenced by any index entry.
+    Orphaned,
+    Index,
+    Footer,
+    /// Data after the end of the index and footer.
+    Trailing,
+}
+
+/// A contiguous byte range of an archive made up of consecutive spans.
+#[derive(Debug)]
+pub(crate) struct Section {
+    pub start: u64,
+    pub end: u64,
+    pub kind: SectionKind,
+}
+
+/// A byte-level map of the structure of an archive.
+///
+/// This is used to explain where two archives that should be identical
+/// differ from one another, and to show the layout of an archive.
+pub(crate) struct ArchiveMap {
+    /// Spans sorted by their start offset.
+    spans: Vec<Span>,
+    /// Uncompressed size of each file, by name.
+    uncompressed_sizes: HashMap<String, u64>,
+    /// Whether the index entries are stored sorted by filename CRC.
+    pub index_sorted: bool,
+    /// Whether the directory filenames are stored sorted by filename CRC.
+    pub directory_sorted: bool,
+    /// Total bytes in the block section not referenced by the index.
+    pub orphaned: u64,
+    /// Total bytes of files overlapping the data of a preceding file.
+    pub overlapping: u64,
+}
+
+impl ArchiveMap {
+    /// Map the structure of an archive held in memory.
+    pub(crate) fn new(data: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let mut reader = PfsReader::open(Cursor::new(data))?;
+        let archive_info = reader.archive_info()?;
+        let filenames = reader.filenames()?;
+
+        let mut files = Vec::with_capacity(filenames.len() + 1);
+        for name in &filenames {
+            let info = reader
+                .info(name)?
+                .ok_or_else(|| format!("{}: not found in index", name))?;
+            files.push((name.clone(), info));
+        }
+        files.push(("[directory]".to_string(), reader.directory_info()?));
+        files.sort_by_key(|(_, info)| info.data_offset);
+
+        let mut spans = vec![
+            field_span(
+                0,
+                4,
+                Location::Header {
+                    field: "index_offset",
+                },
+            ),
+            field_span(
+                4,
+                4,
+                Location::Header {
+                    field: "magic_number",
+                },
+            ),
+            field_span(8, 4, Location::Header { field: "version" }),
+        ];
+
+        // Block section
+        let index_offset = archive_info.index_offset as u64;
+        let mut cursor = HEADER_SIZE;
+        let mut orphaned = 0;
+        let mut overlapping = 0;
+        for (name, info) in &files {
+            let mut pos = info.data_offset as u64;
+            if pos < cursor {
+                // Overlapping entries, only the first is mapped.
+                let end =
+                    pos + info.compressed_size as u64 + info.block_count as u64 * BLOCK_HEADER_SIZE;
+                overlapping += cursor.min(end) - pos;
+                continue;
+            }
+            if pos > cursor {
+                spans.push(Span {
+                    start: cursor,
+                    end: pos,
+                    location: Location::Orphaned,
+                });
+                orphaned += pos - cursor;
+            }
+            for block in 0..info.block_count as usize {
+                let compressed_size = read_u32(data, pos)
+                    .ok_or_else(|| format!("{}: block {} is out of bounds", name, block))?
+                    as u64;
+                spans.push(field_span(
+                    pos,
+                    BLOCK_HEADER_SIZE,
+                    Location::BlockHeader {
+                        file: name.clone(),
+                        block,
+                    },
+                ));
+                spans.push(field_span(
+                    pos + BLOCK_HEADER_SIZE,
+                    compressed_size,
+                    Location::BlockData {
+                        file: name.clone(),
+                        block,
+                    },
+                ));
+                pos += BLOCK_HEADER_SIZE + compressed_size;
+            }
+            cursor = pos;
+        }
+        if index_offset > cursor {
+            spans.push(Span {
+                start: cursor,
+                end: index_offset,
+                location: Location::Orphaned,
+            });
+            orphaned += index_offset - cursor;
+        }
+
+        // Index section
+        let names_by_offset: HashMap<u32, &str> = files
+            .iter()
+            .map(|(name, info)| (info.data_offset, name.as_str()))
+            .collect();
+        let entry_count = read_u32(data, index_offset).ok_or("index is out of bounds")? as u64;
+        spans.push(field_span(index_offset, 4, Location::IndexCount));
+        let mut crcs = Vec::with_capacity(entry_count as usize);
+        for entry in 0..entry_count {
+            let pos = index_offset + 4 + entry * INDEX_ENTRY_SIZE;
+            let (Some(crc), Some(data_offset)) = (read_u32(data, pos), read_u32(data, pos + 4))
+            else {
+                return Err(format!("index entry {} is out of bounds", entry).into());
+            };
+            crcs.push((crc, data_offset));
+            let file = names_by_offset.get(&data_offset).map(|n| n.to_string());
+            let entry = entry as usize;
+            for (offset, field) in [
+                (0, "filename_crc"),
+                (4, "data_offset"),
+                (8, "uncompressed_size"),
+            ] {
+                spans.push(field_span(
+                    pos + offset,
+                    4,
+                    Location::IndexEntry {
+                        entry,
+                        file: file.clone(),
+                        field,
+                    },
+                ));
+            }
+        }
+        let index_sorted = crcs.is_sorted_by_key(|(crc, _)| *crc);
+
+        // Directory filenames are matched to their CRCs through the
+        // data offsets shared by the directory and the index.
+        let offsets_by_name: HashMap<&str, u32> = files
+            .iter()
+            .map(|(name, info)| (name.as_str(), info.data_offset))
+            .collect();
+        let crcs_by_offset: HashMap<u32, u32> =
+            crcs.iter().map(|(crc, offset)| (*offset, *crc)).collect();
+        let directory_crcs: Vec<_> = filenames
+            .iter()
+            .filter_map(|name| crcs_by_offset.get(offsets_by_name.get(name.as_str())?))
+            .collect();
+        let directory_sorted = directory_crcs.is_sorted();
+
+        // Footer
+        let mut end = index_offset + 4 + entry_count * INDEX_ENTRY_SIZE;
+        if archive_info.footer_string.is_some() {
+            spans.push(field_span(
+                end,
+                5,
+                Location::Footer {
+                    field: "footer_string",
+                },
+            ));
+            spans.push(field_span(
+                end + 5,
+                4,
+                Location::Footer { field: "timestamp" },
+            ));
+            end += FOOTER_SIZE;
+        }
+        if (data.len() as u64) > end {
+            spans.push(Span {
+                start: end,
+                end: data.len() as u64,
+                location: Location::Trailing,
+            });
+        }
+
+        spans.sort_by_key(|s| s.start);
+
+        let uncompressed_sizes = files
+            .into_iter()
+            .map(|(name, info)| (name, info.uncompressed_size as u64))
+            .collect();
+
+        Ok(Self {
+            spans,
+            uncompressed_sizes,
+            index_sorted,
+            directory_sorted,
+            orphaned,
+            overlapping,
+        })
+    }
+
+    /// Find the span containing the byte at `offset`.
+    pub(crate) fn locate(&self, offset: u64) -> Option<&Span> {
+        let idx = self.spans.partition_point(|s| s.start <= offset);
+        self.spans[..idx].last().filter(|s| offset < s.end)
+    }
+
+    /// The sections of the archive in offset order.
+    pub(crate) fn sections(&self) -> Vec<Section> {
+        let mut sections: Vec<Section> = Vec::new();
+        for span in &self.spans {
+            let kind = match &span.location {
+                Location::Header { .. } => SectionKind::Header,
+                Location::BlockHeader { file, .. } | Location::BlockData { file, .. } => {
+                    SectionKind::File {
+                        name: file.clone(),
+                        blocks: 0,
+                        compressed_size: 0,
+                        uncompressed_size: self.uncompressed_sizes[file],
+                    }
+                }
+                Location::Orphaned => SectionKind::Orphaned,
+                Location::IndexCount | Location::IndexEntry { .. } => SectionKind::Index,
+                Location::Footer { .. } => SectionKind::Footer,
+                Location::Trailing => SectionKind::Trailing,
+            };
+            let extends_last = sections.last().is_some_and(|last| {
+                last.end == span.start
+                    && match (&last.kind, &kind) {
+                        (SectionKind::File { name: a, .. }, SectionKind::File { name: b, .. }) => {
+                            a == b
+                        }
+                        (a, b) => a == b,
+                    }
+            });
+            if !extends_last {
+                sections.push(Section {
+                    start: span.start,
+                    end: span.start,
+                    kind,
+                });
+            }
+            let section = sections.last_mut().unwrap();
+            section.end = span.end;
+            if let SectionKind::File {
+                blocks,
+                compressed_size,
+                ..
+            } = &mut section.kind
+            {
+                match span.location {
+                    Location::BlockHeader { .. } => *blocks += 1,
+                    _ => *compressed_size += span.end - span.start,
+                }
+            }
+        }
+        sections
+    }
+}
+
+fn field_span(start: u64, size: u64, location: Location) -> Span {
+    Span {
+        start,
+        end: start + size,
+        location,
+    }
+}
+
+fn read_u32(data: &[u8], offset: u64) -> Option<u32> {
+    let offset = usize::try_from(offset).ok()?;
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use libeq_pfs::PfsWriter;
+
+    /// Build an archive holding `files`, removing `removed` from the
+    /// directory after they have been written so their data is orphaned.
+    pub(crate) fn archive(files: &[&str], removed: &[&str]) -> Vec<u8> {
+        let mut writer = PfsWriter::create(Cursor::new(Vec::new())).unwrap();
+        for name in files {
+            writer
+                .insert(*name, Cursor::new(format!("contents of {}", name)))
+                .unwrap();
+        }
+        for name in removed {
+            writer.remove(name);
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn location(map: &ArchiveMap, offset: u64) -> Option<&Location> {
+        map.locate(offset).map(|s| &s.location)
+    }
+
+    #[test]
+    fn it_maps_the_header_and_block_section() {
+        let data = archive(&["a.txt", "b.txt"], &[]);
+        let map = ArchiveMap::new(&data).unwrap();
+
+        assert_eq!(
+            location(&map, 0),
+            Some(&Location::Header {
+                field: "index_offset"
+            })
+        );
+        assert_eq!(
+            location(&map, 7),
+            Some(&Location::Header {
+                field: "magic_number"
+            })
+        );
+        assert_eq!(
+            location(&map, 8),
+            Some(&Location::Header { field: "version" })
+        );
+        assert_eq!(
+            location(&map, HEADER_SIZE),
+            Some(&Location::BlockHeader {
+                file: "a.txt".to_string(),
+                block: 0
+            })
+        );
+        assert_eq!(
+            location(&map, HEADER_SIZE + BLOCK_HEADER_SIZE),
+            Some(&Location::BlockData {
+                file: "a.txt".to_string(),
+                block: 0
+            })
+        );
+        assert_eq!(map.orphaned, 0);
+    }
+
+    #[test]
+    fn it_maps_the_index_and_footer() {
+        let data = archive(&["a.txt", "b.txt"], &[]);
+        let map = ArchiveMap::new(&data).unwrap();
+        let index_offset = read_u32(&data, 0).unwrap() as u64;
+        let len = data.len() as u64;
+
+        assert_eq!(location(&map, index_offset), Some(&Location::IndexCount));
+        assert!(matches!(
+            location(&map, index_offset + 4 + INDEX_ENTRY_SIZE + 4),
+            Some(Location::IndexEntry {
+                entry: 1,
+                file: Some(_),
+                field: "data_offset"
+            })
+        ));
+        assert_eq!(
+            location(&map, len - FOOTER_SIZE),
+            Some(&Location::Footer {
+                field: "footer_string"
+            })
+        );
+        assert_eq!(
+            location(&map, len - 1),
+            Some(&Location::Footer { field: "timestamp" })
+        );
+        assert_eq!(location(&map, len), None);
+        assert!(map.index_sorted);
+        assert!(map.directory_sorted);
+    }
+
+    #[test]
+    fn it_maps_orphaned_data() {
+        let data = archive(&["a.txt", "b.txt"], &["a.txt"]);
+        let map = ArchiveMap::new(&data).unwrap();
+
+        assert_eq!(location(&map, HEADER_SIZE), Some(&Location::Orphaned));
+        assert!(map.orphaned > 0);
+        assert_eq!(
+            location(&map, HEADER_SIZE + map.orphaned),
+            Some(&Location::BlockHeader {
+                file: "b.txt".to_string(),
+                block: 0
+            })
+        );
+    }
+
+    #[test]
+    fn it_maps_trailing_data() {
+        let mut data = archive(&["a.txt"], &[]);
+        let len = data.len() as u64;
+        data.extend_from_slice(b"trailing");
+        let map = ArchiveMap::new(&data).unwrap();
+
+        assert_eq!(location(&map, len), Some(&Location::Trailing));
+        assert_eq!(location(&map, len + 7), Some(&Location::Trailing));
+        assert_eq!(location(&map, len + 8), None);
+    }
+
+    #[test]
+    fn it_groups_spans_into_sections() {
+        let mut data = archive(&["a.txt", "b.txt"], &["a.txt"]);
+        data.extend_from_slice(b"trailing");
+        let map = ArchiveMap::new(&data).unwrap();
+        let sections = map.sections();
+
+        let kinds: Vec<_> = sections
+            .iter()
+            .map(|s| match &s.kind {
+                SectionKind::File { name, .. } => name.as_str(),
+                SectionKind::Header => "[header]",
+                SectionKind::Orphaned => "[orphaned]",
+                SectionKind::Index => "[index]",
+                SectionKind::Footer => "[footer]",
+                SectionKind::Trailing => "[trailing]",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "[header]",
+                "[orphaned]",
+                "b.txt",
+                "[directory]",
+                "[index]",
+                "[footer]",
+                "[trailing]"
+            ]
+        );
+        assert!(sections.windows(2).all(|w| w[0].end == w[1].start));
+        assert_eq!(sections.last().unwrap().end, data.len() as u64);
+        assert_eq!(sections[1].end - sections[1].start, map.orphaned);
+        assert!(matches!(
+            sections[2].kind,
+            SectionKind::File {
+                blocks: 1,
+                uncompressed_size: 17,
+                ..
+            }
+        ));
+        assert_eq!(map.overlapping, 0);
+    }
+
+    #[test]
+    fn it_rejects_an_index_out_of_bounds() {
+        let mut data = archive(&["a.txt"], &[]);
+        let index_offset = read_u32(&data, 0).unwrap() as usize;
+        data.truncate(index_offset + 2);
+
+        assert!(ArchiveMap::new(&data).is_err());
+    }
+}
@@ -2,10 +2,13 @@ use std::error::Error;
 use std::fs::File;
 use std::process;
 
+use convert::ImageFormat;
 use lexopt::prelude::*;
 use libeq_pfs::PfsReader;
 mod cmd;
+mod convert;
 mod fmt;
+mod layout;
 
 enum Command {
     List {
@@ -16,16 +19,19 @@ enum Command {
     Verify {
         files: Vec<String>,
         verbose: bool,
+        hex: bool,
     },
     Extract {
         archive: String,
         files: Vec<String>,
         output: Option<String>,
+        convert: Option<ImageFormat>,
         verbose: bool,
     },
     Create {
         archive: String,
         inputs: Vec<String>,
+        convert: Option<ImageFormat>,
         verbose: bool,
         force: bool,
     },
@@ -33,8 +39,15 @@ enum Command {
         archive: String,
         filename: String,
     },
+    Grep {
+        pattern: Vec<u8>,
+        files: Vec<String>,
+        ignore_case: bool,
+        files_with_matches: bool,
+    },
     Info {
         files: Vec<String>,
+        layout: bool,
         raw: bool,
     },
 }
@@ -49,7 +62,8 @@ Commands:
   verify  (v)    Verify archive integrity
   extract (x)    Extract files from archive
   create  (c)    Create archive from files
-  get            Extract single file to stdout
+  get     (cat)  Extract single file to stdout
+  grep           Search file contents in archives
   info    (i)    Display archive metadata
 
 Run 's3d <command> --help' for more information.";
@@ -69,6 +83,12 @@ pub(crate) fn open_archive(path: &str) -> Result<(PfsReader<File>, Vec<String>),
     Ok((reader, filenames))
 }
 
+fn parse_convert(name: &str, supported: &[ImageFormat]) -> Result<ImageFormat, lexopt::Error> {
+    ImageFormat::from_name(name)
+        .filter(|f| supported.contains(f))
+        .ok_or_else(|| format!("unsupported conversion format: {}", name).into())
+}
+
 fn parse_args() -> Result<Command, lexopt::Error> {
     let mut parser = lexopt::Parser::from_env();
 
@@ -119,11 +139,15 @@ fn parse_args() -> Result<Command, lexopt::Error> {
         "verify" | "v" => {
             let mut files = Vec::new();
             let mut verbose = false;
+            let mut hex = false;
             while let Some(arg) = parser.next()? {
                 match arg {
                     Short('v') | Long("verbose") => {
                         verbose = true;
                     }
+                    Short('x') | Long("hex") => {
+                        hex = true;
+                    }
                     Short('h') | Long("help") => {
                         cmd::verify::print_help();
                         process::exit(0);
@@ -136,12 +160,17 @@ fn parse_args() -> Result<Command, lexopt::Error> {
                 cmd::verify::eprint_help();
                 process::exit(1);
             }
-            Ok(Command::Verify { files, verbose })
+            Ok(Command::Verify {
+                files,
+                verbose,
+                hex,
+            })
         }
         "extract" | "x" => {
             let mut archive = None;
             let mut files = Vec::new();
             let mut output = None;
+            let mut convert = None;
             let mut verbose = false;
             while let Some(arg) = parser.next()? {
                 match arg {
@@ -151,6 +180,12 @@ fn parse_args() -> Result<Command, lexopt::Error> {
                     Short('o') | Long("output") => {
                         output = Some(parser.value()?.string()?);
                     }
+                    Short('c') | Long("convert") => {
+                        convert = Some(parse_convert(
+                            &parser.value()?.string()?,
+                            &[ImageFormat::Png],
+                        )?);
+                    }
                     Short('h') | Long("help") => {
                         cmd::extract::print_help();
                         process::exit(0);
@@ -174,16 +209,24 @@ fn parse_args() -> Result<Command, lexopt::Error> {
                 archive,
                 files,
                 output,
+                convert,
                 verbose,
             })
         }
         "create" | "c" => {
             let mut archive = None;
             let mut inputs = Vec::new();
+            let mut convert = None;
             let mut verbose = false;
             let mut force = false;
             while let Some(arg) = parser.next()? {
                 match arg {
+                    Short('c') | Long("convert") => {
+                        convert = Some(parse_convert(
+                            &parser.value()?.string()?,
+                            &[ImageFormat::Bmp],
+                        )?);
+                    }
                     Short('v') | Long("verbose") => {
                         verbose = true;
                     }
@@ -213,11 +256,12 @@ fn parse_args() -> Result<Command, lexopt::Error> {
             Ok(Command::Create {
                 archive,
                 inputs,
+                convert,
                 verbose,
                 force,
             })
         }
-        "get" => {
+        "get" | "cat" => {
             let mut archive = None;
             let mut filename = None;
             while let Some(arg) = parser.next()? {
@@ -247,11 +291,70 @@ fn parse_args() -> Result<Command, lexopt::Error> {
             let filename = filename.unwrap();
             Ok(Command::Get { archive, filename })
         }
+        "grep" => {
+            let mut pattern = None;
+            let mut files = Vec::new();
+            let mut hex = false;
+            let mut ignore_case = false;
+            let mut files_with_matches = false;
+            while let Some(arg) = parser.next()? {
+                match arg {
+                    Short('x') | Long("hex") => {
+                        hex = true;
+                    }
+                    Short('i') | Long("ignore-case") => {
+                        ignore_case = true;
+                    }
+                    Short('l') | Long("files-with-matches") => {
+                        files_with_matches = true;
+                    }
+                    Short('h') | Long("help") => {
+                        cmd::grep::print_help();
+                        process::exit(0);
+                    }
+                    Value(val) => {
+                        let s = val.string()?;
+                        if pattern.is_none() {
+                            pattern = Some(s);
+                        } else {
+                            files.push(s);
+                        }
+                    }
+                    other => return Err(other.unexpected()),
+                }
+            }
+            let Some(pattern) = pattern else {
+                cmd::grep::eprint_help();
+                process::exit(1);
+            };
+            if files.is_empty() {
+                cmd::grep::eprint_help();
+                process::exit(1);
+            }
+            let pattern = if hex {
+                cmd::grep::parse_hex(&pattern)?
+            } else {
+                pattern.into_bytes()
+            };
+            if pattern.is_empty() {
+                return Err("pattern must not be empty".into());
+            }
+            Ok(Command::Grep {
+                pattern,
+                files,
+                ignore_case,
+                files_with_matches,
+            })
+        }
         "info" | "i" => {
             let mut files = Vec::new();
+            let mut layout = false;
             let mut raw = false;
             while let Some(arg) = parser.next()? {
                 match arg {
+                    Short('l') | Long("layout") => {
+                        layout = true;
+                    }
                     Short('r') | Long("raw") => {
                         raw = true;
                     }
@@ -267,7 +370,7 @@ fn parse_args() -> Result<Command, lexopt::Error> {
                 cmd::info::eprint_help();
                 process::exit(1);
             }
-            Ok(Command::Info { files, raw })
+            Ok(Command::Info { files, layout, raw })
         }
         "help" => {
             // s3d help <subcommand>
@@ -279,7 +382,8 @@ fn parse_args() -> Result<Command, lexopt::Error> {
                         "verify" | "v" => cmd::verify::print_help(),
                         "extract" | "x" => cmd::extract::print_help(),
                         "create" | "c" => cmd::create::print_help(),
-                        "get" => cmd::get::print_help(),
+                        "get" | "cat" => cmd::get::print_help(),
+                        "grep" => cmd::grep::print_help(),
                         "info" | "i" => cmd::info::print_help(),
                         _ => {
                             eprintln!("unknown subcommand: {}", sub);
@@ -324,8 +428,12 @@ fn main() {
                 process::exit(1);
             }
         }
-        Command::Verify { ref files, verbose } => {
-            if let Err(e) = cmd::verify::run(files, verbose) {
+        Command::Verify {
+            ref files,
+            verbose,
+            hex,
+        } => {
+            if let Err(e) = cmd::verify::run(files, verbose, hex) {
                 eprintln!("{}", e);
                 process::exit(1);
             }
@@ -334,9 +442,10 @@ fn main() {
             ref archive,
             ref files,
             ref output,
+            convert,
             verbose,
         } => {
-            if let Err(e) = cmd::extract::run(archive, files, output.as_deref(), verbose) {
+            if let Err(e) = cmd::extract::run(archive, files, output.as_deref(), convert, verbose) {
                 eprintln!("{}", e);
                 process::exit(1);
             }
@@ -344,10 +453,11 @@ fn main() {
         Command::Create {
             ref archive,
             ref inputs,
+            convert,
             verbose,
             force,
         } => {
-            if let Err(e) = cmd::create::run(archive, inputs, verbose, force) {
+            if let Err(e) = cmd::create::run(archive, inputs, convert, verbose, force) {
                 eprintln!("{}", e);
                 process::exit(1);
             }
@@ -361,8 +471,23 @@ fn main() {
                 process::exit(1);
             }
         }
-        Command::Info { ref files, raw } => {
-            if let Err(e) = cmd::info::run(files, !raw) {
+        Command::Grep {
+            ref pattern,
+            ref files,
+            ignore_case,
+            files_with_matches,
+        } => {
+            if let Err(e) = cmd::grep::run(pattern, files, ignore_case, files_with_matches) {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        Command::Info {
+            ref files,
+            layout,
+            raw,
+        } => {
+            if let Err(e) = cmd::info::run(files, layout, !raw) {
                 eprintln!("{}", e);
                 process::exit(1);
             }
@@ -48,6 +48,7 @@ fn cli() -> Command {
             Command::new("create")
                 .about("Create a wld file from a source directory of fragments")
                 .arg(arg!(-f --format <FORMAT> "Format to extract to").value_parser(value_parser!(Format)).default_value("raw"))
+                .arg(arg!(--normalize "Recompute counts, flags and the header from the fragment data, for sources that have been edited"))
                 .arg(arg!(<SOURCE> "The source directory containing a header, strings, and fragment files"))
                 .arg(arg!(<WLD_FILE> "The destination .wld file")),
         )
@@ -94,8 +95,9 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .map(String::as_str)
                 .expect("required");
             let format = sub_matches.get_one::<Format>("format").expect("required");
+            let normalize = sub_matches.get_flag("normalize");
             println!("CREATE: {:?} -> {:?}", source, wld_file);
-            create(source, wld_file, format);
+            create(source, wld_file, format, normalize);
         }
         Some(("stats", sub_matches)) => {
             let wld_file = sub_matches
@@ -111,17 +113,21 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn print_error(error: &WldDocError) -> Result<(), std::io::Error> {
+fn print_error(error: &WldDocError, wld_data: &[u8]) -> Result<(), std::io::Error> {
     let mut out = io::stdout();
     match error {
         WldDocError::Parse { message, .. } => {
             write!(out, "{}", message)?;
         }
+        WldDocError::ParseHeader { offset, message } => {
+            write!(out, "{} (offset 0x{:02x})", message, offset)?;
+        }
         WldDocError::ParseFragment {
             index,
+            fragment_type,
             offset,
-            header,
             message,
+            ..
         } => {
             write!(out, "\n{}\n", "Failed Fragment".red())?;
             write!(out, "{}", message.clone().with(Color::AnsiValue(217)))?;
@@ -129,7 +135,7 @@ fn print_error(error: &WldDocError) -> Result<(), std::io::Error> {
                 out,
                 "\n{} 0x{:02x}, {} {}\n",
                 "type:".with(Color::AnsiValue(245)),
-                header.fragment_type,
+                fragment_type,
                 "index:".with(Color::AnsiValue(245)),
                 index
             )?;
@@ -142,34 +148,49 @@ fn print_error(error: &WldDocError) -> Result<(), std::io::Error> {
             )?;
             writeln!(out, "Dumping fragment body...")?;
             let mut hex_printer = PrinterBuilder::new(&mut out).build();
-            hex_printer.print_all(header.field_data).unwrap();
+            hex_printer
+                .print_all(fragment_body(wld_data, *index))
+                .unwrap();
         }
-        WldDocError::UnknownFragment { index, header } => {
+        WldDocError::UnknownFragment {
+            index,
+            fragment_type,
+            ..
+        } => {
+            let body = fragment_body(wld_data, *index);
             write!(out, "\n{}\n", "Unknown Fragment".yellow())?;
             writeln!(
                 out,
                 "{} 0x{:02x}, {} {} {} {}",
                 "type:".with(Color::AnsiValue(245)),
-                header.fragment_type,
+                fragment_type,
                 "index:".with(Color::AnsiValue(245)),
                 index,
                 "size:".with(Color::AnsiValue(245)),
-                header.size
+                body.len()
             )?;
             writeln!(out, "Dumping fragment body...")?;
             let mut hex_printer = PrinterBuilder::new(&mut out).build();
-            hex_printer.print_all(header.field_data).unwrap();
+            hex_printer.print_all(body).unwrap();
         }
     }
     Ok(())
 }
 
+/// The body of the fragment at `index`, or nothing if the fragment headers can't be read.
+fn fragment_body(wld_data: &[u8], index: usize) -> &[u8] {
+    WldDoc::dump_raw_fragments(wld_data)
+        .ok()
+        .and_then(|(_, headers)| headers.get(index).map(|h| h.field_data))
+        .unwrap_or_default()
+}
+
 fn explore(wld_filename: &str) -> Result<(), Box<dyn Error>> {
     let wld_data = read_wld_file(wld_filename).expect("Could not read wld file");
     let wld_doc = parser::WldDoc::parse(&wld_data)
         .map_err(|e| {
             for error in e.iter() {
-                print_error(error).unwrap();
+                print_error(error, &wld_data).unwrap();
             }
         })
         .expect("Could not read wld file");
@@ -209,7 +230,7 @@ fn extract(wld_filename: &str, destination: &str, format: &Format) {
     let wld_doc = parser::WldDoc::parse(&wld_data)
         .map_err(|e| {
             for error in e.iter() {
-                print_error(error).unwrap();
+                print_error(error, &wld_data).unwrap();
             }
         })
         .expect("Could not read wld file");
@@ -242,7 +263,7 @@ fn extract_raw(wld_filename: &str, destination: &str) {
     let wld = parser::WldDoc::parse(&wld_data)
         .map_err(|e| {
             for error in e.iter() {
-                print_error(error).unwrap();
+                print_error(error, &wld_data).unwrap();
             }
         })
         .expect("Could not read wld file");
@@ -264,10 +285,10 @@ fn extract_raw(wld_filename: &str, destination: &str) {
     }
 }
 
-fn create(source: &str, wld_filename: &str, format: &Format) {
+fn create(source: &str, wld_filename: &str, format: &Format, normalize: bool) {
     let mut reader =
         File::open(source).unwrap_or_else(|_| panic!("Could not open source file: {}", source));
-    let wld_doc: WldDoc = match format {
+    let mut wld_doc: WldDoc = match format {
         Format::Raw => {
             let mut buff = vec![];
             reader
@@ -276,7 +297,7 @@ fn create(source: &str, wld_filename: &str, format: &Format) {
             parser::WldDoc::parse(&buff)
                 .map_err(|e| {
                     for error in e.iter() {
-                        print_error(error).unwrap();
+                        print_error(error, &buff).unwrap();
                     }
                 })
                 .expect("Could not read wld file");
@@ -285,6 +306,9 @@ fn create(source: &str, wld_filename: &str, format: &Format) {
         Format::Json => serde_json::from_reader(reader).expect("Could not deserialize from json"),
         Format::Ron => ron::de::from_reader(reader).expect("Could not deserialize from ron"),
     };
+    if normalize {
+        wld_doc.normalize();
+    }
     let mut out = File::create(wld_filename).expect("Could not create wld file");
     out.write_all(&wld_doc.to_bytes())
         .expect("Failed to write to wld file");
@@ -292,12 +316,25 @@ fn create(source: &str, wld_filename: &str, format: &Format) {
 
 fn stats(wld_filename: &str) -> Result<(), Box<dyn Error>> {
     let file = read_wld_file(wld_filename)?;
-    let fragment_headers = parser::WldDoc::fragment_headers_by_offset(&file);
-    println!("Index, Offset, Type, Size");
-    for (idx, (k, v)) in fragment_headers.iter().enumerate() {
+    let fragment_table = match WldDoc::fragment_table(&file) {
+        Ok(table) => table,
+        Err(errors) => {
+            for error in errors.iter() {
+                print_error(error, &file)?;
+            }
+            return Err("Could not read wld file".into());
+        }
+    };
+    println!("Index, Offset, Type, Type Name, Size, Name");
+    for entry in fragment_table {
         println!(
-            "{}, {:#010x}, {:#04x}, {:#010x}",
-            idx, k, v.fragment_type, v.size
+            "{}, {:#010x}, {:#04x}, {}, {:#010x}, {}",
+            entry.index,
+            entry.offset,
+            entry.type_id,
+            entry.type_name.unwrap_or_default(),
+            entry.size,
+            entry.name.unwrap_or_default()
         );
     }
     Ok(())
@@ -114,5 +114,6 @@ pub fn get_frag_name_and_color(fragment_type: &FragmentType) -> (&'static str, C
         FragmentType::DirectionalLight(_) => {
             (DirectionalLight::TYPE_NAME, Color::Rgb(0x80, 0x80, 0x20))
         }
+        FragmentType::Raw(_) => ("Raw", Color::Rgb(0x80, 0x80, 0x80)),
     }
 }